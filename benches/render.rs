@@ -0,0 +1,59 @@
+//! The cost of one tick of the game (without any window) depending on the size
+//! of the map, when the map has not changed since the previous tick and when
+//! it has to be drawn again (which is what happened on every tick before the
+//! map layer was kept from one tick to the next).
+
+use std::sync::mpsc::channel;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pas_cman_ipl::{frame::Frame, pascman_protocol::{Item, Message, Position}, State};
+
+/// The sizes of the maps (in tiles): the default one and much larger ones
+const SIZES: [(usize, usize); 3] = [(30, 20), (120, 80), (256, 256)];
+
+/// Creates a running game on a maze of the given size: the map is surrounded
+/// by walls and every other tile inside of it is a wall too
+fn game(width: usize, height: usize) -> State {
+    let (sx, rx) = channel();
+    let mut state = State::new(rx);
+    state.resize(width, height);
+
+    let mut id = 0;
+    for y in 0..height as u32 {
+        for x in 0..width as u32 {
+            let border = x == 0 || y == 0 || x + 1 == width as u32 || y + 1 == height as u32;
+            let item   = if border || (x % 2 == 0 && y % 2 == 0) { Item::WALL } else { Item::FLOOR };
+            sx.send(Message::spawn(id, item, Position { x, y })).unwrap();
+            id += 1;
+        }
+    }
+    sx.send(Message::registration(1)).unwrap();
+    state.update(None, 16.0);
+    state
+}
+
+fn tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+    for (width, height) in SIZES {
+        let size = format!("{width}x{height}");
+
+        let mut state = game(width, height);
+        group.bench_function(BenchmarkId::new("unchanged map", &size), |b| {
+            b.iter(|| state.update(None, 16.0))
+        });
+
+        let mut state = game(width, height);
+        group.bench_function(BenchmarkId::new("redrawn map", &size), |b| {
+            b.iter(|| {
+                if let Some(mut frame) = state.resources.get_mut::<Frame>() {
+                    frame.invalidate_map();
+                }
+                state.update(None, 16.0)
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tick);
+criterion_main!(benches);
@@ -0,0 +1,243 @@
+//! This module generates the C header (`pascman.h`) describing the protocol.
+//! The constants, enums, structs and unions which have a C equivalent are
+//! declared once, with the `protocol!` macro: it produces both the Rust
+//! definitions and their declarations in the header (with the same docs). A
+//! field whose type has no C equivalent does not compile, and the layout of
+//! every type is checked against the one of the C compiler by the tests of
+//! this crate (see `tests/header.rs`).
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use alloc::{format, string::String};
+
+use crate::DECLARATIONS;
+
+/// The comment at the top of the generated header
+const PREAMBLE: &str = "\
+#ifndef __PASCMAN__
+#define __PASCMAN__
+//! Ce header comprend les définitions de types dont vous aurez besoin pour
+//! interagir avec l'interface graphique du jeu que vous allez développer
+//!
+//! Ce fichier est généré à partir de la crate `pascman-protocol` à l'aide de la
+//! commande `pas-cman-ipl emit-header`: ne le modifiez pas à la main.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+#include <stdbool.h>
+#include <stdint.h>
+";
+
+/// The end of the generated header
+const POSTAMBLE: &str = "#endif //__PASCMAN__\n";
+
+/// A Rust type which has an equivalent in C
+pub(crate) trait CType {
+    /// The name of the C type (e.g. `enum Item`)
+    const C_TYPE: &'static str;
+}
+impl CType for u32 {
+    const C_TYPE: &'static str = "uint32_t";
+}
+
+/// A field of a struct or union
+pub(crate) struct Field {
+    pub docs: &'static [&'static str],
+    pub name: &'static str,
+    /// The name of the C type of the field
+    pub ty: &'static str,
+}
+
+/// A variant of an enum along with its discriminant
+pub(crate) struct Variant {
+    pub docs: &'static [&'static str],
+    pub name: &'static str,
+    pub value: u32,
+}
+
+/// Something which is declared in the header
+pub(crate) enum Declaration {
+    Define { docs: &'static [&'static str], name: &'static str, value: &'static str },
+    Enum { docs: &'static [&'static str], name: &'static str, variants: &'static [Variant] },
+    /// A struct or a union
+    Compound { kind: &'static str, docs: &'static [&'static str], name: &'static str, fields: &'static [Field] },
+}
+
+/// Declares the constants and types of the protocol along with their
+/// declarations in the C header (`DECLARATIONS`, in the order of the header).
+/// The doc comments are those of the Rust items.
+macro_rules! protocol {
+    (@declare [$($declaration:expr,)*]) => {
+        /// All the declarations of the C header, in order
+        pub(crate) const DECLARATIONS: &[$crate::header::Declaration] = &[$($declaration,)*];
+    };
+    (@declare [$($declaration:expr,)*]
+        $(#[doc = $doc:literal])*
+        pub const $name:ident: $ty:ty = $value:literal;
+        $($rest:tt)*
+    ) => {
+        $(#[doc = $doc])*
+        pub const $name: $ty = $value;
+
+        $crate::header::protocol!(@declare [$($declaration,)*
+            $crate::header::Declaration::Define { docs: &[$($doc),*], name: stringify!($name), value: stringify!($value) },
+        ] $($rest)*);
+    };
+    (@declare [$($declaration:expr,)*]
+        $(#[doc = $doc:literal])*
+        pub const $name:ident: $ty:ty = $value:expr;
+        $($rest:tt)*
+    ) => {
+        $(#[doc = $doc])*
+        pub const $name: $ty = $value;
+
+        $crate::header::protocol!(@declare [$($declaration,)*
+            $crate::header::Declaration::Define {
+                docs: &[$($doc),*],
+                name: stringify!($name),
+                value: concat!("(", stringify!($value), ")"),
+            },
+        ] $($rest)*);
+    };
+    (@declare [$($declaration:expr,)*]
+        $(#[doc = $doc:literal])*
+        pub enum $name:ident {
+            $($(#[doc = $variant_doc:literal])* $variant:ident = $value:literal,)*
+        }
+        $($rest:tt)*
+    ) => {
+        $(#[doc = $doc])*
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #[allow(non_camel_case_types)]
+        pub enum $name {
+            $($(#[doc = $variant_doc])* $variant = $value,)*
+        }
+        impl $crate::header::CType for $name {
+            const C_TYPE: &'static str = concat!("enum ", stringify!($name));
+        }
+
+        $crate::header::protocol!(@declare [$($declaration,)*
+            $crate::header::Declaration::Enum {
+                docs: &[$($doc),*],
+                name: stringify!($name),
+                variants: &[$($crate::header::Variant {
+                    docs: &[$($variant_doc),*],
+                    name: stringify!($variant),
+                    value: $name::$variant as u32,
+                },)*],
+            },
+        ] $($rest)*);
+    };
+    (@declare [$($declaration:expr,)*]
+        $(#[doc = $doc:literal])*
+        pub struct $name:ident {
+            $($(#[doc = $field_doc:literal])* pub $field:ident: $ty:ty),* $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        $(#[doc = $doc])*
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct $name {
+            $($(#[doc = $field_doc])* pub $field: $ty,)*
+        }
+
+        impl $crate::header::CType for $name {
+            const C_TYPE: &'static str = concat!("struct ", stringify!($name));
+        }
+
+        $crate::header::protocol!(@declare [$($declaration,)*
+            $crate::header::protocol!(@compound "struct" $name [$($doc),*] [$([$($field_doc),*] $field: $ty),*]),
+        ] $($rest)*);
+    };
+    (@declare [$($declaration:expr,)*]
+        $(#[doc = $doc:literal])*
+        pub union $name:ident {
+            $($(#[doc = $field_doc:literal])* pub $field:ident: $ty:ty),* $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        $(#[doc = $doc])*
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        pub union $name {
+            $($(#[doc = $field_doc])* pub $field: $ty,)*
+        }
+
+        impl $crate::header::CType for $name {
+            const C_TYPE: &'static str = concat!("union ", stringify!($name));
+        }
+
+        $crate::header::protocol!(@declare [$($declaration,)*
+            $crate::header::protocol!(@compound "union" $name [$($doc),*] [$([$($field_doc),*] $field: $ty),*]),
+        ] $($rest)*);
+    };
+    // the declaration of a struct or union
+    (@compound $kind:literal $name:ident [$($doc:literal),*] [$([$($field_doc:literal),*] $field:ident: $ty:ty),*]) => {
+        $crate::header::Declaration::Compound {
+            kind: $kind,
+            docs: &[$($doc),*],
+            name: stringify!($name),
+            fields: &[$($crate::header::Field {
+                docs: &[$($field_doc),*],
+                name: stringify!($field),
+                ty: <$ty as $crate::header::CType>::C_TYPE,
+            },)*],
+        }
+    };
+    ($($items:tt)*) => {
+        $crate::header::protocol!(@declare [] $($items)*);
+    };
+}
+pub(crate) use protocol;
+
+/// Generates the C header equivalent to the protocol
+pub fn c_header() -> String {
+    let mut header = String::from(PREAMBLE);
+    for declaration in DECLARATIONS {
+        header.push('\n');
+        match declaration {
+            Declaration::Define { docs, name, value } => {
+                header.push_str(&c_docs(docs, ""));
+                header.push_str(&format!("#define {name} {value}\n"));
+            },
+            Declaration::Enum { docs, name, variants } => {
+                header.push_str(&c_docs(docs, ""));
+                header.push_str(&format!("enum {name} {{\n"));
+                for Variant { docs, name, value } in variants.iter() {
+                    header.push_str(&c_docs(docs, "    "));
+                    header.push_str(&format!("    {name} = {value},\n"));
+                }
+                header.push_str("};\n");
+            },
+            Declaration::Compound { kind, docs, name, fields } => {
+                header.push_str(&c_docs(docs, ""));
+                header.push_str(&format!("{kind} {name} {{\n"));
+                for Field { docs, name, ty } in fields.iter() {
+                    header.push_str(&c_docs(docs, "    "));
+                    header.push_str(&format!("    {ty} {name};\n"));
+                }
+                header.push_str("};\n");
+            },
+        }
+    }
+    header.push('\n');
+    header.push_str(POSTAMBLE);
+    header
+}
+
+/// Writes the doc comment lines with the given indentation (the docs are
+/// those of the Rust items: each line starts with the space after `///`)
+fn c_docs(docs: &[&str], indent: &str) -> String {
+    docs.iter()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end())
+        .map(|line| if line.is_empty() { format!("{indent}///\n") } else { format!("{indent}/// {line}\n") })
+        .collect()
+}
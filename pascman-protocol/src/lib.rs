@@ -0,0 +1,711 @@
+//! The protocol for the pas cman game. This contains the stuctures and enum
+//! you will use throughout the game.
+//!
+//! This crate does not need the standard library (it only needs `alloc` for
+//! the text protocol), so that servers, bots and tools can use the messages
+//! without the game. The `std` feature (enabled by default) implements
+//! `std::error::Error` for the errors, and the `serde` feature makes all the
+//! messages serializable.
+//! 
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT 
+
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use core::{fmt, str::FromStr};
+
+/// the generation of the C header (`pascman.h`)
+pub mod header;
+
+// Les constantes et les types qui ont un équivalent en C: la macro `protocol!`
+// les déclare aussi dans le header `pascman.h` (cf. le module `header`).
+header::protocol! {
+    /// Une map est constituée de 30 x 20 tuiles. Chacunes de ces tuiles peut etre
+    /// soit un mur, soit du sol. Il n'est possible de placer de la nourriture que
+    /// sur les cases de qui sont du sol. Il n'est aussi possible de se déplacer 
+    /// que sur des cases qui sont du sol.
+    pub const MAP_SIZE: usize = 30*20;
+
+    /// Par définition, on considere que la map qu'on crée dans notre jeu a une
+    /// dimension de 30 colonnes et 20 lignes
+    pub const WIDTH: usize = 30;
+
+    /// Par définition, on considere que la map qu'on crée dans notre jeu a une
+    /// dimension de 30 colonnes et 20 lignes
+    pub const HEIGHT: usize = 20;
+
+    /// Lorsqu'un utilisateur utilisera les flèches de son clavier au sein de
+    /// l'interface graphique, celle-ci écrira une direction (haut, bas, gauche, droite)
+    /// sur la sortie standard. De cette façon, vous pourrez toujours savoir ce que
+    /// l'utilisateur voulait faire meme si ce n'est pas vous qui avez programmé 
+    /// les interactions clavier.
+    pub enum Direction {
+        DOWN  = 0,
+        RIGHT = 1,
+        LEFT  = 2,
+        UP    = 3,
+    }
+
+    /// Une position représente la position d'un item sur la map. Il s'agit donc 
+    /// d'une position qui peut aller de {x: 0, y: 0} (coin supérieur gauche) à
+    /// {x: 29, y: 19} (coin inférieur droit).
+    pub struct Position {
+        pub x: u32,
+        pub y: u32,
+    }
+
+    /// Un item est tout type d'élément qui peut exister sur le plateau de jeu.
+    /// Au début du jeu, tous les items sont introduits à l'aide de messages 
+    /// de type 'spawn'.
+    pub enum Item {
+        /// un mur - type de tuile qui constitue un obstacle sur la carte
+        WALL      = 1,
+        /// du sol - type de tuile sur lesquelles on peut marcher sur la carte
+        FLOOR     = 2,
+        /// de la nourriture - les resources que les joueures doivent collecter pour gagner
+        FOOD      = 3,
+        /// de la superfood qui rapporte plus de points que la nourriture normale
+        SUPERFOOD = 4,
+        /// le joueur 1
+        PLAYER1   = 5,
+        /// le joueur 2
+        PLAYER2   = 6,
+    }
+
+    /// Le type de message qui est envoyé depuis l'extérieur à notre interface de jeu
+    pub enum MessageType {
+        /// To tell the system that you've been registered with the server.
+        REGISTRATION = 0,
+        /// To introduce an item in the game
+        SPAWN = 1,
+        /// To indicate that a given item moves on the map
+        MOVEMENT = 2,
+        /// To indicate that someone ate some food
+        EAT_FOOD = 3,
+        /// To tell that the game is over
+        GAME_OVER = 4,
+    }
+
+    /// Registration est le message qui sert à dire au jeu qu'on est un joueur en particulier.
+    pub struct Registration {
+        /// Ce messagetype devra toujours avoir la valeur REGISTRATION
+        pub msgt: MessageType,
+        /// L'identifiant du joueur
+        pub player: u32,
+    }
+
+    /// Spawn est le message qui sert à introduire un item dans le jeu.
+    /// 
+    /// Tous les items ont un identifiant numérique qui leur est attaché tout au cours de la 
+    /// partie. Chaque item possède aussi un type d'item et une position
+    pub struct Spawn {
+        /// Ce messagetype devra toujours avoir la valeur SPAWN
+        pub msgt: MessageType,
+        /// L'identifiant unique de la ressource à introduire
+        pub id  : u32,
+        /// Le type de l'item qu'on introduit
+        pub item: Item,
+        /// La position sur le plateau de jeu ou cet item doit etre introduit
+        pub pos : Position
+    }
+
+    /// Movement est le message qui sert à déplacer un item sur la carte.
+    pub struct Movement {
+        /// Ce messagetype devra toujours avoir la valeur MOVEMENT
+        pub msgt: MessageType,
+        /// L'identifiant unique de l'item qui doit se déplacer sur la carte
+        pub id:  u32,
+        /// La nouvelle position de l'item 
+        pub pos: Position
+    }
+
+    /// Indique que le qqn a mangé de la nourriture
+    pub struct EatFood {
+        /// Ce messagetype devra toujours avoir la valeur EAT_FOOD
+        pub msgt: MessageType,
+        pub eater: u32,
+        pub food: u32,
+    }
+
+    /// Indique que la partie est finie
+    pub struct GameOver {
+        /// Ce messagetype devra toujours avoir la valeur GAME_OVER
+        pub msgt: MessageType,
+        pub winner: u32,
+    }
+
+    /// Cette union encapsule tous les messages que vous pourriez vouloir envoyer à l'interface
+    /// graphique de votre jeu depuis votre programme.
+    pub union Message {
+        pub msgt: MessageType,
+        pub registration: Registration,
+        pub spawn: Spawn,
+        pub movement: Movement,
+        pub eat_food: EatFood,
+        pub game_over: GameOver,
+    }
+}
+
+/// L'identifiant du premier joueur (cf. le schéma d'identifiants décrit dans `game.h`)
+pub const PLAYER1_ID: u32 = 3 * MAP_SIZE as u32;
+
+/// L'identifiant du second joueur (cf. le schéma d'identifiants décrit dans `game.h`)
+pub const PLAYER2_ID: u32 = 3 * MAP_SIZE as u32 + 1;
+
+impl TryFrom<u32> for Direction {
+    type Error = u32;
+
+    /// Décode une direction telle qu'elle a été écrite par un client
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Direction::DOWN),
+            1 => Ok(Direction::RIGHT),
+            2 => Ok(Direction::LEFT),
+            3 => Ok(Direction::UP),
+            _ => Err(value),
+        }
+    }
+}
+
+impl TryFrom<u32> for Item {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Item::WALL),
+            2 => Ok(Item::FLOOR),
+            3 => Ok(Item::FOOD),
+            4 => Ok(Item::SUPERFOOD),
+            5 => Ok(Item::PLAYER1),
+            6 => Ok(Item::PLAYER2),
+            _ => Err(value),
+        }
+    }
+}
+
+impl TryFrom<u32> for MessageType {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MessageType::REGISTRATION),
+            1 => Ok(MessageType::SPAWN),
+            2 => Ok(MessageType::MOVEMENT),
+            3 => Ok(MessageType::EAT_FOOD),
+            4 => Ok(MessageType::GAME_OVER),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Les erreurs qui peuvent survenir lorsqu'on décode un message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Le champ 'msgt' ne correspond à aucun type de message
+    InvalidMessageType(u32),
+    /// Le champ 'item' d'un SPAWN ne correspond à aucun type d'item
+    InvalidItem(u32),
+}
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidMessageType(value) => write!(f, "invalid message type: {value}"),
+            DecodeError::InvalidItem(value)        => write!(f, "invalid item: {value}"),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// La taille (en octets) d'un message lorsqu'il est envoyé sur un pipe ou une socket
+pub const MESSAGE_SIZE: usize = core::mem::size_of::<Message>();
+
+impl Message {
+    /// Encode le message exactement comme un programme C l'écrirait avec
+    /// `write(fd, &msg, sizeof(union Message))`. Les octets qui ne sont pas
+    /// utilisés par le type de message sont mis à zéro.
+    pub fn to_bytes(&self) -> [u8; MESSAGE_SIZE] {
+        let mut words = [0_u32; MESSAGE_SIZE / 4];
+        unsafe {
+            words[0] = self.msgt as u32;
+            match self.msgt {
+                MessageType::REGISTRATION => {
+                    words[1] = self.registration.player;
+                },
+                MessageType::SPAWN => {
+                    let spawn = self.spawn;
+                    words[1] = spawn.id;
+                    words[2] = spawn.item as u32;
+                    words[3] = spawn.pos.x;
+                    words[4] = spawn.pos.y;
+                },
+                MessageType::MOVEMENT => {
+                    let movement = self.movement;
+                    words[1] = movement.id;
+                    words[2] = movement.pos.x;
+                    words[3] = movement.pos.y;
+                },
+                MessageType::EAT_FOOD => {
+                    words[1] = self.eat_food.eater;
+                    words[2] = self.eat_food.food;
+                },
+                MessageType::GAME_OVER => {
+                    words[1] = self.game_over.winner;
+                },
+            }
+        }
+
+        let mut bytes = [0_u8; MESSAGE_SIZE];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+            chunk.copy_from_slice(&word.to_ne_bytes());
+        }
+        bytes
+    }
+
+    /// Décode un message tel qu'il a été écrit par un programme C. Contrairement
+    /// à une simple conversion de pointeur, cette fonction vérifie que le type
+    /// de message et le type d'item sont valides.
+    pub fn from_bytes(bytes: &[u8; MESSAGE_SIZE]) -> Result<Self, DecodeError> {
+        let mut words = [0_u32; MESSAGE_SIZE / 4];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        let msgt = MessageType::try_from(words[0]).map_err(DecodeError::InvalidMessageType)?;
+        let message = match msgt {
+            MessageType::REGISTRATION => Message::registration(words[1]),
+            MessageType::SPAWN => {
+                let item = Item::try_from(words[2]).map_err(DecodeError::InvalidItem)?;
+                Message::spawn(words[1], item, Position { x: words[3], y: words[4] })
+            },
+            MessageType::MOVEMENT  => Message::movement(words[1], Position { x: words[2], y: words[3] }),
+            MessageType::EAT_FOOD  => Message::eat_food(words[1], words[2]),
+            MessageType::GAME_OVER => Message::game_over(words[1]),
+        };
+        Ok(message)
+    }
+
+    /// Crée le message qui signale au joueur `player` qu'il est enregistré
+    pub fn registration(player: u32) -> Self {
+        Message { registration: Registration { msgt: MessageType::REGISTRATION, player } }
+    }
+    /// Crée le message qui introduit l'item `id` de type `item` à la position `pos`
+    pub fn spawn(id: u32, item: Item, pos: Position) -> Self {
+        Message { spawn: Spawn { msgt: MessageType::SPAWN, id, item, pos } }
+    }
+    /// Crée le message qui déplace l'item `id` à la position `pos`
+    pub fn movement(id: u32, pos: Position) -> Self {
+        Message { movement: Movement { msgt: MessageType::MOVEMENT, id, pos } }
+    }
+    /// Crée le message qui indique que `eater` a mangé la nourriture `food`
+    pub fn eat_food(eater: u32, food: u32) -> Self {
+        Message { eat_food: EatFood { msgt: MessageType::EAT_FOOD, eater, food } }
+    }
+    /// Crée le message qui indique que la partie est finie
+    pub fn game_over(winner: u32) -> Self {
+        Message { game_over: GameOver { msgt: MessageType::GAME_OVER, winner } }
+    }
+}
+
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+impl Eq for Message {}
+
+impl fmt::Debug for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        unsafe {
+            match self.msgt {
+                MessageType::REGISTRATION => self.registration.fmt(f),
+                MessageType::SPAWN        => self.spawn.fmt(f),
+                MessageType::MOVEMENT     => self.movement.fmt(f),
+                MessageType::EAT_FOOD     => self.eat_food.fmt(f),
+                MessageType::GAME_OVER    => self.game_over.fmt(f),
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Le protocole texte
+// ----------------------------------------------------------------------------
+// En plus de l'union binaire, le jeu comprend un protocole texte (option
+// `--text-protocol`) dans lequel chaque ligne est un message. Par exemple:
+//
+//   REGISTRATION 1
+//   SPAWN 12 FOOD 3 4
+//   MOVEMENT 1800 3 4
+//   EAT_FOOD 1800 12
+//   GAME_OVER 1
+//
+// Dans ce mode, les directions choisies par l'utilisateur sont écrites sous la
+// forme `DIR LEFT` (une direction par ligne).
+
+/// Le mot clé qui précède une direction envoyée en mode texte (ex: `DIR LEFT`)
+pub const DIRECTION_KEYWORD: &str = "DIR";
+
+/// The way messages are encoded on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolMode {
+    /// Messages are the raw bytes of the `Message` union
+    Binary = 0,
+    /// Messages are lines of text such as `SPAWN 12 FOOD 3 4`
+    Text = 1,
+}
+
+impl FromStr for ProtocolMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "binary" => Ok(ProtocolMode::Binary),
+            "text"   => Ok(ProtocolMode::Text),
+            _ => Err(format!("unknown protocol mode '{s}' (expected binary or text)")),
+        }
+    }
+}
+
+/// Les erreurs qui peuvent survenir lorsqu'on lit un message au format texte
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// La ligne ne contient aucun mot
+    Empty,
+    /// Le mot ne correspond à aucun mot clé attendu (type de message, item, direction)
+    UnknownKeyword(String),
+    /// Le mot n'est pas un nombre
+    InvalidNumber(String),
+    /// Le message n'a pas le bon nombre d'arguments
+    WrongArity { keyword: String, expected: usize, found: usize },
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty                 => write!(f, "empty message"),
+            ParseError::UnknownKeyword(word)  => write!(f, "unknown keyword: '{word}'"),
+            ParseError::InvalidNumber(word)   => write!(f, "expected a number, got '{word}'"),
+            ParseError::WrongArity { keyword, expected, found } =>
+                write!(f, "{keyword} expects {expected} argument(s), got {found}"),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// Lit un nombre
+fn number(word: &str) -> Result<u32, ParseError> {
+    word.parse().map_err(|_| ParseError::InvalidNumber(word.to_string()))
+}
+
+/// Découpe la ligne en mots, vérifie qu'elle commence par le mot clé attendu
+/// et qu'elle est suivie du bon nombre d'arguments. Retourne les arguments.
+fn arguments<'a>(line: &'a str, keyword: &str, arity: usize) -> Result<Vec<&'a str>, ParseError> {
+    let mut words = line.split_whitespace();
+    let first = words.next().ok_or(ParseError::Empty)?;
+    if first != keyword {
+        return Err(ParseError::UnknownKeyword(first.to_string()));
+    }
+    let args = words.collect::<Vec<_>>();
+    if args.len() != arity {
+        return Err(ParseError::WrongArity { keyword: keyword.to_string(), expected: arity, found: args.len() });
+    }
+    Ok(args)
+}
+
+// Les noms des variantes sont ceux du protocole texte
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+impl FromStr for Direction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "DOWN"  => Ok(Direction::DOWN),
+            "RIGHT" => Ok(Direction::RIGHT),
+            "LEFT"  => Ok(Direction::LEFT),
+            "UP"    => Ok(Direction::UP),
+            ""      => Err(ParseError::Empty),
+            word    => Err(ParseError::UnknownKeyword(word.to_string())),
+        }
+    }
+}
+
+/// Une direction envoyée en mode texte. Elle s'écrit `DIR LEFT`, ou bien
+/// `DIR 2 LEFT` lorsqu'elle est précédée du joueur qui l'a choisie (mode hot-seat)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirectionLine {
+    /// Le joueur qui a choisi la direction (s'il est précisé)
+    pub player: Option<u32>,
+    pub direction: Direction,
+}
+impl fmt::Display for DirectionLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.player {
+            Some(player) => write!(f, "{DIRECTION_KEYWORD} {player} {}", self.direction),
+            None         => write!(f, "{DIRECTION_KEYWORD} {}", self.direction),
+        }
+    }
+}
+impl FromStr for DirectionLine {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let first = words.next().ok_or(ParseError::Empty)?;
+        if first != DIRECTION_KEYWORD {
+            return Err(ParseError::UnknownKeyword(first.to_string()));
+        }
+        match words.collect::<Vec<_>>().as_slice() {
+            [direction]         => Ok(DirectionLine { player: None, direction: direction.parse()? }),
+            [player, direction] => Ok(DirectionLine { player: Some(number(player)?), direction: direction.parse()? }),
+            args => {
+                let expected = if args.is_empty() { 1 } else { 2 };
+                Err(ParseError::WrongArity { keyword: DIRECTION_KEYWORD.to_string(), expected, found: args.len() })
+            },
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+impl FromStr for Item {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "WALL"      => Ok(Item::WALL),
+            "FLOOR"     => Ok(Item::FLOOR),
+            "FOOD"      => Ok(Item::FOOD),
+            "SUPERFOOD" => Ok(Item::SUPERFOOD),
+            "PLAYER1"   => Ok(Item::PLAYER1),
+            "PLAYER2"   => Ok(Item::PLAYER2),
+            ""          => Err(ParseError::Empty),
+            word        => Err(ParseError::UnknownKeyword(word.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for MessageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+impl FromStr for MessageType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "REGISTRATION" => Ok(MessageType::REGISTRATION),
+            "SPAWN"        => Ok(MessageType::SPAWN),
+            "MOVEMENT"     => Ok(MessageType::MOVEMENT),
+            "EAT_FOOD"     => Ok(MessageType::EAT_FOOD),
+            "GAME_OVER"    => Ok(MessageType::GAME_OVER),
+            ""             => Err(ParseError::Empty),
+            word           => Err(ParseError::UnknownKeyword(word.to_string())),
+        }
+    }
+}
+
+/// Une position s'écrit `x y`
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.x, self.y)
+    }
+}
+impl FromStr for Position {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_whitespace().collect::<Vec<_>>().as_slice() {
+            []     => Err(ParseError::Empty),
+            [x, y] => Ok(Position { x: number(x)?, y: number(y)? }),
+            words  => Err(ParseError::WrongArity { keyword: "position".to_string(), expected: 2, found: words.len() }),
+        }
+    }
+}
+
+/// Un enregistrement s'écrit `REGISTRATION player`
+impl fmt::Display for Registration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", MessageType::REGISTRATION, self.player)
+    }
+}
+impl FromStr for Registration {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let args = arguments(s, "REGISTRATION", 1)?;
+        Ok(Registration { msgt: MessageType::REGISTRATION, player: number(args[0])? })
+    }
+}
+
+/// Un spawn s'écrit `SPAWN id item x y`
+impl fmt::Display for Spawn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} {}", MessageType::SPAWN, self.id, self.item, self.pos)
+    }
+}
+impl FromStr for Spawn {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let args = arguments(s, "SPAWN", 4)?;
+        Ok(Spawn {
+            msgt: MessageType::SPAWN,
+            id  : number(args[0])?,
+            item: args[1].parse()?,
+            pos : Position { x: number(args[2])?, y: number(args[3])? },
+        })
+    }
+}
+
+/// Un mouvement s'écrit `MOVEMENT id x y`
+impl fmt::Display for Movement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", MessageType::MOVEMENT, self.id, self.pos)
+    }
+}
+impl FromStr for Movement {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let args = arguments(s, "MOVEMENT", 3)?;
+        Ok(Movement {
+            msgt: MessageType::MOVEMENT,
+            id  : number(args[0])?,
+            pos : Position { x: number(args[1])?, y: number(args[2])? },
+        })
+    }
+}
+
+/// Un repas s'écrit `EAT_FOOD eater food`
+impl fmt::Display for EatFood {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", MessageType::EAT_FOOD, self.eater, self.food)
+    }
+}
+impl FromStr for EatFood {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let args = arguments(s, "EAT_FOOD", 2)?;
+        Ok(EatFood { msgt: MessageType::EAT_FOOD, eater: number(args[0])?, food: number(args[1])? })
+    }
+}
+
+/// La fin de partie s'écrit `GAME_OVER winner`
+impl fmt::Display for GameOver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", MessageType::GAME_OVER, self.winner)
+    }
+}
+impl FromStr for GameOver {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let args = arguments(s, "GAME_OVER", 1)?;
+        Ok(GameOver { msgt: MessageType::GAME_OVER, winner: number(args[0])? })
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        unsafe {
+            match self.msgt {
+                MessageType::REGISTRATION => self.registration.fmt(f),
+                MessageType::SPAWN        => self.spawn.fmt(f),
+                MessageType::MOVEMENT     => self.movement.fmt(f),
+                MessageType::EAT_FOOD     => self.eat_food.fmt(f),
+                MessageType::GAME_OVER    => self.game_over.fmt(f),
+            }
+        }
+    }
+}
+impl FromStr for Message {
+    type Err = ParseError;
+
+    /// Lit un message écrit sur une ligne (ex: `SPAWN 12 FOOD 3 4`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keyword = s.split_whitespace().next().ok_or(ParseError::Empty)?;
+        let message = match keyword.parse()? {
+            MessageType::REGISTRATION => Message { registration: s.parse()? },
+            MessageType::SPAWN        => Message { spawn: s.parse()? },
+            MessageType::MOVEMENT     => Message { movement: s.parse()? },
+            MessageType::EAT_FOOD     => Message { eat_food: s.parse()? },
+            MessageType::GAME_OVER    => Message { game_over: s.parse()? },
+        };
+        Ok(message)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Sérialisation (feature `serde`)
+// ----------------------------------------------------------------------------
+// Un message est sérialisé comme la structure qui correspond à son type (ex:
+// `{"msgt": "SPAWN", "id": 12, "item": "FOOD", "pos": {"x": 3, "y": 4}}`).
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Message {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        unsafe {
+            match self.msgt {
+                MessageType::REGISTRATION => self.registration.serialize(serializer),
+                MessageType::SPAWN        => self.spawn.serialize(serializer),
+                MessageType::MOVEMENT     => self.movement.serialize(serializer),
+                MessageType::EAT_FOOD     => self.eat_food.serialize(serializer),
+                MessageType::GAME_OVER    => self.game_over.serialize(serializer),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Message {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        /// Tous les champs qu'un message peut avoir (seuls ceux de son type sont requis)
+        #[derive(serde::Deserialize)]
+        struct Fields {
+            msgt  : MessageType,
+            player: Option<u32>,
+            id    : Option<u32>,
+            item  : Option<Item>,
+            pos   : Option<Position>,
+            eater : Option<u32>,
+            food  : Option<u32>,
+            winner: Option<u32>,
+        }
+
+        fn required<T, E: Error>(field: Option<T>, name: &'static str) -> Result<T, E> {
+            field.ok_or_else(|| E::missing_field(name))
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        let message = match fields.msgt {
+            MessageType::REGISTRATION => Message::registration(required(fields.player, "player")?),
+            MessageType::SPAWN        => Message::spawn(
+                required(fields.id, "id")?,
+                required(fields.item, "item")?,
+                required(fields.pos, "pos")?,
+            ),
+            MessageType::MOVEMENT     => Message::movement(required(fields.id, "id")?, required(fields.pos, "pos")?),
+            MessageType::EAT_FOOD     => Message::eat_food(required(fields.eater, "eater")?, required(fields.food, "food")?),
+            MessageType::GAME_OVER    => Message::game_over(required(fields.winner, "winner")?),
+        };
+        Ok(message)
+    }
+}
@@ -0,0 +1,117 @@
+//! The binary encoding of the messages (the bytes of the C union).
+
+use pascman_protocol::*;
+
+/// Reads the `n`-th 32 bits word of an encoded message
+fn word(bytes: &[u8; MESSAGE_SIZE], n: usize) -> u32 {
+    u32::from_ne_bytes(bytes[4 * n..4 * n + 4].try_into().unwrap())
+}
+
+/// Encodes the given words as a C program would
+fn encode(words: &[u32]) -> [u8; MESSAGE_SIZE] {
+    let mut bytes = [0_u8; MESSAGE_SIZE];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_ne_bytes());
+    }
+    bytes
+}
+
+const ITEMS: [Item; 6] = [Item::WALL, Item::FLOOR, Item::FOOD, Item::SUPERFOOD, Item::PLAYER1, Item::PLAYER2];
+
+#[test]
+fn a_message_is_twenty_bytes_long() {
+    assert_eq!(MESSAGE_SIZE, 20);
+}
+
+#[test]
+fn a_registration_survives_a_round_trip() {
+    for player in [1, 2, u32::MAX] {
+        let message = Message::registration(player);
+        let bytes = message.to_bytes();
+        assert_eq!(bytes, encode(&[MessageType::REGISTRATION as u32, player]));
+        assert_eq!(Message::from_bytes(&bytes), Ok(message));
+    }
+}
+
+#[test]
+fn a_spawn_survives_a_round_trip() {
+    for item in ITEMS {
+        let message = Message::spawn(12, item, Position { x: 29, y: 19 });
+        let bytes = message.to_bytes();
+        assert_eq!(bytes, encode(&[MessageType::SPAWN as u32, 12, item as u32, 29, 19]));
+        assert_eq!(Message::from_bytes(&bytes), Ok(message));
+    }
+}
+
+#[test]
+fn a_movement_survives_a_round_trip() {
+    let message = Message::movement(PLAYER2_ID, Position { x: 3, y: 4 });
+    let bytes = message.to_bytes();
+    assert_eq!(bytes, encode(&[MessageType::MOVEMENT as u32, PLAYER2_ID, 3, 4]));
+    assert_eq!(Message::from_bytes(&bytes), Ok(message));
+}
+
+#[test]
+fn an_eat_food_survives_a_round_trip() {
+    let message = Message::eat_food(PLAYER1_ID, 42);
+    let bytes = message.to_bytes();
+    assert_eq!(bytes, encode(&[MessageType::EAT_FOOD as u32, PLAYER1_ID, 42]));
+    assert_eq!(Message::from_bytes(&bytes), Ok(message));
+}
+
+#[test]
+fn a_game_over_survives_a_round_trip() {
+    let message = Message::game_over(2);
+    let bytes = message.to_bytes();
+    assert_eq!(bytes, encode(&[MessageType::GAME_OVER as u32, 2]));
+    assert_eq!(Message::from_bytes(&bytes), Ok(message));
+}
+
+#[test]
+fn the_unused_bytes_are_ignored_when_decoding() {
+    let bytes = encode(&[MessageType::GAME_OVER as u32, 1, 0xdead, 0xbeef, 0xcafe]);
+    let message = Message::from_bytes(&bytes).unwrap();
+    assert_eq!(message, Message::game_over(1));
+    // ... and they are zeroed when encoding
+    assert_eq!(word(&message.to_bytes(), 2), 0);
+}
+
+#[test]
+fn an_unknown_message_type_is_rejected() {
+    for msgt in [5, 42, u32::MAX] {
+        let bytes = encode(&[msgt, 1]);
+        assert_eq!(Message::from_bytes(&bytes), Err(DecodeError::InvalidMessageType(msgt)));
+    }
+}
+
+#[test]
+fn a_spawn_of_an_unknown_item_is_rejected() {
+    for item in [0, 7, u32::MAX] {
+        let bytes = encode(&[MessageType::SPAWN as u32, 12, item, 3, 4]);
+        assert_eq!(Message::from_bytes(&bytes), Err(DecodeError::InvalidItem(item)));
+    }
+}
+
+#[test]
+fn the_decoding_errors_are_explained() {
+    assert_eq!(DecodeError::InvalidMessageType(42).to_string(), "invalid message type: 42");
+    assert_eq!(DecodeError::InvalidItem(7).to_string(), "invalid item: 7");
+}
+
+#[test]
+fn the_discriminants_are_decoded() {
+    for item in ITEMS {
+        assert_eq!(Item::try_from(item as u32), Ok(item));
+    }
+    assert_eq!(Item::try_from(0), Err(0));
+
+    for msgt in [MessageType::REGISTRATION, MessageType::SPAWN, MessageType::MOVEMENT, MessageType::EAT_FOOD, MessageType::GAME_OVER] {
+        assert_eq!(MessageType::try_from(msgt as u32), Ok(msgt));
+    }
+    assert_eq!(MessageType::try_from(5), Err(5));
+
+    for direction in [Direction::DOWN, Direction::RIGHT, Direction::LEFT, Direction::UP] {
+        assert_eq!(Direction::try_from(direction as u32), Ok(direction));
+    }
+    assert_eq!(Direction::try_from(4), Err(4));
+}
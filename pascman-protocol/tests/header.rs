@@ -0,0 +1,59 @@
+//! Checks that the generated C header is up to date and that the C compiler
+//! lays the messages out exactly like Rust does.
+
+use std::{env, fmt::Write, fs, mem::{offset_of, size_of}, path::PathBuf, process::Command};
+
+use pascman_protocol::{header::c_header, *};
+
+/// Writes the static assertions checking the size of a C type and the
+/// offsets of its fields against those of the Rust type
+macro_rules! layout {
+    ($out:expr, $kind:literal $ty:ident $(, $field:ident)*) => {
+        writeln!($out, "_Static_assert(sizeof({} {}) == {}, \"sizeof({} {})\");",
+            $kind, stringify!($ty), size_of::<$ty>(), $kind, stringify!($ty)).unwrap();
+        $(
+        writeln!($out, "_Static_assert(offsetof({} {}, {}) == {}, \"offsetof({} {}, {})\");",
+            $kind, stringify!($ty), stringify!($field), offset_of!($ty, $field),
+            $kind, stringify!($ty), stringify!($field)).unwrap();
+        )*
+    };
+}
+
+#[test]
+fn the_header_is_up_to_date() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../pascman.h");
+    let header = fs::read_to_string(path).unwrap();
+    assert!(header == c_header(), "pascman.h is outdated: regenerate it with `pas-cman-ipl emit-header`");
+}
+
+#[test]
+fn the_c_layout_is_the_same_as_the_rust_one() {
+    let mut program = String::from("#include <stddef.h>\n#include \"pascman.h\"\n\n");
+    layout!(program, "enum"   Direction);
+    layout!(program, "enum"   Item);
+    layout!(program, "enum"   MessageType);
+    layout!(program, "struct" Position, x, y);
+    layout!(program, "struct" Registration, msgt, player);
+    layout!(program, "struct" Spawn, msgt, id, item, pos);
+    layout!(program, "struct" Movement, msgt, id, pos);
+    layout!(program, "struct" EatFood, msgt, eater, food);
+    layout!(program, "struct" GameOver, msgt, winner);
+    layout!(program, "union"  Message, msgt, registration, spawn, movement, eat_food, game_over);
+    writeln!(program, "_Static_assert(MAP_SIZE == {MAP_SIZE}, \"MAP_SIZE\");").unwrap();
+    writeln!(program, "_Static_assert(WIDTH == {WIDTH}, \"WIDTH\");").unwrap();
+    writeln!(program, "_Static_assert(HEIGHT == {HEIGHT}, \"HEIGHT\");").unwrap();
+
+    let dir = env::temp_dir().join(format!("pascman-header-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("pascman.h"), c_header()).unwrap();
+    fs::write(dir.join("layout.c"), program).unwrap();
+
+    let compiler = env::var("CC").unwrap_or_else(|_| String::from("cc"));
+    let output = Command::new(compiler)
+        .current_dir(&dir)
+        .args(["-std=c11", "-pedantic", "-Wall", "-Werror", "-c", "layout.c", "-o", "layout.o"])
+        .output()
+        .expect("could not run the C compiler");
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
@@ -0,0 +1,41 @@
+//! The serialization of the messages (`serde` feature).
+#![cfg(feature = "serde")]
+
+use pascman_protocol::*;
+use serde_json::json;
+
+fn all_messages() -> Vec<Message> {
+    vec![
+        Message::registration(1),
+        Message::spawn(12, Item::SUPERFOOD, Position { x: 3, y: 4 }),
+        Message::movement(PLAYER1_ID, Position { x: 0, y: 19 }),
+        Message::eat_food(PLAYER2_ID, 12),
+        Message::game_over(2),
+    ]
+}
+
+#[test]
+fn a_message_is_serialized_as_the_structure_of_its_type() {
+    let value = serde_json::to_value(Message::spawn(12, Item::FOOD, Position { x: 3, y: 4 })).unwrap();
+    assert_eq!(value, json!({"msgt": "SPAWN", "id": 12, "item": "FOOD", "pos": {"x": 3, "y": 4}}));
+}
+
+#[test]
+fn the_messages_survive_a_round_trip() {
+    for message in all_messages() {
+        let text = serde_json::to_string(&message).unwrap();
+        assert_eq!(serde_json::from_str::<Message>(&text).unwrap(), message, "{text}");
+    }
+}
+
+#[test]
+fn the_malformed_messages_are_rejected() {
+    let missing = serde_json::from_str::<Message>(r#"{"msgt": "MOVEMENT", "id": 1800}"#).unwrap_err();
+    assert!(missing.to_string().contains("missing field `pos`"), "{missing}");
+
+    let unknown = serde_json::from_str::<Message>(r#"{"msgt": "TELEPORT"}"#).unwrap_err();
+    assert!(unknown.to_string().contains("unknown variant `TELEPORT`"), "{unknown}");
+
+    let item = serde_json::from_str::<Message>(r#"{"msgt": "SPAWN", "id": 1, "item": "PIZZA", "pos": {"x": 0, "y": 0}}"#).unwrap_err();
+    assert!(item.to_string().contains("unknown variant `PIZZA`"), "{item}");
+}
@@ -0,0 +1,96 @@
+//! The text protocol.
+
+use pascman_protocol::{Direction, DirectionLine, Item, Message, ParseError, Position, ProtocolMode};
+
+fn all_messages() -> Vec<Message> {
+    vec![
+        Message::registration(2),
+        Message::spawn(12, Item::FOOD, Position { x: 3, y: 4 }),
+        Message::spawn(600, Item::WALL, Position { x: 0, y: 19 }),
+        Message::movement(1800, Position { x: 29, y: 0 }),
+        Message::eat_food(1801, 12),
+        Message::game_over(1),
+    ]
+}
+
+#[test]
+fn the_messages_are_written_on_one_line() {
+    let lines = all_messages().iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(lines, vec![
+        "REGISTRATION 2",
+        "SPAWN 12 FOOD 3 4",
+        "SPAWN 600 WALL 0 19",
+        "MOVEMENT 1800 29 0",
+        "EAT_FOOD 1801 12",
+        "GAME_OVER 1",
+    ]);
+}
+
+#[test]
+fn the_messages_survive_a_round_trip() {
+    for message in all_messages() {
+        assert_eq!(message.to_string().parse::<Message>(), Ok(message));
+    }
+}
+
+#[test]
+fn the_extra_blanks_are_ignored() {
+    assert_eq!("  MOVEMENT\t1800  3 4 ".parse::<Message>(), Ok(Message::movement(1800, Position { x: 3, y: 4 })));
+}
+
+#[test]
+fn the_malformed_messages_are_rejected() {
+    assert_eq!("".parse::<Message>(), Err(ParseError::Empty));
+    assert_eq!("TELEPORT 1 2".parse::<Message>(), Err(ParseError::UnknownKeyword("TELEPORT".into())));
+    assert_eq!("SPAWN 12 PIZZA 3 4".parse::<Message>(), Err(ParseError::UnknownKeyword("PIZZA".into())));
+    assert_eq!("MOVEMENT 1800 x 4".parse::<Message>(), Err(ParseError::InvalidNumber("x".into())));
+    assert_eq!("GAME_OVER -1".parse::<Message>(), Err(ParseError::InvalidNumber("-1".into())));
+    assert_eq!(
+        "EAT_FOOD 1800".parse::<Message>(),
+        Err(ParseError::WrongArity { keyword: "EAT_FOOD".into(), expected: 2, found: 1 })
+    );
+    assert_eq!(
+        "REGISTRATION 1 2".parse::<Message>(),
+        Err(ParseError::WrongArity { keyword: "REGISTRATION".into(), expected: 1, found: 2 })
+    );
+}
+
+#[test]
+fn the_directions_survive_a_round_trip() {
+    for direction in [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT] {
+        for player in [None, Some(1), Some(2)] {
+            let line = DirectionLine { player, direction };
+            assert_eq!(line.to_string().parse::<DirectionLine>(), Ok(line));
+        }
+    }
+}
+
+#[test]
+fn the_directions_are_preceded_by_their_keyword_and_player() {
+    assert_eq!(DirectionLine { player: None, direction: Direction::LEFT }.to_string(), "DIR LEFT");
+    assert_eq!(DirectionLine { player: Some(2), direction: Direction::UP }.to_string(), "DIR 2 UP");
+    assert_eq!(" DIR  DOWN ".parse(), Ok(DirectionLine { player: None, direction: Direction::DOWN }));
+}
+
+#[test]
+fn the_malformed_directions_are_rejected() {
+    assert_eq!("".parse::<DirectionLine>(), Err(ParseError::Empty));
+    assert_eq!("LEFT".parse::<DirectionLine>(), Err(ParseError::UnknownKeyword("LEFT".into())));
+    assert_eq!("DIR SIDEWAYS".parse::<DirectionLine>(), Err(ParseError::UnknownKeyword("SIDEWAYS".into())));
+    assert_eq!("DIR two LEFT".parse::<DirectionLine>(), Err(ParseError::InvalidNumber("two".into())));
+    assert_eq!(
+        "DIR".parse::<DirectionLine>(),
+        Err(ParseError::WrongArity { keyword: "DIR".into(), expected: 1, found: 0 })
+    );
+    assert_eq!(
+        "DIR 1 2 LEFT".parse::<DirectionLine>(),
+        Err(ParseError::WrongArity { keyword: "DIR".into(), expected: 2, found: 3 })
+    );
+}
+
+#[test]
+fn the_protocol_mode_is_parsed_from_its_name() {
+    assert_eq!("binary".parse(), Ok(ProtocolMode::Binary));
+    assert_eq!("text".parse(), Ok(ProtocolMode::Text));
+    assert!("json".parse::<ProtocolMode>().is_err());
+}
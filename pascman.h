@@ -2,14 +2,23 @@
 #define __PASCMAN__
 //! Ce header comprend les définitions de types dont vous aurez besoin pour
 //! interagir avec l'interface graphique du jeu que vous allez développer
-//! 
+//!
+//! Ce fichier est généré à partir de la crate `pascman-protocol` à l'aide de la
+//! commande `pas-cman-ipl emit-header`: ne le modifiez pas à la main.
+//!
 //! Author:  X. Gillard
 //! Date:    March 2023
-//! Licence: MIT 
+//! Licence: MIT
 
 #include <stdbool.h>
 #include <stdint.h>
 
+/// Une map est constituée de 30 x 20 tuiles. Chacunes de ces tuiles peut etre
+/// soit un mur, soit du sol. Il n'est possible de placer de la nourriture que
+/// sur les cases de qui sont du sol. Il n'est aussi possible de se déplacer
+/// que sur des cases qui sont du sol.
+#define MAP_SIZE (30*20)
+
 /// Par définition, on considere que la map qu'on crée dans notre jeu a une
 /// dimension de 30 colonnes et 20 lignes
 #define WIDTH 30
@@ -18,25 +27,19 @@
 /// dimension de 30 colonnes et 20 lignes
 #define HEIGHT 20
 
-/// Une map est constituée de 30 x 20 tuiles. Chacunes de ces tuiles peut etre
-/// soit un mur, soit du sol. Il n'est possible de placer de la nourriture que
-/// sur les cases de qui sont du sol. Il n'est aussi possible de se déplacer 
-/// que sur des cases qui sont du sol.
-#define MAP_SIZE (30*20)
-
 /// Lorsqu'un utilisateur utilisera les flèches de son clavier au sein de
 /// l'interface graphique, celle-ci écrira une direction (haut, bas, gauche, droite)
 /// sur la sortie standard. De cette façon, vous pourrez toujours savoir ce que
-/// l'utilisateur voulait faire meme si ce n'est pas vous qui avez programmé 
+/// l'utilisateur voulait faire meme si ce n'est pas vous qui avez programmé
 /// les interactions clavier.
 enum Direction {
-    DOWN  = 0,
+    DOWN = 0,
     RIGHT = 1,
-    LEFT  = 2,
-    UP    = 3
+    LEFT = 2,
+    UP = 3,
 };
 
-/// Une position représente la position d'un item sur la map. Il s'agit donc 
+/// Une position représente la position d'un item sur la map. Il s'agit donc
 /// d'une position qui peut aller de {x: 0, y: 0} (coin supérieur gauche) à
 /// {x: 29, y: 19} (coin inférieur droit).
 struct Position {
@@ -45,15 +48,21 @@ struct Position {
 };
 
 /// Un item est tout type d'élément qui peut exister sur le plateau de jeu.
-/// Au début du jeu, tous les items sont introduits à l'aide de messages 
+/// Au début du jeu, tous les items sont introduits à l'aide de messages
 /// de type 'spawn'.
 enum Item {
-    WALL      = 1, // un mur - type de tuile qui constitue un obstacle sur la carte  
-    FLOOR     = 2, // du sol - type de tuile sur lesquelles on peut marcher sur la carte
-    FOOD      = 3, // de la nourriture - les resources que les joueures doivent collecter pour gagner
-    SUPERFOOD = 4, // de la superfood qui rapporte plus de points que la nourriture normale
-    PLAYER1   = 5, // le joueur 1
-    PLAYER2   = 6, // le joueur 1
+    /// un mur - type de tuile qui constitue un obstacle sur la carte
+    WALL = 1,
+    /// du sol - type de tuile sur lesquelles on peut marcher sur la carte
+    FLOOR = 2,
+    /// de la nourriture - les resources que les joueures doivent collecter pour gagner
+    FOOD = 3,
+    /// de la superfood qui rapporte plus de points que la nourriture normale
+    SUPERFOOD = 4,
+    /// le joueur 1
+    PLAYER1 = 5,
+    /// le joueur 2
+    PLAYER2 = 6,
 };
 
 /// Le type de message qui est envoyé depuis l'extérieur à notre interface de jeu
@@ -70,7 +79,6 @@ enum MessageType {
     GAME_OVER = 4,
 };
 
-
 /// Registration est le message qui sert à dire au jeu qu'on est un joueur en particulier.
 struct Registration {
     /// Ce messagetype devra toujours avoir la valeur REGISTRATION
@@ -80,8 +88,8 @@ struct Registration {
 };
 
 /// Spawn est le message qui sert à introduire un item dans le jeu.
-/// 
-/// Tous les items ont un identifiant numérique qui leur est attaché tout au cours de la 
+///
+/// Tous les items ont un identifiant numérique qui leur est attaché tout au cours de la
 /// partie. Chaque item possède aussi un type d'item et une position
 struct Spawn {
     /// Ce messagetype devra toujours avoir la valeur SPAWN
@@ -94,16 +102,13 @@ struct Spawn {
     struct Position pos;
 };
 
-/// Spawn est le message qui sert à introduire un item dans le jeu.
-/// 
-/// Tous les items ont un identifiant numérique qui leur est attaché tout au cours de la 
-/// partie. Chaque item possède aussi un type d'item et une position
+/// Movement est le message qui sert à déplacer un item sur la carte.
 struct Movement {
     /// Ce messagetype devra toujours avoir la valeur MOVEMENT
     enum MessageType msgt;
     /// L'identifiant unique de l'item qui doit se déplacer sur la carte
     uint32_t id;
-    /// La nouvelle position de l'item 
+    /// La nouvelle position de l'item
     struct Position pos;
 };
 
@@ -115,7 +120,7 @@ struct EatFood {
     uint32_t food;
 };
 
-/// Indique que le jeu est fini.
+/// Indique que la partie est finie
 struct GameOver {
     /// Ce messagetype devra toujours avoir la valeur GAME_OVER
     enum MessageType msgt;
@@ -133,4 +138,4 @@ union Message {
     struct GameOver game_over;
 };
 
-#endif //__PASCMAN__
\ No newline at end of file
+#endif //__PASCMAN__
@@ -0,0 +1,58 @@
+#ifndef __PASCMAN_GUI__
+#define __PASCMAN_GUI__
+//! Ce header déclare l'API C de l'interface graphique du jeu. Plutôt que de
+//! lancer `pas-cman-ipl` et de lui envoyer les messages à travers un pipe, votre
+//! programme peut être lié à la librairie `libpas_cman_ipl.so` (compilée avec
+//! `cargo build --release`) et piloter l'interface directement:
+//!
+//!     gcc -o client client.c -Ltarget/release -lpas_cman_ipl
+//!
+//! L'interface tourne dans le thread qui appelle `pascman_gui_run` (de préférence
+//! le thread principal, certaines plateformes refusant d'ouvrir une fenêtre
+//! ailleurs) tandis que votre programme joue depuis un autre thread. Il ne peut y
+//! avoir qu'une seule interface ouverte à la fois.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+#include <stdbool.h>
+#include "pascman.h"
+
+/// Les options de l'interface
+struct PascmanGuiOptions {
+    /// Le répertoire où se trouvent les polices (PAS_RESOURCES ou "resources/" si NULL)
+    const char* resources;
+    /// Faire tourner l'interface sans fenêtre
+    bool headless;
+    /// Rejeter les messages qui ne respectent pas le protocole
+    bool strict;
+};
+
+/// Prépare l'interface dans le thread appelant (avec une fenêtre et les options
+/// par défaut lorsque `options` est NULL). C'est ce même thread qui doit ensuite
+/// appeler `pascman_gui_run`. Retourne 0 si l'interface est prête, -1 si elle est
+/// déjà ouverte.
+int pascman_gui_open(const struct PascmanGuiOptions* options);
+
+/// Fait tourner l'interface dans le thread appelant jusqu'à ce qu'elle soit fermée.
+/// Retourne 0 une fois l'interface fermée, -1 si aucune interface n'a été ouverte
+/// dans ce thread ou qu'elle n'a pas pu démarrer (sa fenêtre par exemple).
+int pascman_gui_run(void);
+
+/// Envoie un message à l'interface (depuis n'importe quel thread). Retourne 0 si
+/// le message a été envoyé, -1 s'il est invalide ou que l'interface n'est pas ouverte.
+int pascman_gui_push(const union Message* message);
+
+/// Récupère (sans bloquer, depuis n'importe quel thread) la prochaine direction
+/// choisie par l'utilisateur. Retourne 1 si une direction a été écrite dans
+/// `direction`, 0 si l'utilisateur n'en a choisi aucune et -1 si l'interface n'est
+/// pas ouverte.
+int pascman_gui_poll_direction(enum Direction* direction);
+
+/// Arrête l'interface (depuis n'importe quel thread): `pascman_gui_run` retourne
+/// dès que possible. Attention: lorsque l'interface a une fenêtre, la fermer
+/// termine aussi votre programme.
+void pascman_gui_close(void);
+
+#endif //__PASCMAN_GUI__
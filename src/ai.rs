@@ -0,0 +1,146 @@
+//! The artificial intelligence driving the villains. The personality of each
+//! villain decides the tile it is targeting; it then follows the shortest path
+//! (computed with A*) towards that tile. Frightened villains, on the other
+//! hand, run away from the hero.
+//!
+//! When a scatterer cannot reach its corner, it chases the hero instead.
+//!
+//! Nothing in here depends on the ECS: the AI only needs the map and the
+//! positions of the characters. This is why it can be used by the offline
+//! mode as well as by a server (see `Bot`, which plays on behalf of one of
+//! the players of the `GameRules`).
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use bracket_geometry::prelude::{DistanceAlg, Point};
+use bracket_pathfinding::prelude::{a_star_search, Algorithm2D, BaseMap};
+
+use crate::{pascman_protocol::{self, Item}, rules::GameRules, Corner, Direction, Map, Personality, Position, TileType};
+
+/// The number of tiles ahead of the hero an ambusher is aiming at
+const AMBUSH_DISTANCE: usize = 4;
+
+/// Returns the position where a villain located at `me` should go next
+/// (or None when it should stay where it is). The hero is located at `hero`
+/// and is heading towards `heading`.
+///
+/// Note: the personality of a scatterer is updated each time it reaches a
+/// corner of the map, so that it heads for the next one.
+pub fn next_step(
+    map: &Map,
+    personality: &mut Personality,
+    me: Position,
+    hero: Position,
+    heading: Direction,
+    frightened: bool,
+) -> Option<Position> {
+    if frightened {
+        return flee(map, me, hero);
+    }
+
+    match personality {
+        Personality::Chaser   => step_towards(map, me, hero),
+        Personality::Ambusher => step_towards(map, me, ambush_target(map, hero, heading)),
+        Personality::Scatterer(corner) => {
+            let mut target = corner_target(map, *corner);
+            if target == Some(me) {
+                *corner = corner.next();
+                target  = corner_target(map, *corner);
+            }
+            // the corner might be out of reach (e.g. walled in): chase the hero instead
+            target.and_then(|target| step_towards(map, me, target))
+                .or_else(|| step_towards(map, me, hero))
+        },
+    }
+}
+
+/// A villain driven by the computer on behalf of one of the players of the
+/// `GameRules`: the other player is the hero it hunts.
+#[derive(Debug, Clone)]
+pub struct Bot {
+    player: Item,
+    personality: Personality,
+    map: Map,
+}
+
+impl Bot {
+    /// Creates a bot playing the given player (PLAYER1 or PLAYER2) with the
+    /// given personality on the map of the game
+    pub fn new(player: Item, personality: Personality, rules: &GameRules) -> Self {
+        Self { player, personality, map: rules.to_map() }
+    }
+
+    /// Returns the direction the bot chooses, knowing where the hero is
+    /// heading (or None when it does not want to move)
+    pub fn play(&mut self, rules: &GameRules, heading: pascman_protocol::Direction, frightened: bool) -> Option<pascman_protocol::Direction> {
+        let hero = if self.player == Item::PLAYER1 { Item::PLAYER2 } else { Item::PLAYER1 };
+        let position = |player: Item| rules.position(player).map(|pos| Position { x: pos.x as usize, y: pos.y as usize });
+        let me   = position(self.player)?;
+        let hero = position(hero)?;
+        let next = next_step(&self.map, &mut self.personality, me, hero, heading.into(), frightened)?;
+        me.direction_to(next).map(pascman_protocol::Direction::from)
+    }
+}
+
+/// Returns the first step on the shortest path leading from `me` to `target`
+pub fn step_towards(map: &Map, me: Position, target: Position) -> Option<Position> {
+    let start = map.point2d_to_index(me.into_point());
+    let end   = map.point2d_to_index(target.into_point());
+    if start == end {
+        return None;
+    }
+
+    let path = a_star_search(start, end, map);
+    if path.success {
+        path.steps.get(1).map(|idx| Position::from_point(map.index_to_point2d(*idx)))
+    } else {
+        None
+    }
+}
+
+/// Returns the neighboring tile which brings `me` the farthest from the hero
+pub fn flee(map: &Map, me: Position, hero: Position) -> Option<Position> {
+    let here = map.point2d_to_index(me.into_point());
+    let hero = map.point2d_to_index(hero.into_point());
+
+    map.get_available_exits(here)
+        .iter()
+        .map(|(idx, _cost)| (*idx, map.get_pathing_distance(*idx, hero)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(idx, _dist)| Position::from_point(map.index_to_point2d(idx)))
+}
+
+/// Returns the farthest tile (at most AMBUSH_DISTANCE tiles) the hero could
+/// reach by going straight ahead
+fn ambush_target(map: &Map, hero: Position, heading: Direction) -> Position {
+    let mut target = hero;
+    for _ in 0..AMBUSH_DISTANCE {
+        match heading.next(target) {
+            Some(next) if map.can_enter(next.into_point()) => target = next,
+            _ => break,
+        }
+    }
+    target
+}
+
+/// Returns the floor tile which is the closest to the given corner of the map
+fn corner_target(map: &Map, corner: Corner) -> Option<Position> {
+    let w = map.width  as i32 - 1;
+    let h = map.height as i32 - 1;
+    let corner = match corner {
+        Corner::TopLeft     => Point::new(0, 0),
+        Corner::TopRight    => Point::new(w, 0),
+        Corner::BottomRight => Point::new(w, h),
+        Corner::BottomLeft  => Point::new(0, h),
+    };
+
+    map.tiles.iter()
+        .enumerate()
+        .filter(|(_idx, tile)| **tile == TileType::Floor)
+        .map(|(idx, _tile)| map.index_to_point2d(idx))
+        .map(|pt| (pt, DistanceAlg::PythagorasSquared.distance2d(pt, corner)))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(pt, _dist)| Position::from_point(pt))
+}
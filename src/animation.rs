@@ -0,0 +1,232 @@
+//! The animations of the characters. Each character has a sprite set: a
+//! sequence of frames for each direction it may go, plus the frames shown
+//! when it stands still (facing some direction), when it dies and when it is
+//! frightened. The sprite sets are described in a data file
+//! (`resources/sprites.toml`) which maps the glyphs of the font onto
+//! animations:
+//!
+//! ```toml
+//! [villain]
+//! period     = 200      # how long (in milliseconds) each frame is shown
+//! down       = [33]     # the indices of the glyphs in the font
+//! right      = [49]
+//! left       = [65]
+//! up         = [33]
+//! idle       = [1]      # optional (as are all the entries below)
+//! idle_right = [49]     # overrides `idle` when standing still facing right
+//! death      = [33, 65, 49, 33]
+//! frightened = [2, 3]
+//! ```
+//!
+//! A character standing still shows its `idle` frames or, when it has none,
+//! the frames of the direction it faces.
+//!
+//! Which animation is played depends on the pose of the character, and each
+//! character keeps track of how long it has been in that pose (see the
+//! `Character` component).
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{collections::HashMap, fs, path::Path, str::FromStr, sync::Arc};
+
+use serde::Deserialize;
+
+use crate::{frame::cp437, Direction};
+
+/// The file (in the resources directory) describing the sprite sets
+pub const SPRITES_FILE: &str = "sprites.toml";
+/// The sprite set of the heroes
+pub const HERO: &str = "hero";
+/// The sprite set of the villains
+pub const VILLAIN: &str = "villain";
+/// How long (in milliseconds) a character must stand still before it is idle
+pub const IDLE_AFTER: f32 = 500.0;
+
+/// The sprite sets which are used when none is given
+const DEFAULT_SPRITES: &str = include_str!("../resources/sprites.toml");
+
+/// A sequence of frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Animation {
+    pub frames: Vec<char>,
+}
+
+impl Animation {
+    /// Returns the frame shown after `elapsed` milliseconds, when each frame
+    /// is shown for `period` milliseconds. Unless the animation loops, it
+    /// stops on its last frame.
+    pub fn frame(&self, period: f32, elapsed: f32, looping: bool) -> char {
+        let count = if period > 0.0 { (elapsed / period) as usize } else { 0 };
+        let index = if looping { count % self.frames.len() } else { count.min(self.frames.len() - 1) };
+        self.frames[index]
+    }
+}
+
+/// What a character is doing, which decides the animation to play
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pose {
+    /// Going in some direction
+    Walking(Direction),
+    /// Standing still, facing some direction
+    Idle(Direction),
+    /// Dying
+    Dying,
+    /// Frightened (it is about to be eaten)
+    Frightened,
+}
+
+/// All the animations of one character
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteSet {
+    /// How long (in milliseconds) each frame is shown
+    pub period: u32,
+    pub down: Animation,
+    pub right: Animation,
+    pub left: Animation,
+    pub up: Animation,
+    /// Standing still, facing down, right, left or up
+    pub idle_down: Animation,
+    pub idle_right: Animation,
+    pub idle_left: Animation,
+    pub idle_up: Animation,
+    pub death: Animation,
+    pub frightened: Animation,
+}
+
+impl SpriteSet {
+    /// Returns the frame shown after the character has been `elapsed`
+    /// milliseconds in the given pose (only the death is not played in a loop)
+    pub fn frame(&self, pose: Pose, elapsed: f32) -> char {
+        let animation = match pose {
+            Pose::Walking(Direction::Down)  => &self.down,
+            Pose::Walking(Direction::Right) => &self.right,
+            Pose::Walking(Direction::Left)  => &self.left,
+            Pose::Walking(Direction::Up)    => &self.up,
+            Pose::Idle(Direction::Down)     => &self.idle_down,
+            Pose::Idle(Direction::Right)    => &self.idle_right,
+            Pose::Idle(Direction::Left)     => &self.idle_left,
+            Pose::Idle(Direction::Up)       => &self.idle_up,
+            Pose::Dying                     => &self.death,
+            Pose::Frightened                => &self.frightened,
+        };
+        animation.frame(self.period as f32, elapsed, pose != Pose::Dying)
+    }
+}
+
+/// The sprite sets of all the characters, by name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sprites {
+    sets: HashMap<String, Arc<SpriteSet>>,
+}
+
+impl Default for Sprites {
+    /// The sprite sets of the `resources/sprites.toml` file
+    fn default() -> Self {
+        DEFAULT_SPRITES.parse().expect("the default sprite sets are invalid")
+    }
+}
+
+impl Sprites {
+    /// Loads the sprite sets from the given file
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let error = |reason: String| format!("invalid sprites {}: {reason}", path.display());
+        let text = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        text.parse().map_err(error)
+    }
+
+    /// Loads the sprite sets from the resources directory, or uses the
+    /// default ones when that directory has no sprites file
+    pub fn load(resources: &Path) -> Result<Self, String> {
+        let path = resources.join(SPRITES_FILE);
+        if path.is_file() {
+            Self::from_file(&path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Returns the sprite set of the heroes
+    pub fn hero(&self) -> Arc<SpriteSet> {
+        self.sets[HERO].clone()
+    }
+
+    /// Returns the sprite set of the villains
+    pub fn villain(&self) -> Arc<SpriteSet> {
+        self.sets[VILLAIN].clone()
+    }
+}
+
+impl FromStr for Sprites {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let raw = toml::from_str::<HashMap<String, RawSpriteSet>>(text).map_err(|e| e.to_string())?;
+        let sets = raw.into_iter()
+            .map(|(name, set)| set.resolve(&name).map(|set| (name, Arc::new(set))))
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        for name in [HERO, VILLAIN] {
+            if !sets.contains_key(name) {
+                return Err(format!("the sprite set '{name}' is missing"));
+            }
+        }
+        Ok(Sprites { sets })
+    }
+}
+
+// The sprite sets as they are written in the file: the frames are glyph
+// indices which still have to be checked.
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSpriteSet {
+    period: u32,
+    down: Vec<u8>,
+    right: Vec<u8>,
+    left: Vec<u8>,
+    up: Vec<u8>,
+    idle: Option<Vec<u8>>,
+    idle_down: Option<Vec<u8>>,
+    idle_right: Option<Vec<u8>>,
+    idle_left: Option<Vec<u8>>,
+    idle_up: Option<Vec<u8>>,
+    death: Option<Vec<u8>>,
+    frightened: Option<Vec<u8>>,
+}
+
+impl RawSpriteSet {
+    fn resolve(self, name: &str) -> Result<SpriteSet, String> {
+        let animation = |entry: &str, frames: Vec<u8>| {
+            if frames.is_empty() {
+                Err(format!("{name}.{entry}: at least one frame is needed"))
+            } else {
+                Ok(Animation { frames: frames.into_iter().map(cp437).collect() })
+            }
+        };
+        let optional = |entry: &str, frames: Option<Vec<u8>>, default: &Animation| match frames {
+            None => Ok(default.clone()),
+            Some(frames) => animation(entry, frames),
+        };
+        let down  = animation("down", self.down)?;
+        let right = animation("right", self.right)?;
+        let left  = animation("left", self.left)?;
+        let up    = animation("up", self.up)?;
+        // standing still, a character faces the direction it was going to,
+        // unless it has idle frames of its own
+        let idle = self.idle.map(|frames| animation("idle", frames)).transpose()?;
+        Ok(SpriteSet {
+            period: self.period,
+            idle_down: optional("idle_down", self.idle_down, idle.as_ref().unwrap_or(&down))?,
+            idle_right: optional("idle_right", self.idle_right, idle.as_ref().unwrap_or(&right))?,
+            idle_left: optional("idle_left", self.idle_left, idle.as_ref().unwrap_or(&left))?,
+            idle_up: optional("idle_up", self.idle_up, idle.as_ref().unwrap_or(&up))?,
+            death: optional("death", self.death, &down)?,
+            frightened: optional("frightened", self.frightened, &down)?,
+            down,
+            right,
+            left,
+            up,
+        })
+    }
+}
@@ -0,0 +1,218 @@
+//! A conformance test harness for the pas cman servers. It stands in for the
+//! graphical interface: it consumes the stream of messages sent by a server
+//! (actually, by the client program which talks to the server), sends a
+//! scripted sequence of directions and then checks the messages it received
+//! (see the `conformance` module for the list of checks).
+//!
+//! It prints a pass/fail report along with the indices of the offending
+//! messages, and exits with status 1 when some check failed.
+//!
+//! The script lists the directions to send, e.g.:
+//!
+//! ```text
+//! # this is a comment
+//! wait 1s         # waits a little before sending the next direction
+//! right x3        # sends 'right' three times (one direction per pace)
+//! down
+//! ```
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{
+    fs,
+    io::{self, stdin, stdout, BufRead, BufReader, Read, Write},
+    path::PathBuf,
+    process::{exit, Command, Stdio},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use pas_cman_ipl::{
+    conformance::{analyze, report},
+    pascman_protocol::{Direction, DirectionLine, Message, MessageType, MESSAGE_SIZE},
+    scenario::{parse_direction, parse_duration},
+};
+use structopt::StructOpt;
+
+/// How long to keep listening once the GAME_OVER has been received
+const GRACE_PERIOD: Duration = Duration::from_millis(500);
+
+/// pas-cman-check: a fake interface which checks the behavior of a server
+#[derive(Debug, StructOpt)]
+#[structopt(name = "pas-cman-check")]
+struct Opt {
+    /// The script listing the directions to send (none when omitted)
+    #[structopt(long, parse(from_os_str))]
+    script: Option<PathBuf>,
+    /// The time between two directions
+    #[structopt(long, default_value = "250ms", parse(try_from_str = parse_duration))]
+    pace: Duration,
+    /// How long to wait for the registration, and then for the end of the
+    /// game once all the directions have been sent
+    #[structopt(long, default_value = "10s", parse(try_from_str = parse_duration))]
+    timeout: Duration,
+    /// Use the line oriented text protocol rather than the binary one
+    #[structopt(long)]
+    text_protocol: bool,
+    /// The command to run in place of the interface (e.g. your client). When
+    /// omitted, the messages are read from the standard input and the
+    /// directions are written to the standard output (the report then goes
+    /// to the standard error)
+    command: Vec<String>,
+}
+
+/// One direction of the script along with the time to wait before sending it
+struct Step {
+    delay: Duration,
+    direction: Direction,
+}
+
+/// What the harness received from the server
+enum Inbound {
+    /// A message (or the reason why it could not be decoded)
+    Message(Result<Message, String>),
+    /// The end of the stream
+    Eof,
+}
+
+/// Compiles the script into a sequence of timed directions
+fn compile(text: &str, pace: Duration) -> Result<Vec<Step>, String> {
+    let mut steps = vec![];
+    let mut delay = Duration::ZERO;
+    for (n, line) in text.lines().enumerate() {
+        let line  = line.split('#').next().unwrap_or_default();
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let error = |reason: String| format!("line {}: {reason}", n + 1);
+        match words.as_slice() {
+            [] => {},
+            ["wait", duration] => delay += parse_duration(duration).map_err(error)?,
+            [direction] | [direction, _] => {
+                let direction = parse_direction(direction).map_err(error)?;
+                let times = match words.get(1) {
+                    None => 1,
+                    Some(times) => times.strip_prefix('x')
+                        .and_then(|t| t.parse::<u32>().ok())
+                        .ok_or_else(|| error(format!("expected a repetition such as 'x4', got '{times}'")))?,
+                };
+                for _ in 0..times {
+                    steps.push(Step { delay: delay + pace, direction });
+                    delay = Duration::ZERO;
+                }
+            },
+            _ => return Err(error(format!("cannot understand '{}'", line.trim()))),
+        }
+    }
+    Ok(steps)
+}
+
+/// Reads the messages sent by the server and forwards them on a channel
+fn read_messages(input: Box<dyn Read + Send>, text: bool) -> Receiver<Inbound> {
+    let (sx, rx) = channel();
+    thread::spawn(move || {
+        if text {
+            for line in BufReader::new(input).lines().map_while(Result::ok) {
+                if !line.trim().is_empty() {
+                    let message = line.parse::<Message>().map_err(|e| format!("'{line}': {e}"));
+                    let _ = sx.send(Inbound::Message(message));
+                }
+            }
+        } else {
+            let mut input  = input;
+            let mut buffer = [0_u8; MESSAGE_SIZE];
+            while input.read_exact(&mut buffer).is_ok() {
+                let message = Message::from_bytes(&buffer).map_err(|e| e.to_string());
+                let _ = sx.send(Inbound::Message(message));
+            }
+        }
+        let _ = sx.send(Inbound::Eof);
+    });
+    rx
+}
+
+/// Collects the messages until the deadline is reached, the stream is closed
+/// or a message satisfies the `stop` condition. Returns true unless the
+/// deadline has been reached.
+fn collect_until<F: Fn(&Message) -> bool>(rx: &Receiver<Inbound>, messages: &mut Vec<Result<Message, String>>, deadline: Instant, stop: F) -> bool {
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(timeout) {
+            Ok(Inbound::Message(message)) => {
+                let done = message.as_ref().is_ok_and(&stop);
+                messages.push(message);
+                if done {
+                    return true;
+                }
+            },
+            Ok(Inbound::Eof) | Err(RecvTimeoutError::Disconnected) => return true,
+            Err(RecvTimeoutError::Timeout) => return false,
+        }
+    }
+}
+
+fn is(msgt: MessageType) -> impl Fn(&Message) -> bool {
+    move |message| unsafe { message.msgt == msgt }
+}
+
+fn never(_: &Message) -> bool {
+    false
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let opt = Opt::from_args();
+    let steps = match &opt.script {
+        Some(path) => compile(&fs::read_to_string(path)?, opt.pace)?,
+        None => vec![],
+    };
+
+    let mut child = None;
+    let (input, mut output, mut out): (Box<dyn Read + Send>, Box<dyn Write>, Box<dyn Write>) = match opt.command.split_first() {
+        None => (Box::new(stdin()), Box::new(stdout()), Box::new(io::stderr())),
+        Some((program, args)) => {
+            let mut process = Command::new(program)
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()?;
+            let input  = process.stdout.take().expect("piped stdout");
+            let output = process.stdin.take().expect("piped stdin");
+            child = Some(process);
+            (Box::new(input), Box::new(output), Box::new(stdout()))
+        },
+    };
+
+    let rx = read_messages(input, opt.text_protocol);
+    let mut messages = vec![];
+
+    // the directions are only sent once the client has been registered
+    let registered = collect_until(&rx, &mut messages, Instant::now() + opt.timeout, is(MessageType::REGISTRATION));
+    if registered {
+        for step in steps.iter() {
+            collect_until(&rx, &mut messages, Instant::now() + step.delay, never);
+            let sent = if opt.text_protocol {
+                writeln!(output, "{}", DirectionLine { player: None, direction: step.direction })
+            } else {
+                output.write_all(&(step.direction as u32).to_ne_bytes())
+            };
+            if sent.and_then(|_| output.flush()).is_err() {
+                break;
+            }
+        }
+        if collect_until(&rx, &mut messages, Instant::now() + opt.timeout, is(MessageType::GAME_OVER)) {
+            collect_until(&rx, &mut messages, Instant::now() + GRACE_PERIOD, never);
+        }
+    }
+
+    if let Some(mut child) = child {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    let checks = analyze(&messages);
+    if !report(&mut out, messages.len(), &checks)? {
+        exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! A reference server for the pas cman game. It applies the very same rules
+//! as `game.c` (see the `rules` module) so that you can always compare the
+//! behavior of your own server with this one.
+//!
+//! The server waits for the given number of clients to connect on localhost,
+//! registers each of them, broadcasts the map and then applies the directions
+//! sent by the players until the game is over.
+//!
+//! The second player may also be a villain driven by the computer (see the
+//! `ai` module), in which case it takes one step at a regular pace and the
+//! second client only watches the game.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{io::{self, Read, Write}, net::{Ipv4Addr, TcpListener, TcpStream}, path::PathBuf, sync::mpsc::{channel, RecvTimeoutError, Sender}, thread, time::{Duration, Instant}};
+
+use pas_cman_ipl::{ai::Bot, load_map_file, pascman_protocol::{Direction, Item, Message, HEIGHT, WIDTH}, rules::GameRules, Personality};
+use structopt::StructOpt;
+
+/// pas-cman-server: a reference server for the pas cman game
+#[derive(Debug, StructOpt)]
+#[structopt(name = "pas-cman-server")]
+struct Opt {
+    /// The map to load
+    #[structopt(long, parse(from_os_str), default_value = "resources/map.txt")]
+    map: PathBuf,
+    /// The port to listen on (on localhost)
+    #[structopt(long, default_value = "9090")]
+    port: u16,
+    /// The number of clients to wait for before starting the game. The first
+    /// two clients play the game, the others only watch it
+    #[structopt(long, default_value = "2")]
+    clients: usize,
+    /// Let the computer play the second player as a villain with the given
+    /// personality: chaser, ambusher or scatterer
+    #[structopt(long)]
+    villain: Option<Personality>,
+    /// The number of milliseconds it takes for the villain to take one step
+    #[structopt(long, default_value = "250")]
+    villain_pace: u64,
+}
+
+/// Spawns a thread which forwards all the directions sent by the given player
+/// to the main thread
+fn listen(mut stream: TcpStream, player: Item, sx: Sender<(Item, Direction)>) {
+    thread::spawn(move || {
+        let mut buffer = [0_u8; 4];
+        while stream.read_exact(&mut buffer).is_ok() {
+            match Direction::try_from(u32::from_ne_bytes(buffer)) {
+                Ok(dir) => {
+                    if sx.send((player, dir)).is_err() {
+                        break;
+                    }
+                },
+                Err(value) => eprintln!("{player:?} sent an invalid direction: {value}"),
+            }
+        }
+    });
+}
+
+/// Sends all the messages to all the clients. The clients which cannot be
+/// written to anymore are dropped.
+fn broadcast(clients: &mut Vec<TcpStream>, messages: &[Message]) {
+    clients.retain_mut(|client| {
+        messages.iter().all(|message| client.write_all(&message.to_bytes()).is_ok())
+    });
+}
+
+fn main() -> io::Result<()> {
+    let opt = Opt::from_args();
+    let map = load_map_file(&opt.map, WIDTH, HEIGHT)?;
+    let mut rules = GameRules::new(&map);
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, opt.port))?;
+    eprintln!("waiting for {} clients on port {}", opt.clients, opt.port);
+
+    let (sx, rx) = channel();
+    let mut clients = vec![];
+    for client in 1..=opt.clients {
+        let (mut stream, address) = listener.accept()?;
+        eprintln!("client {client} connected from {address}");
+
+        stream.write_all(&Message::registration(client as u32).to_bytes())?;
+        match client {
+            1 => listen(stream.try_clone()?, Item::PLAYER1, sx.clone()),
+            2 if opt.villain.is_none() => listen(stream.try_clone()?, Item::PLAYER2, sx.clone()),
+            _ => { /* this client only watches the game */ },
+        }
+        clients.push(stream);
+    }
+    drop(sx);
+
+    broadcast(&mut clients, &map);
+    let mut villain = opt.villain.map(|personality| Bot::new(Item::PLAYER2, personality, &rules));
+    let pace = Duration::from_millis(opt.villain_pace);
+    let mut next_step = Instant::now() + pace;
+    let mut heading = Direction::DOWN;
+    while !rules.is_over() {
+        let received = match villain {
+            Some(_) => rx.recv_timeout(next_step.saturating_duration_since(Instant::now())),
+            None    => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let messages = match (received, &mut villain) {
+            (Ok((player, dir)), _) => {
+                if player == Item::PLAYER1 {
+                    heading = dir;
+                }
+                rules.play(player, dir)
+            },
+            (Err(RecvTimeoutError::Timeout), Some(villain)) => {
+                next_step += pace;
+                match villain.play(&rules, heading, false) {
+                    Some(dir) => rules.play(Item::PLAYER2, dir),
+                    None => continue,
+                }
+            },
+            _ => break,
+        };
+        broadcast(&mut clients, &messages);
+    }
+
+    Ok(())
+}
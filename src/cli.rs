@@ -0,0 +1,99 @@
+//! The values of the command line arguments of `pas-cman-ipl` which need to
+//! be validated: each parser returns the message explaining why the value is
+//! rejected, which is what structopt shows to the user.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{fmt, str::FromStr};
+
+use crate::{config::TILE_SIZES, replay::{MAX_SPEED, MIN_SPEED}};
+
+/// The largest width or height of the map
+pub const MAX_DIMENSION: usize = 256;
+
+/// Where the messages come from and where the directions go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transport {
+    /// The standard input and output
+    Stdio,
+    /// A TCP connection to the given address (HOST:PORT)
+    Tcp(String),
+}
+
+impl FromStr for Transport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let valid = |address: &str| address.rsplit_once(':')
+            .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+        match s.strip_prefix("tcp:") {
+            None if s == "stdio" => Ok(Transport::Stdio),
+            Some(address) if valid(address) => Ok(Transport::Tcp(address.to_string())),
+            _ => Err(format!("unknown transport '{s}' (expected stdio or tcp:HOST:PORT)")),
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transport::Stdio => write!(f, "stdio"),
+            Transport::Tcp(address) => write!(f, "tcp:{address}"),
+        }
+    }
+}
+
+impl Transport {
+    /// Checks that no transport has been chosen for the given mode, in which
+    /// the interface neither reads messages from a server nor sends it any
+    /// direction (the default transport is accepted since it is implied).
+    pub fn check_unused(&self, mode: &str) -> Result<(), String> {
+        match self {
+            Transport::Stdio => Ok(()),
+            Transport::Tcp(_) => Err(format!("the transport {self} cannot be used with {mode}")),
+        }
+    }
+}
+
+/// Parses the width or the height of the map
+pub fn parse_dimension(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(n) if (1..=MAX_DIMENSION).contains(&n) => Ok(n),
+        _ => Err(format!("expected a number of tiles from 1 to {MAX_DIMENSION}, got '{s}'")),
+    }
+}
+
+/// Parses the size of a tile
+pub fn parse_tile_size(s: &str) -> Result<u32, String> {
+    match s.parse::<u32>() {
+        Ok(n) if TILE_SIZES.contains(&n) => Ok(n),
+        _ => Err(format!("expected a number of pixels from {} to {}, got '{s}'", TILE_SIZES.start(), TILE_SIZES.end())),
+    }
+}
+
+/// Parses the maximum number of frames per second
+pub fn parse_fps(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(fps) if fps.is_finite() && fps > 0.0 => Ok(fps),
+        _ => Err(format!("expected a positive number of frames per second, got '{s}'")),
+    }
+}
+
+/// Parses the speed of a replay
+pub fn parse_speed(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(speed) if (MIN_SPEED..=MAX_SPEED).contains(&speed) => Ok(speed),
+        _ => Err(format!("expected a speed from {MIN_SPEED} to {MAX_SPEED}, got '{s}'")),
+    }
+}
+
+/// Parses the title of the window
+pub fn parse_title(s: &str) -> Result<String, String> {
+    if s.trim().is_empty() {
+        Err(String::from("the title of the window cannot be empty"))
+    } else {
+        Ok(s.to_string())
+    }
+}
@@ -6,16 +6,66 @@
 //! Date:    March 2023
 //! Licence: MIT 
 
-use bracket_lib::terminal::Point;
+use std::{str::FromStr, sync::Arc};
+
+use bracket_geometry::prelude::Point;
+
+use crate::{animation::{Pose, SpriteSet, IDLE_AFTER}, Map};
 
 /// Le joueur qui joue une partie.
 #[derive(Debug, Clone, Copy)]
 pub struct Player(pub u32);
 
 /// This component indicates that the entity is a character 
-/// (they should be rendered on top of both the map and the food)
-#[derive(Debug, Clone, Copy)]
-pub struct Character(pub &'static [char; 4]);
+/// (they should be rendered on top of both the map and the food).
+/// A character is animated with the frames of its sprite set (see the
+/// `animation` module).
+#[derive(Debug, Clone)]
+pub struct Character {
+    pub sprites: Arc<SpriteSet>,
+    /// What the character is doing
+    pub pose: Pose,
+    /// How long (in milliseconds) the character has been in that pose
+    pub elapsed: f32,
+    /// How long (in milliseconds) the character has been standing still
+    pub still: f32,
+    /// Where the character stood during the previous tick
+    pub last: Option<Position>,
+}
+impl Character {
+    pub fn new(sprites: Arc<SpriteSet>) -> Self {
+        Self { sprites, pose: Pose::Idle(Direction::Down), elapsed: 0.0, still: 0.0, last: None }
+    }
+    /// Lets `dt` milliseconds elapse for the character standing at the given
+    /// position and facing the given direction. This decides its pose.
+    pub fn animate(&mut self, position: Position, direction: Direction, frightened: bool, dying: bool, dt: f32) {
+        if self.last == Some(position) {
+            self.still += dt;
+        } else {
+            self.last  = Some(position);
+            self.still = 0.0;
+        }
+        let pose = if dying {
+            Pose::Dying
+        } else if frightened {
+            Pose::Frightened
+        } else if self.still >= IDLE_AFTER {
+            Pose::Idle(direction)
+        } else {
+            Pose::Walking(direction)
+        };
+        if pose == self.pose {
+            self.elapsed += dt;
+        } else {
+            self.pose    = pose;
+            self.elapsed = 0.0;
+        }
+    }
+    /// Returns the glyph to draw for the character
+    pub fn glyph(&self) -> char {
+        self.sprites.frame(self.pose, self.elapsed)
+    }
+}
 
 /// This is going to be our action hero (aka the pizza guy, aka
 /// the main character w/ which you usually play on old arcade).
@@ -52,8 +102,21 @@ impl Position {
         Point::new(self.x, self.y)
     }
 
-    pub fn is_valid(self) -> bool {
-        self.x < 30 && self.y < 20
+    pub fn from_point(Point { x, y }: Point) -> Self {
+        Position { x: x as usize, y: y as usize }
+    }
+
+    /// Returns the direction to take in order to reach the given adjacent
+    /// position (or None when that position is not a neighbour of this one)
+    pub fn direction_to(self, next: Position) -> Option<Direction> {
+        [Direction::Down, Direction::Right, Direction::Left, Direction::Up]
+            .into_iter()
+            .find(|direction| direction.next(self) == Some(next))
+    }
+
+    /// Returns true iff the position lies on the given map
+    pub fn is_valid(self, map: &Map) -> bool {
+        self.x < map.width && self.y < map.height
     }
 }
 
@@ -69,7 +132,7 @@ pub struct Food(pub char);
 #[derive(Debug, Clone, Copy)]
 pub struct Superfood;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Down = 0,
     Right= 1,
@@ -87,6 +150,42 @@ impl From<Direction> for [u8; 4] {
     }
 }
 
+impl From<Direction> for crate::pascman_protocol::Direction {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Down  => Self::DOWN,
+            Direction::Right => Self::RIGHT,
+            Direction::Left  => Self::LEFT,
+            Direction::Up    => Self::UP,
+        }
+    }
+}
+
+impl From<crate::pascman_protocol::Direction> for Direction {
+    fn from(direction: crate::pascman_protocol::Direction) -> Self {
+        match direction {
+            crate::pascman_protocol::Direction::DOWN  => Self::Down,
+            crate::pascman_protocol::Direction::RIGHT => Self::Right,
+            crate::pascman_protocol::Direction::LEFT  => Self::Left,
+            crate::pascman_protocol::Direction::UP    => Self::Up,
+        }
+    }
+}
+
+impl Direction {
+    /// Returns the position one would reach by taking one step in this
+    /// direction from `pos` (if that position is not outside the map)
+    pub fn next(self, pos: Position) -> Option<Position> {
+        let Position { x, y } = pos;
+        match self {
+            Direction::Down  => Some(Position { x, y: y + 1 }),
+            Direction::Right => Some(Position { x: x + 1, y }),
+            Direction::Left  => x.checked_sub(1).map(|x| Position { x, y }),
+            Direction::Up    => y.checked_sub(1).map(|y| Position { x, y }),
+        }
+    }
+}
+
 /// To tell that a given entity intends to move somewhere in the game.
 #[derive(Debug, Clone, Copy)]
 pub struct IntendsToMove(pub Position);
@@ -104,4 +203,89 @@ pub struct Kill {
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct LeftGame;
\ No newline at end of file
+pub struct LeftGame;
+
+/// The pace at which a character controlled by the computer moves: it
+/// takes one step every `period` milliseconds.
+#[derive(Debug, Clone, Copy)]
+pub struct Pace {
+    pub period: f32,
+    pub elapsed: f32,
+}
+impl Pace {
+    pub fn new(period: f32) -> Self {
+        Self { period, elapsed: 0.0 }
+    }
+    /// Lets `dt` milliseconds elapse and returns true iff it is time to
+    /// take one more step.
+    pub fn tick(&mut self, dt: f32) -> bool {
+        self.elapsed += dt;
+        if self.elapsed >= self.period {
+            self.elapsed -= self.period;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// This component is attached to the villains after the hero ate some
+/// superfood. It tells how many milliseconds they remain edible.
+#[derive(Debug, Clone, Copy)]
+pub struct Frightened(pub f32);
+
+/// This component is attached to a local player whose predicted move has
+/// been corrected by the server. It tells how many milliseconds the
+/// correction remains visible.
+#[derive(Debug, Clone, Copy)]
+pub struct Corrected(pub f32);
+
+/// This component is attached to a villain which has been eaten by the hero
+/// when the game is played offline (and sent back home). It tells how many
+/// milliseconds are left before it starts hunting again.
+#[derive(Debug, Clone, Copy)]
+pub struct Dying(pub f32);
+
+/// One of the four corners of the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomLeft,
+}
+impl Corner {
+    /// Returns the next corner when going clockwise around the map
+    pub fn next(self) -> Corner {
+        match self {
+            Corner::TopLeft     => Corner::TopRight,
+            Corner::TopRight    => Corner::BottomRight,
+            Corner::BottomRight => Corner::BottomLeft,
+            Corner::BottomLeft  => Corner::TopLeft,
+        }
+    }
+}
+
+/// The personality of a villain decides which tile it targets while it
+/// hunts the hero. (Frightened villains always flee, whatever their personality)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Personality {
+    /// Heads straight for the hero
+    Chaser,
+    /// Aims a few tiles ahead of the hero so as to cut its way
+    Ambusher,
+    /// Keeps patrolling from one corner of the map to the next
+    Scatterer(Corner),
+}
+impl FromStr for Personality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chaser"    => Ok(Personality::Chaser),
+            "ambusher"  => Ok(Personality::Ambusher),
+            "scatterer" => Ok(Personality::Scatterer(Corner::TopLeft)),
+            _ => Err(format!("unknown personality '{s}' (expected chaser, ambusher or scatterer)")),
+        }
+    }
+}
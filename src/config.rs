@@ -0,0 +1,462 @@
+//! The configuration of the interface: the keys steering the hero (and those
+//! of the two players in hot-seat mode), the colors of each layer of the
+//! frame, the font of the sprites, the style of the walls, the way the keys
+//! are repeated and buffered (see the `input` module) and the way the
+//! characters glide between tiles (see the `motion` module). It is read from
+//! a TOML file (given with `--config` or found in the XDG configuration
+//! directory) in which every entry is optional:
+//!
+//! ```toml
+//! [keys]
+//! up    = ["Up", "W"]
+//! down  = ["Down", "S"]
+//! left  = ["Left", "A"]
+//! right = ["Right", "D"]
+//!
+//! [hot_seat.player1]   # same entries as [keys], W, A, S and D by default
+//! up = ["W", "Z"]
+//!
+//! [hot_seat.player2]   # same entries as [keys], the arrows by default
+//! up = ["Up", "I"]
+//!
+//! [colors]
+//! map        = { fg = "#3f48cc", bg = "black" }
+//! food       = { fg = "yellow" }
+//! characters = { fg = "white" }
+//! messages   = { fg = "white", bg = "#202020" }
+//!
+//! [font]
+//! file      = "pas-cman-font-32.png"
+//! tile_size = 32
+//!
+//! [walls]
+//! style = "lines"      # or "blocks" (the `0` glyph of the font)
+//!
+//! [input]              # in milliseconds
+//! repeat_delay    = 250
+//! repeat_interval = 100
+//! min_interval    = 50
+//! buffer_turns    = true
+//! turn_buffer     = 1000
+//!
+//! [motion]             # in milliseconds, 0 to jump from tile to tile
+//! glide = 120
+//! ```
+//!
+//! The keys are named after the variants of `VirtualKeyCode` (e.g. `Left`,
+//! `Space` or `W`) and the colors are either `#rrggbb` or one of the names
+//! black, white, grey, red, orange, yellow, tan, green, cyan, blue or magenta.
+//! A key cannot be bound to both hot-seat players.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{env, fmt, fs, ops::RangeInclusive, path::{Path, PathBuf}, str::FromStr};
+
+use bracket_color::prelude::{ColorPair, BLACK, BLUE, CYAN, GREEN, GREY, MAGENTA, ORANGE, RED, RGBA, TAN, WHITE, YELLOW};
+use serde::Deserialize;
+
+use crate::{input::InputSettings, motion::MotionSettings, keys::{key_named, VirtualKeyCode}, walls::WallStyle, Direction};
+
+/// The sizes a tile may have (in pixels)
+pub const TILE_SIZES: RangeInclusive<u32> = 8..=128;
+
+/// The configuration of the interface
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub keys: KeyBindings,
+    pub hot_seat: HotSeat,
+    pub colors: Colors,
+    pub font: FontConfig,
+    pub walls: WallStyle,
+    pub input: InputSettings,
+    pub motion: MotionSettings,
+}
+
+/// The keys steering the hero (several keys may be bound to the same direction)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    pub up: Vec<VirtualKeyCode>,
+    pub down: Vec<VirtualKeyCode>,
+    pub left: Vec<VirtualKeyCode>,
+    pub right: Vec<VirtualKeyCode>,
+}
+
+impl Default for KeyBindings {
+    /// The arrow keys
+    fn default() -> Self {
+        Self {
+            up: vec![VirtualKeyCode::Up],
+            down: vec![VirtualKeyCode::Down],
+            left: vec![VirtualKeyCode::Left],
+            right: vec![VirtualKeyCode::Right],
+        }
+    }
+}
+
+impl KeyBindings {
+    /// The W, A, S and D keys
+    pub fn wasd() -> Self {
+        Self {
+            up: vec![VirtualKeyCode::W],
+            down: vec![VirtualKeyCode::S],
+            left: vec![VirtualKeyCode::A],
+            right: vec![VirtualKeyCode::D],
+        }
+    }
+
+    /// Returns the direction bound to the given key (if any)
+    pub fn direction(&self, key: VirtualKeyCode) -> Option<Direction> {
+        self.bindings()
+            .into_iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(direction, _)| direction)
+    }
+
+    fn bindings(&self) -> [(Direction, &[VirtualKeyCode]); 4] {
+        [
+            (Direction::Up, &self.up),
+            (Direction::Down, &self.down),
+            (Direction::Left, &self.left),
+            (Direction::Right, &self.right),
+        ]
+    }
+}
+
+/// The keys of the two players sharing the keyboard in hot-seat mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotSeat {
+    /// The keys of player 1 and those of player 2
+    pub players: [KeyBindings; 2],
+}
+
+impl Default for HotSeat {
+    /// WASD for player 1 and the arrow keys for player 2
+    fn default() -> Self {
+        Self { players: [KeyBindings::wasd(), KeyBindings::default()] }
+    }
+}
+
+impl HotSeat {
+    /// Returns the player (1 or 2) steered by the given key and the direction
+    /// bound to that key (if any)
+    pub fn direction(&self, key: VirtualKeyCode) -> Option<(u32, Direction)> {
+        self.players.iter()
+            .zip(1..)
+            .find_map(|(keys, player)| keys.direction(key).map(|direction| (player, direction)))
+    }
+}
+
+/// The colors used to draw each layer of the frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Colors {
+    /// The walls and the floor
+    pub map: ColorPair,
+    /// The food and the superfood
+    pub food: ColorPair,
+    /// The hero and the villains
+    pub characters: ColorPair,
+    /// The box shown when the game is over (and the background of its text)
+    pub messages: ColorPair,
+}
+
+impl Default for Colors {
+    /// White on black
+    fn default() -> Self {
+        let white_on_black = ColorPair::new(WHITE, BLACK);
+        Self { map: white_on_black, food: white_on_black, characters: white_on_black, messages: white_on_black }
+    }
+}
+
+/// The font holding the sprites of the game. What is not configured here is
+/// given on the command line (or takes its default value).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FontConfig {
+    /// The file of the font (in the resources directory)
+    pub file: Option<String>,
+    /// The size of a tile (in pixels)
+    pub tile_size: Option<u32>,
+}
+
+/// The reason why a configuration file could not be loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration {}: {}", self.path.display(), self.reason)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// The file where the configuration is looked for when none is given:
+    /// `$XDG_CONFIG_HOME/pas-cman/config.toml` (or `~/.config/pas-cman/config.toml`)
+    pub fn default_path() -> Option<PathBuf> {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .map(|dir| dir.join("pas-cman").join("config.toml"))
+    }
+
+    /// Loads the configuration from the given file. When no file is given, it
+    /// is loaded from the default path (if there is a file at that place).
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        match path {
+            Some(path) => Self::from_file(path),
+            None => match Self::default_path() {
+                Some(path) if path.is_file() => Self::from_file(&path),
+                _ => Ok(Self::default()),
+            },
+        }
+    }
+
+    /// Loads the configuration from the given file
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let error = |reason: String| ConfigError { path: path.to_path_buf(), reason };
+        let text = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        text.parse().map_err(error)
+    }
+}
+
+impl FromStr for Config {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let raw = toml::from_str::<RawConfig>(text).map_err(|e| e.to_string())?;
+        Ok(Config {
+            keys: raw.keys.resolve("keys", KeyBindings::default())?,
+            hot_seat: raw.hot_seat.resolve()?,
+            colors: raw.colors.resolve()?,
+            font: raw.font.resolve()?,
+            walls: raw.walls.resolve()?,
+            input: raw.input.resolve()?,
+            motion: raw.motion.resolve(),
+        })
+    }
+}
+
+// The configuration as it is written in the file: everything is optional and
+// still has to be checked.
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawConfig {
+    keys: RawKeys,
+    hot_seat: RawHotSeat,
+    colors: RawColors,
+    font: RawFont,
+    walls: RawWalls,
+    input: RawInput,
+    motion: RawMotion,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawKeys {
+    up: Option<Vec<String>>,
+    down: Option<Vec<String>>,
+    left: Option<Vec<String>>,
+    right: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawHotSeat {
+    player1: RawKeys,
+    player2: RawKeys,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawColors {
+    map: RawColorPair,
+    food: RawColorPair,
+    characters: RawColorPair,
+    messages: RawColorPair,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawColorPair {
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawFont {
+    file: Option<String>,
+    tile_size: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawWalls {
+    style: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawInput {
+    repeat_delay: Option<u32>,
+    repeat_interval: Option<u32>,
+    min_interval: Option<u32>,
+    buffer_turns: Option<bool>,
+    turn_buffer: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawMotion {
+    glide: Option<u32>,
+}
+
+impl RawKeys {
+    /// Resolves the keys of the given section (the directions which are not
+    /// configured keep their default keys)
+    fn resolve(self, section: &str, defaults: KeyBindings) -> Result<KeyBindings, String> {
+        let keys = |entry: &str, names: Option<Vec<String>>, default: Vec<VirtualKeyCode>| match names {
+            None => Ok(default),
+            Some(names) if names.is_empty() => Err(format!("{section}.{entry}: at least one key is needed")),
+            Some(names) => names.iter()
+                .map(|name| key_named(name).ok_or_else(|| format!("{section}.{entry}: unknown key '{name}'")))
+                .collect(),
+        };
+        let bindings = KeyBindings {
+            up: keys("up", self.up, defaults.up)?,
+            down: keys("down", self.down, defaults.down)?,
+            left: keys("left", self.left, defaults.left)?,
+            right: keys("right", self.right, defaults.right)?,
+        };
+
+        // a key cannot steer the hero in two directions at once
+        let all = bindings.bindings();
+        for (i, (direction, keys)) in all.iter().enumerate() {
+            for (other, other_keys) in &all[i + 1..] {
+                if let Some(key) = keys.iter().find(|key| other_keys.contains(key)) {
+                    return Err(format!("{section}: {key:?} is bound to both {direction:?} and {other:?}"));
+                }
+            }
+        }
+        Ok(bindings)
+    }
+}
+
+impl RawHotSeat {
+    fn resolve(self) -> Result<HotSeat, String> {
+        let [player1, player2] = HotSeat::default().players;
+        let player1 = self.player1.resolve("hot_seat.player1", player1)?;
+        let player2 = self.player2.resolve("hot_seat.player2", player2)?;
+
+        // a key cannot steer both players
+        let shared = player1.bindings().into_iter()
+            .flat_map(|(_, keys)| keys)
+            .find(|key| player2.direction(**key).is_some());
+        if let Some(key) = shared {
+            return Err(format!("hot_seat: {key:?} is bound to both player1 and player2"));
+        }
+        Ok(HotSeat { players: [player1, player2] })
+    }
+}
+
+impl RawColors {
+    fn resolve(self) -> Result<Colors, String> {
+        Ok(Colors {
+            map: self.map.resolve("map")?,
+            food: self.food.resolve("food")?,
+            characters: self.characters.resolve("characters")?,
+            messages: self.messages.resolve("messages")?,
+        })
+    }
+}
+
+impl RawColorPair {
+    fn resolve(self, layer: &str) -> Result<ColorPair, String> {
+        let color = |entry: &str, name: Option<String>, default: (u8, u8, u8)| match name {
+            None => Ok(RGBA::from(default)),
+            Some(name) => parse_color(&name).ok_or_else(|| format!(
+                "colors.{layer}.{entry}: unknown color '{name}' (expected #rrggbb or a name such as white)")),
+        };
+        Ok(ColorPair::new(color("fg", self.fg, WHITE)?, color("bg", self.bg, BLACK)?))
+    }
+}
+
+impl RawFont {
+    fn resolve(self) -> Result<FontConfig, String> {
+        if self.file.as_deref().is_some_and(|file| file.trim().is_empty()) {
+            return Err(String::from("font.file: the name of the font cannot be empty"));
+        }
+        if let Some(size) = self.tile_size.filter(|size| !TILE_SIZES.contains(size)) {
+            return Err(format!("font.tile_size: expected a number of pixels from {} to {}, got {size}",
+                TILE_SIZES.start(), TILE_SIZES.end()));
+        }
+        Ok(FontConfig { file: self.file, tile_size: self.tile_size })
+    }
+}
+
+impl RawWalls {
+    fn resolve(self) -> Result<WallStyle, String> {
+        match self.style.as_deref() {
+            None | Some("lines") => Ok(WallStyle::Lines),
+            Some("blocks")       => Ok(WallStyle::Blocks),
+            Some(style) => Err(format!("walls.style: unknown style '{style}' (expected lines or blocks)")),
+        }
+    }
+}
+
+impl RawInput {
+    fn resolve(self) -> Result<InputSettings, String> {
+        if self.repeat_interval == Some(0) {
+            return Err(String::from("input.repeat_interval: a key cannot be repeated every 0 ms"));
+        }
+        let defaults = InputSettings::default();
+        let millis = |value: Option<u32>, default: f32| value.map_or(default, |ms| ms as f32);
+        Ok(InputSettings {
+            repeat_delay: millis(self.repeat_delay, defaults.repeat_delay),
+            repeat_interval: millis(self.repeat_interval, defaults.repeat_interval),
+            min_interval: millis(self.min_interval, defaults.min_interval),
+            buffer_turns: self.buffer_turns.unwrap_or(defaults.buffer_turns),
+            turn_buffer: millis(self.turn_buffer, defaults.turn_buffer),
+        })
+    }
+}
+
+impl RawMotion {
+    fn resolve(self) -> MotionSettings {
+        let defaults = MotionSettings::default();
+        MotionSettings { glide: self.glide.map_or(defaults.glide, |ms| ms as f32) }
+    }
+}
+
+/// Parses a color written as `#rrggbb` or named after one of the usual colors
+fn parse_color(text: &str) -> Option<RGBA> {
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(RGBA::from((channel(0)?, channel(2)?, channel(4)?)));
+    }
+    let color = match text.to_ascii_lowercase().as_str() {
+        "black"          => BLACK,
+        "white"          => WHITE,
+        "grey" | "gray"  => GREY,
+        "red"            => RED,
+        "orange"         => ORANGE,
+        "yellow"         => YELLOW,
+        "tan"            => TAN,
+        "green"          => GREEN,
+        "cyan"           => CYAN,
+        "blue"           => BLUE,
+        "magenta"        => MAGENTA,
+        _ => return None,
+    };
+    Some(RGBA::from(color))
+}
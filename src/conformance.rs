@@ -0,0 +1,211 @@
+//! The report of the conformance test harness (`pas-cman-check`). Once the
+//! stream of messages sent by a server has been collected, it is analyzed
+//! to check that:
+//!
+//! * every message can be decoded and conforms to the protocol (see the
+//!   `validator` module: map before the game, unique ids, legal moves, ...)
+//! * every tile is spawned exactly once,
+//! * the client is registered (exactly once) and both players are spawned,
+//! * the food counts are consistent,
+//! * there is exactly one GAME_OVER and its winner is plausible.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{collections::{HashMap, HashSet}, io::{self, Write}};
+
+use crate::{
+    pascman_protocol::{Item, Message, MessageType, Position, PLAYER1_ID, PLAYER2_ID},
+    rules::{FOOD_POINTS, SUPERFOOD_POINTS},
+    validator::{ValidationMode, Validator},
+};
+
+/// One of the checks of the report
+pub struct Check {
+    pub name: &'static str,
+    /// The failures along with the index of the offending message (if any)
+    pub failures: Vec<(Option<usize>, String)>,
+}
+impl Check {
+    pub fn new(name: &'static str) -> Self {
+        Self { name, failures: vec![] }
+    }
+    pub fn fail(&mut self, index: Option<usize>, reason: String) {
+        self.failures.push((index, reason));
+    }
+}
+
+/// Checks all the invariants on the messages that have been received
+pub fn analyze(messages: &[Result<Message, String>]) -> Vec<Check> {
+    let mut decoding     = Check::new("every message can be decoded");
+    let mut protocol     = Check::new("every message conforms to the protocol");
+    let mut tiles        = Check::new("every tile is spawned exactly once");
+    let mut registration = Check::new("the client is registered exactly once");
+    let mut players      = Check::new("both players are spawned exactly once");
+    let mut food         = Check::new("the food counts are consistent");
+    let mut game_over    = Check::new("exactly one GAME_OVER with a plausible winner");
+
+    let mut validator     = Validator::silent(ValidationMode::Lenient);
+    let mut validated     = vec![];
+    let mut tile_spawns   = HashMap::<(u32, u32), usize>::new();
+    let mut registrations = vec![];
+    let mut positions     = HashMap::<u32, (Item, Position)>::new();
+    let mut player_spawns = [0, 0];
+    let mut items         = vec![];
+    let mut food_points   = HashMap::<u32, u32>::new();
+    let mut eaten         = HashSet::<u32>::new();
+    let mut scores        = [0_u32, 0_u32];
+    let mut game_overs    = vec![];
+
+    for (index, message) in messages.iter().enumerate() {
+        let message = match message {
+            Ok(message) => message,
+            Err(reason) => {
+                decoding.fail(Some(index), reason.clone());
+                continue;
+            },
+        };
+        validated.push(index);
+        validator.check(message);
+
+        unsafe {
+            match message.msgt {
+                MessageType::REGISTRATION => registrations.push((index, message.registration.player)),
+                MessageType::SPAWN => {
+                    let spawn = message.spawn;
+                    let Position { x, y } = spawn.pos;
+                    match spawn.item {
+                        Item::WALL | Item::FLOOR => {
+                            if let Some(first) = tile_spawns.get(&(x, y)) {
+                                tiles.fail(Some(index), format!("the tile at ({x},{y}) has already been spawned by #{first}"));
+                            } else {
+                                tile_spawns.insert((x, y), index);
+                            }
+                        },
+                        Item::FOOD | Item::SUPERFOOD => {
+                            let points = if spawn.item == Item::FOOD { FOOD_POINTS } else { SUPERFOOD_POINTS };
+                            food_points.insert(spawn.id, points);
+                        },
+                        Item::PLAYER1 => player_spawns[0] += 1,
+                        Item::PLAYER2 => player_spawns[1] += 1,
+                    }
+                    if !matches!(spawn.item, Item::WALL | Item::FLOOR) {
+                        items.push((index, spawn.item, spawn.pos));
+                    }
+                    positions.insert(spawn.id, (spawn.item, spawn.pos));
+                },
+                MessageType::MOVEMENT => {
+                    let movement = message.movement;
+                    if let Some((_, pos)) = positions.get_mut(&movement.id) {
+                        *pos = movement.pos;
+                    }
+                },
+                MessageType::EAT_FOOD => {
+                    let (eater, eaten_food) = (message.eat_food.eater, message.eat_food.food);
+                    match food_points.get(&eaten_food) {
+                        None => food.fail(Some(index), format!("{eaten_food} is not some food")),
+                        Some(_) if eaten.contains(&eaten_food) => food.fail(Some(index), format!("the food {eaten_food} has already been eaten")),
+                        Some(points) => {
+                            eaten.insert(eaten_food);
+                            match eater {
+                                PLAYER1_ID => scores[0] += points,
+                                PLAYER2_ID => scores[1] += points,
+                                _ => food.fail(Some(index), format!("{eater} is not a player")),
+                            }
+                        },
+                    }
+                },
+                MessageType::GAME_OVER => {
+                    let remaining = food_points.len() - eaten.len();
+                    game_overs.push((index, message.game_over.winner, remaining, scores));
+                },
+            }
+        }
+    }
+
+    for violation in validator.violations() {
+        let index = validated[violation.index];
+        protocol.fail(Some(index), violation.reason.clone());
+    }
+
+    for (index, item, Position { x, y }) in items {
+        let on_floor = tile_spawns.get(&(x, y))
+            .and_then(|first| messages[*first].as_ref().ok())
+            .is_some_and(|m| unsafe { m.spawn.item == Item::FLOOR });
+        if !on_floor {
+            tiles.fail(Some(index), format!("the {item} at ({x},{y}) is not on a floor tile"));
+        }
+    }
+
+    match registrations.as_slice() {
+        [] => registration.fail(None, "no REGISTRATION received".to_string()),
+        [(index, player)] => {
+            if !(1..=2).contains(player) {
+                registration.fail(Some(*index), format!("registered as player {player} (expected 1 or 2)"));
+            }
+        },
+        [_, others @ ..] => {
+            for (index, _) in others {
+                registration.fail(Some(*index), "registered more than once".to_string());
+            }
+        },
+    }
+
+    for (n, count) in player_spawns.iter().enumerate() {
+        if *count != 1 {
+            players.fail(None, format!("player {} has been spawned {count} time(s)", n + 1));
+        }
+    }
+
+    match game_overs.as_slice() {
+        [] => {
+            game_over.fail(None, "no GAME_OVER received".to_string());
+            if !food_points.is_empty() && food_points.len() == eaten.len() {
+                food.fail(None, "all the food has been eaten but the game is not over".to_string());
+            }
+        },
+        [(index, winner, remaining, scores), others @ ..] => {
+            let expected = if scores[0] > scores[1] { 1 } else { 2 };
+            if *winner != expected {
+                game_over.fail(Some(*index), format!("the winner is {winner} but the scores are {} vs {} (expected {expected})", scores[0], scores[1]));
+            }
+            if *remaining > 0 {
+                let collided = match (positions.get(&PLAYER1_ID), positions.get(&PLAYER2_ID)) {
+                    (Some((_, p1)), Some((_, p2))) => p1.x.abs_diff(p2.x) + p1.y.abs_diff(p2.y) <= 1,
+                    _ => false,
+                };
+                if !collided {
+                    food.fail(Some(*index), format!("the game is over while {remaining} food remain(s) and the players did not collide"));
+                }
+            }
+            for (index, ..) in others {
+                game_over.fail(Some(*index), "more than one GAME_OVER".to_string());
+            }
+        },
+    }
+
+    vec![decoding, protocol, tiles, registration, players, food, game_over]
+}
+
+/// Writes the report and returns true iff all checks passed
+pub fn report(out: &mut dyn Write, received: usize, checks: &[Check]) -> io::Result<bool> {
+    writeln!(out, "pas-cman-check: {received} message(s) received")?;
+    for check in checks {
+        let verdict = if check.failures.is_empty() { "PASS" } else { "FAIL" };
+        writeln!(out, "  [{verdict}] {}", check.name)?;
+        for (index, reason) in check.failures.iter() {
+            match index {
+                Some(index) => writeln!(out, "         #{index}: {reason}")?,
+                None        => writeln!(out, "         {reason}")?,
+            }
+        }
+    }
+    let failed = checks.iter().filter(|c| !c.failures.is_empty()).count();
+    if failed == 0 {
+        writeln!(out, "RESULT: PASS")?;
+    } else {
+        writeln!(out, "RESULT: FAIL ({failed} of {} checks failed)", checks.len())?;
+    }
+    Ok(failed == 0)
+}
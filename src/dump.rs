@@ -0,0 +1,149 @@
+//! A pretty printer for the binary protocol of the pas cman game. It reads a
+//! raw stream of messages and writes one decoded line per message, e.g.:
+//!
+//! ```text
+//! #42 MOVEMENT id=1800 -> (12,9)
+//! ```
+//!
+//! Invalid discriminants, positions lying outside of the map and trailing
+//! partial records are flagged (and counted as problems).
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::io::{self, ErrorKind, Read, Write};
+
+use serde::Serialize;
+
+use crate::pascman_protocol::{Message, MessageType, Position, MESSAGE_SIZE};
+
+/// What has been read at some point of the stream
+#[derive(Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "UPPERCASE")]
+pub enum Record {
+    /// A message that could be decoded (along with the problems it shows)
+    Valid { message: Message, warnings: Vec<&'static str> },
+    /// A record that could not be decoded
+    Invalid { error: String, bytes: Vec<u8> },
+    /// The bytes left at the end of the stream
+    Truncated { bytes: Vec<u8> },
+}
+impl Record {
+    /// Returns true iff the record shows some problem
+    pub fn is_problem(&self) -> bool {
+        !matches!(self, Record::Valid { warnings, .. } if warnings.is_empty())
+    }
+}
+
+/// One line of the JSON output
+#[derive(Serialize)]
+struct Line<'a> {
+    index: usize,
+    #[serde(flatten)]
+    record: &'a Record,
+}
+
+/// Reads as many bytes as possible (at most buffer.len()) from the input
+/// and returns the number of bytes that have been read
+fn read_record(input: &mut dyn Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match input.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Decodes one record and checks it for problems (the positions must lie on
+/// a map of the given size)
+pub fn decode(bytes: &[u8; MESSAGE_SIZE], width: usize, height: usize) -> Record {
+    match Message::from_bytes(bytes) {
+        Err(e) => Record::Invalid { error: e.to_string(), bytes: bytes.to_vec() },
+        Ok(message) => {
+            let on_map = |pos: Position| (pos.x as usize) < width && (pos.y as usize) < height;
+            let mut warnings = vec![];
+            let pos = unsafe {
+                match message.msgt {
+                    MessageType::SPAWN    => Some(message.spawn.pos),
+                    MessageType::MOVEMENT => Some(message.movement.pos),
+                    _ => None,
+                }
+            };
+            if pos.is_some_and(|pos| !on_map(pos)) {
+                warnings.push("position out of range");
+            }
+            Record::Valid { message, warnings }
+        },
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect::<Vec<_>>().join(" ")
+}
+
+/// Formats the record as a human readable line
+pub fn to_text(index: usize, record: &Record) -> String {
+    match record {
+        Record::Valid { message, warnings } => {
+            let text = unsafe {
+                match message.msgt {
+                    MessageType::REGISTRATION => format!("REGISTRATION player={}", message.registration.player),
+                    MessageType::SPAWN => {
+                        let s = message.spawn;
+                        format!("SPAWN id={} {:?} at ({},{})", s.id, s.item, s.pos.x, s.pos.y)
+                    },
+                    MessageType::MOVEMENT => {
+                        let m = message.movement;
+                        format!("MOVEMENT id={} -> ({},{})", m.id, m.pos.x, m.pos.y)
+                    },
+                    MessageType::EAT_FOOD  => format!("EAT_FOOD eater={} food={}", message.eat_food.eater, message.eat_food.food),
+                    MessageType::GAME_OVER => format!("GAME_OVER winner={}", message.game_over.winner),
+                }
+            };
+            let warnings = warnings.iter().map(|w| format!("  !! {w}")).collect::<String>();
+            format!("#{index} {text}{warnings}")
+        },
+        Record::Invalid { error, bytes } => format!("#{index} INVALID {error} [{}]", hex(bytes)),
+        Record::Truncated { bytes } => format!("#{index} TRUNCATED {} trailing bytes [{}]", bytes.len(), hex(bytes)),
+    }
+}
+
+/// Formats the record as a JSON object
+pub fn to_json(index: usize, record: &Record) -> String {
+    serde_json::to_string(&Line { index, record })
+        .expect("a record can always be serialized")
+}
+
+/// Decodes the whole stream read from the input (for a map of the given size)
+/// and writes one line per record on the output (as JSON objects when `json`
+/// is set). Returns the number of problems that were found.
+pub fn dump(input: &mut dyn Read, output: &mut dyn Write, json: bool, width: usize, height: usize) -> io::Result<usize> {
+    let mut problems = 0;
+    let mut buffer   = [0_u8; MESSAGE_SIZE];
+    for index in 0.. {
+        let size = read_record(input, &mut buffer)?;
+        let record = match size {
+            0 => break,
+            MESSAGE_SIZE => decode(&buffer, width, height),
+            _ => Record::Truncated { bytes: buffer[..size].to_vec() },
+        };
+
+        if record.is_problem() {
+            problems += 1;
+        }
+        if json {
+            writeln!(output, "{}", to_json(index, &record))?;
+        } else {
+            writeln!(output, "{}", to_text(index, &record))?;
+        }
+        if size < MESSAGE_SIZE {
+            break;
+        }
+    }
+    Ok(problems)
+}
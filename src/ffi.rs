@@ -0,0 +1,264 @@
+//! The C API of the game (see `pascman_gui.h`). Rather than forking and
+//! piping messages into `pas-cman-ipl`, a C program can link against the
+//! library (`libpas_cman_ipl.so`) and drive the interface directly:
+//!
+//! * `pascman_gui_open` prepares the interface on the calling thread;
+//! * `pascman_gui_run` runs the interface on that very same thread (it only
+//!   returns once the interface is closed);
+//! * `pascman_gui_push` sends a message to the interface;
+//! * `pascman_gui_poll_direction` fetches the directions chosen by the user;
+//! * `pascman_gui_close` stops the interface.
+//!
+//! The event loop thus runs on the thread of the caller (which is meant to be
+//! the main thread, since some platforms refuse to open a window anywhere
+//! else) while the program plays the game from another thread: pushing,
+//! polling and closing can be done from any thread.
+//!
+//! There is at most one interface per process. Note that bracket-lib ends
+//! the process when its window is closed: in windowed mode, closing the
+//! interface is thus meant to happen when the program terminates (this is
+//! not the case in headless mode, which tests rely on).
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, CStr},
+    ptr,
+    sync::{atomic::{AtomicBool, Ordering}, mpsc::{channel, Receiver, Sender, TryRecvError}, Arc, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    logging::{self, LogLevel},
+    pascman_protocol::{Direction, Item, Message, MessageType},
+    validator::{ValidationMode, Validator},
+    DirectionSink, State,
+};
+
+/// The duration of one tick when the interface runs without any window
+const HEADLESS_FRAME_TIME: Duration = Duration::from_millis(33);
+
+/// The options of the interface (`struct PascmanGuiOptions` in `pascman_gui.h`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PascmanGuiOptions {
+    /// The directory where the fonts are found (`PAS_RESOURCES` or `resources/` when NULL)
+    pub resources: *const c_char,
+    /// Run the interface without any window
+    pub headless: bool,
+    /// Reject the messages which violate the protocol
+    pub strict: bool,
+}
+
+/// The interface which is currently open (if any)
+static GUI: Mutex<Option<Gui>> = Mutex::new(None);
+
+thread_local! {
+    /// The interface which has been opened on this thread and is waiting to
+    /// be run (the state of the game cannot be sent across threads)
+    static INTERFACE: RefCell<Option<Interface>> = const { RefCell::new(None) };
+}
+
+/// The C side of an open interface
+struct Gui {
+    /// Where the messages pushed by the C program are sent
+    messages: Sender<Message>,
+    /// The directions chosen by the user
+    directions: Receiver<Direction>,
+    /// Tells the interface to stop
+    quit: Arc<AtomicBool>,
+}
+
+/// The interface itself, on the thread which opened it
+struct Interface {
+    options: Options,
+    state: State,
+}
+
+/// The options once they have been read from C
+struct Options {
+    resources: Option<String>,
+    headless: bool,
+    strict: bool,
+}
+
+/// Gives access to the state of the interface which has been opened (and is
+/// not running yet) on the current thread. This is how a Rust program
+/// embedding the library can drive the interface tick by tick rather than
+/// calling `pascman_gui_run`.
+pub fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> Option<R> {
+    INTERFACE.with(|interface| interface.borrow_mut().as_mut().map(|interface| f(&mut interface.state)))
+}
+
+/// Runs the interface until it is told to quit (or its window is closed)
+fn run(Interface { options, mut state }: Interface) -> Result<(), String> {
+    if options.headless {
+        let frame_time_ms = HEADLESS_FRAME_TIME.as_secs_f32() * 1000.0;
+        while !state.quit.load(Ordering::Relaxed) {
+            state.update(None, frame_time_ms);
+            thread::sleep(HEADLESS_FRAME_TIME);
+        }
+        Ok(())
+    } else {
+        windowed(options, state)
+    }
+}
+
+/// Opens the window and runs the interface in it
+#[cfg(feature = "gui")]
+fn windowed(options: Options, state: State) -> Result<(), String> {
+    use crate::{main_loop, window};
+
+    let mut window = window::WindowOptions::default();
+    if let Some(resources) = options.resources {
+        window.resources = resources;
+    }
+    let context = window::open_window(&window).map_err(|e| e.to_string())?;
+    main_loop(context, state).map_err(|e| e.to_string())
+}
+
+/// Without the `gui` feature, there is no window to open
+#[cfg(not(feature = "gui"))]
+fn windowed(_options: Options, _state: State) -> Result<(), String> {
+    Err(String::from("built without the `gui` feature: use the headless mode"))
+}
+
+/// Prepares the interface on the calling thread, which is the one that must
+/// then call `pascman_gui_run`. When `options` is NULL, the interface opens a
+/// window with the default options. Returns 0 when the interface is ready, or
+/// -1 if it is already open.
+///
+/// # Safety
+/// `options` must either be NULL or point to valid options whose `resources`
+/// is either NULL or a valid nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pascman_gui_open(options: *const PascmanGuiOptions) -> c_int {
+    let mut gui = GUI.lock().unwrap_or_else(PoisonError::into_inner);
+    if gui.is_some() {
+        return -1;
+    }
+
+    let options = match options.as_ref() {
+        None => Options { resources: None, headless: false, strict: false },
+        Some(options) => Options {
+            resources: (!options.resources.is_null())
+                .then(|| CStr::from_ptr(options.resources).to_string_lossy().into_owned()),
+            headless: options.headless,
+            strict: options.strict,
+        },
+    };
+
+    let (messages, rx)     = channel();
+    let (sink, directions) = channel();
+    let quit = Arc::new(AtomicBool::new(false));
+
+    let mut state = State::new(rx);
+    state.resources.insert(DirectionSink::Channel(sink));
+    state.quit = Arc::clone(&quit);
+    if options.strict {
+        state.resources.insert(Validator::new(ValidationMode::Strict));
+    }
+
+    INTERFACE.with(|interface| *interface.borrow_mut() = Some(Interface { options, state }));
+    *gui = Some(Gui { messages, directions, quit });
+    0
+}
+
+/// Runs the interface on the calling thread, until it is closed. Returns 0
+/// once the interface has been closed, or -1 if no interface has been opened
+/// on this thread or it could not be started (e.g. its window).
+#[no_mangle]
+pub extern "C" fn pascman_gui_run() -> c_int {
+    let Some(interface) = INTERFACE.with(|interface| interface.borrow_mut().take()) else {
+        return -1;
+    };
+    match run(interface) {
+        Ok(()) => 0,
+        Err(e) => {
+            logging::log(LogLevel::Error, format_args!("pascman_gui_run: {e}"));
+            -1
+        },
+    }
+}
+
+/// Reads the message written by the C program through the fields of the
+/// union. The enums are checked before the fields holding them are read
+/// since C does not guarantee their validity.
+unsafe fn read_message(message: *const Message) -> Option<Message> {
+    let msgt = ptr::addr_of!((*message).msgt).cast::<u32>().read();
+    let message = match MessageType::try_from(msgt).ok()? {
+        MessageType::REGISTRATION => Message::registration((*message).registration.player),
+        MessageType::SPAWN => {
+            let spawn = ptr::addr_of!((*message).spawn);
+            let item  = Item::try_from(ptr::addr_of!((*spawn).item).cast::<u32>().read()).ok()?;
+            Message::spawn((*spawn).id, item, (*spawn).pos)
+        },
+        MessageType::MOVEMENT  => Message::movement((*message).movement.id, (*message).movement.pos),
+        MessageType::EAT_FOOD  => Message::eat_food((*message).eat_food.eater, (*message).eat_food.food),
+        MessageType::GAME_OVER => Message::game_over((*message).game_over.winner),
+    };
+    Some(message)
+}
+
+/// Sends a message to the interface (from any thread). Returns 0 when the
+/// message has been sent, or -1 if it is invalid or the interface is not open.
+///
+/// # Safety
+/// `message` must either be NULL or point to a readable `union Message`.
+#[no_mangle]
+pub unsafe extern "C" fn pascman_gui_push(message: *const Message) -> c_int {
+    if message.is_null() {
+        return -1;
+    }
+    let Some(message) = read_message(message) else {
+        return -1;
+    };
+
+    let gui = GUI.lock().unwrap_or_else(PoisonError::into_inner);
+    match gui.as_ref().map(|gui| gui.messages.send(message)) {
+        Some(Ok(())) => 0,
+        _ => -1,
+    }
+}
+
+/// Fetches the next direction chosen by the user (without blocking, from any
+/// thread). Returns 1 when a direction has been written to `direction`, 0 when
+/// the user has not chosen any, or -1 if the interface is not open.
+///
+/// # Safety
+/// `direction` must either be NULL or point to a writable `enum Direction`.
+#[no_mangle]
+pub unsafe extern "C" fn pascman_gui_poll_direction(direction: *mut Direction) -> c_int {
+    if direction.is_null() {
+        return -1;
+    }
+    let gui = GUI.lock().unwrap_or_else(PoisonError::into_inner);
+    let Some(gui) = gui.as_ref() else {
+        return -1;
+    };
+    match gui.directions.try_recv() {
+        Ok(chosen) => {
+            direction.write(chosen);
+            1
+        },
+        Err(TryRecvError::Empty) => 0,
+        Err(TryRecvError::Disconnected) => -1,
+    }
+}
+
+/// Stops the interface (from any thread): `pascman_gui_run` returns as soon
+/// as possible. Nothing happens when the interface is not open. In windowed
+/// mode, this ends the process.
+#[no_mangle]
+pub extern "C" fn pascman_gui_close() {
+    let gui = GUI.lock().unwrap_or_else(PoisonError::into_inner).take();
+    if let Some(Gui { quit, .. }) = gui {
+        quit.store(true, Ordering::Relaxed);
+    }
+    // an interface which has not been run yet is simply forgotten
+    INTERFACE.with(|interface| interface.borrow_mut().take());
+}
@@ -0,0 +1,427 @@
+//! The frame is what the game draws upon each tick. Rather than drawing
+//! straight onto the consoles of the window, the rendering systems draw onto
+//! the four layers of the frame (the same as the consoles of the window):
+//!
+//! * layer 0: the map (one cell per tile)
+//! * layer 1: the food (one cell per tile)
+//! * layer 2: the characters (sprites which may lie between two tiles)
+//! * layer 3: the messages (2x2 cells per tile, written with a 8x8 font)
+//!
+//! Unlike the other layers, the map layer is not erased upon each tick: it is
+//! only drawn again (and copied onto the window) when the map has changed.
+//!
+//! The window simply copies the frame onto its consoles, while the headless
+//! mode composites the layers into a text grid which can be dumped as plain
+//! ASCII or with ANSI colors. In that text grid, a tile is two characters wide
+//! (so that the map does not look squashed in a terminal) and a row of text
+//! covers two rows of the message layer (only the first of which is shown,
+//! unless nothing has been drawn on it). A sprite lying between two tiles is
+//! shown on the nearest one, and the characters are shown as in the map files
+//! (`@` for the hero, `!` for the villain) whatever the glyph of their sprite.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{fmt::Write, str::FromStr};
+
+use bracket_color::prelude::{ColorPair, BLACK, WHITE};
+use bracket_geometry::prelude::{Point, Rect};
+
+use crate::walls::is_wall_glyph;
+#[cfg(feature = "gui")]
+use bracket_color::prelude::RGBA;
+#[cfg(feature = "gui")]
+use bracket_geometry::prelude::{PointF, Radians};
+#[cfg(feature = "gui")]
+use bracket_lib::terminal::{to_cp437, DrawBatch};
+
+/// The layer where the map is drawn
+pub const MAP_LAYER: usize = 0;
+/// The layer where the food is drawn
+pub const FOOD_LAYER: usize = 1;
+/// The layer where the characters are drawn
+pub const CHARACTER_LAYER: usize = 2;
+/// The layer where the messages are drawn
+pub const MESSAGE_LAYER: usize = 3;
+/// The number of layers of a frame
+pub const NB_LAYERS: usize = 4;
+
+/// What has been drawn on one cell of a layer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub color: ColorPair,
+}
+
+/// The character represented by a sprite
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Figure {
+    /// The first player
+    Hero,
+    /// The second player
+    Villain,
+}
+
+impl Figure {
+    /// The character standing for the figure in the text dumps (the same as
+    /// in the map files)
+    pub fn symbol(self) -> char {
+        match self {
+            Figure::Hero    => '@',
+            Figure::Villain => '!',
+        }
+    }
+}
+
+/// A glyph drawn at a position which is not necessarily a whole tile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub x: f32,
+    pub y: f32,
+    pub glyph: char,
+    pub color: ColorPair,
+    /// The character represented by the sprite (if any)
+    pub figure: Option<Figure>,
+}
+
+/// One layer of the frame: a grid of cells on which nothing might have been
+/// drawn, plus the sprites drawn on top of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub width: usize,
+    pub height: usize,
+    /// Whether the background of the layer hides the layers below it
+    pub opaque: bool,
+    cells: Vec<Option<Cell>>,
+    sprites: Vec<Sprite>,
+}
+
+impl Layer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, opaque: false, cells: vec![None; width * height], sprites: vec![] }
+    }
+
+    /// Erases everything that has been drawn on the layer
+    pub fn clear(&mut self) {
+        self.opaque = false;
+        self.cells.iter_mut().for_each(|cell| *cell = None);
+        self.sprites.clear();
+    }
+
+    /// Returns what has been drawn at the given position (if anything). A
+    /// sprite is found on the tile it is the nearest to.
+    pub fn get(&self, x: usize, y: usize) -> Option<Cell> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let sprite = self.sprite(x, y).map(|sprite| Cell { glyph: sprite.glyph, color: sprite.color });
+        sprite.or(self.cells[y * self.width + x])
+    }
+
+    /// Returns the character represented by the sprite found at the given
+    /// position (if any)
+    pub fn figure(&self, x: usize, y: usize) -> Option<Figure> {
+        self.sprite(x, y).and_then(|sprite| sprite.figure)
+    }
+
+    /// The last sprite drawn on the tile it is the nearest to
+    fn sprite(&self, x: usize, y: usize) -> Option<&Sprite> {
+        self.sprites.iter().rev()
+            .find(|sprite| sprite.x.round() == x as f32 && sprite.y.round() == y as f32)
+    }
+
+    /// The sprites which have been drawn on the layer
+    pub fn sprites(&self) -> &[Sprite] {
+        &self.sprites
+    }
+
+    /// Draws the glyph at the given position (nothing happens when the
+    /// position lies outside of the layer)
+    pub fn set(&mut self, pos: Point, color: ColorPair, glyph: char) {
+        if pos.x >= 0 && pos.y >= 0 && (pos.x as usize) < self.width && (pos.y as usize) < self.height {
+            self.cells[pos.y as usize * self.width + pos.x as usize] = Some(Cell { glyph, color });
+        }
+    }
+
+    /// Draws the glyph at the given (fractional) position, which may lie
+    /// between two tiles
+    pub fn set_fancy(&mut self, x: f32, y: f32, color: ColorPair, glyph: char) {
+        self.sprites.push(Sprite { x, y, glyph, color, figure: None });
+    }
+
+    /// Draws the sprite of a character at the given (fractional) position
+    pub fn set_figure(&mut self, x: f32, y: f32, color: ColorPair, glyph: char, figure: Figure) {
+        self.sprites.push(Sprite { x, y, glyph, color, figure: Some(figure) });
+    }
+
+    /// Writes the text starting at the given position
+    pub fn print(&mut self, pos: Point, text: &str, color: ColorPair) {
+        for (i, glyph) in text.chars().enumerate() {
+            self.set(Point::new(pos.x + i as i32, pos.y), color, glyph);
+        }
+    }
+
+    /// Writes the text on the given line, centered horizontally
+    pub fn print_centered(&mut self, y: usize, text: &str, color: ColorPair) {
+        let x = (self.width / 2).saturating_sub(text.chars().count() / 2);
+        self.print(Point::new(x, y), text, color);
+    }
+
+    /// Draws a box (with a single line border) and clears its content
+    pub fn draw_box(&mut self, rect: Rect, color: ColorPair) {
+        for y in rect.y1..=rect.y2 {
+            for x in rect.x1..=rect.x2 {
+                let glyph = match (x, y) {
+                    (x, y) if x == rect.x1 && y == rect.y1 => '┌',
+                    (x, y) if x == rect.x2 && y == rect.y1 => '┐',
+                    (x, y) if x == rect.x1 && y == rect.y2 => '└',
+                    (x, y) if x == rect.x2 && y == rect.y2 => '┘',
+                    (_, y) if y == rect.y1 || y == rect.y2 => '─',
+                    (x, _) if x == rect.x1 || x == rect.x2 => '│',
+                    _ => ' ',
+                };
+                self.set(Point::new(x, y), color, glyph);
+            }
+        }
+    }
+}
+
+/// The way a frame is dumped as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// Plain text
+    Ascii,
+    /// Text with ANSI colors
+    Ansi,
+}
+
+impl FromStr for DumpFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(DumpFormat::Ascii),
+            "ansi"  => Ok(DumpFormat::Ansi),
+            _ => Err(format!("unknown dump format '{s}' (expected ascii or ansi)")),
+        }
+    }
+}
+
+/// Everything that is drawn during one tick of the game
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub layers: [Layer; NB_LAYERS],
+    /// The generation of the map drawn on the map layer (if any)
+    map_generation: Option<u64>,
+    /// Whether the map layer has been drawn since it was last copied onto the window
+    map_changed: bool,
+}
+
+impl Frame {
+    /// Creates an empty frame for a map of the given size
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            layers: [
+                Layer::new(width, height),
+                Layer::new(width, height),
+                Layer::new(width, height),
+                Layer::new(width * 2, height * 2),
+            ],
+            map_generation: None,
+            map_changed: true,
+        }
+    }
+
+    /// The width of the map (in tiles)
+    pub fn width(&self) -> usize {
+        self.layers[MAP_LAYER].width
+    }
+
+    /// The height of the map (in tiles)
+    pub fn height(&self) -> usize {
+        self.layers[MAP_LAYER].height
+    }
+
+    /// Erases everything that has been drawn on the frame, except for the map
+    pub fn clear(&mut self) {
+        self.layers.iter_mut()
+            .enumerate()
+            .filter(|(layer, _)| *layer != MAP_LAYER)
+            .for_each(|(_, layer)| layer.clear());
+    }
+
+    /// The generation of the map drawn on the map layer (if any)
+    pub fn map_generation(&self) -> Option<u64> {
+        self.map_generation
+    }
+
+    /// Tells that the map layer has just been drawn for the given generation of the map
+    pub fn set_map_generation(&mut self, generation: u64) {
+        self.map_generation = Some(generation);
+        self.map_changed    = true;
+    }
+
+    /// Erases the map layer, which will be drawn again on the next tick (e.g.
+    /// because another map is about to be loaded or because its colors changed)
+    pub fn invalidate_map(&mut self) {
+        self.layers[MAP_LAYER].clear();
+        self.map_generation = None;
+        self.map_changed    = true;
+    }
+
+    /// Returns true iff the map layer has been drawn since it was last copied
+    /// onto the window
+    pub fn map_changed(&self) -> bool {
+        self.map_changed
+    }
+
+    /// Composites all the layers into a text grid (each tile is two cells wide
+    /// and each row of the grid covers two rows of the message layer)
+    pub fn composite(&self) -> Vec<Vec<Cell>> {
+        let blank = Cell { glyph: ' ', color: ColorPair::new(WHITE, BLACK) };
+        let mut grid = vec![vec![blank; self.width() * 2]; self.height()];
+
+        for (y, row) in grid.iter_mut().enumerate() {
+            for x in 0..self.width() {
+                let mut from_map = false;
+                for layer in [MAP_LAYER, FOOD_LAYER, CHARACTER_LAYER] {
+                    if let Some(cell) = self.layers[layer].get(x, y) {
+                        let glyph = match layer {
+                            MAP_LAYER if is_wall_glyph(cell.glyph) => '#',
+                            CHARACTER_LAYER => self.layers[layer].figure(x, y).map_or(cell.glyph, Figure::symbol),
+                            _ => cell.glyph,
+                        };
+                        let bg = row[2 * x].color.bg;
+                        let color = if layer == MAP_LAYER { cell.color } else { ColorPair::new(cell.color.fg, bg) };
+                        row[2 * x] = Cell { glyph, color };
+                        from_map = layer == MAP_LAYER;
+                    }
+                }
+                // walls are drawn on both cells of the tile, the rest on the first one only
+                row[2 * x + 1] = if from_map { row[2 * x] } else { Cell { glyph: ' ', color: row[2 * x].color } };
+            }
+        }
+
+        // a row of the grid shows the first of its two rows of messages,
+        // unless nothing has been drawn on it
+        let messages = &self.layers[MESSAGE_LAYER];
+        let drawn = |y: usize| (0..messages.width).any(|x| messages.get(x, y).is_some());
+        for (y, row) in grid.iter_mut().enumerate() {
+            let line = if drawn(2 * y) { 2 * y } else { 2 * y + 1 };
+            for (x, target) in row.iter_mut().enumerate() {
+                match messages.get(x, line) {
+                    Some(cell) => *target = cell,
+                    None if messages.opaque => *target = blank,
+                    None => { /* the layers below remain visible */ },
+                }
+            }
+        }
+        grid
+    }
+
+    /// Dumps the frame in the given format
+    pub fn dump(&self, format: DumpFormat) -> String {
+        match format {
+            DumpFormat::Ascii => self.to_ascii(),
+            DumpFormat::Ansi  => self.to_ansi(),
+        }
+    }
+
+    /// Dumps the frame as plain text (the box drawing characters are replaced by ASCII ones)
+    pub fn to_ascii(&self) -> String {
+        let mut text = String::new();
+        for row in self.composite() {
+            let line = row.iter().map(|cell| ascii(cell.glyph)).collect::<String>();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Dumps the frame as text with ANSI (24 bits) colors
+    pub fn to_ansi(&self) -> String {
+        let mut text = String::new();
+        for row in self.composite() {
+            for cell in row {
+                let (fg, bg) = (cell.color.fg, cell.color.bg);
+                let _ = write!(
+                    text,
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m{}",
+                    byte(fg.r), byte(fg.g), byte(fg.b),
+                    byte(bg.r), byte(bg.g), byte(bg.b),
+                    cell.glyph,
+                );
+            }
+            text.push_str("\x1b[0m\n");
+        }
+        text
+    }
+
+    /// Copies the frame onto the consoles of the window. The map console is
+    /// left untouched unless the map layer has been drawn again.
+    #[cfg(feature = "gui")]
+    pub fn draw(&mut self) {
+        for (console, layer) in self.layers.iter().enumerate() {
+            if console == MAP_LAYER && !self.map_changed {
+                continue;
+            }
+            let mut batch = DrawBatch::new();
+            batch.target(console);
+            if console == MAP_LAYER {
+                batch.cls();
+            }
+            if layer.opaque {
+                batch.set_all_alpha(1.0, 1.0);
+            }
+            for y in 0..layer.height {
+                for x in 0..layer.width {
+                    if let Some(cell) = layer.cells[y * layer.width + x] {
+                        batch.set(Point::new(x, y), cell.color, to_cp437(cell.glyph));
+                    }
+                }
+            }
+            for sprite in layer.sprites() {
+                // the background of a sprite never hides the layers below it
+                let color = ColorPair::new(sprite.color.fg, RGBA::from_f32(0.0, 0.0, 0.0, 0.0));
+                let scale = PointF::new(1.0, 1.0);
+                batch.set_fancy(PointF::new(sprite.x, sprite.y), 1, Radians::new(0.0), scale, color, to_cp437(sprite.glyph));
+            }
+            batch.submit(console * 10_000).expect("error submitting draw batch");
+        }
+        self.map_changed = false;
+    }
+}
+
+/// The symbols of the code page 437 (the order of the glyphs in the fonts)
+/// which replace the ASCII control characters, from index 1 to 31
+const CP437_LOW: &str = "☺☻♥♦♣♠•◘○◙♂♀♪♫☼►◄↕‼¶§▬↨↑↓→←∟↔▲▼";
+
+/// The characters of the code page 437 (the order of the glyphs in the fonts)
+/// which are not ASCII, from index 128 onwards
+const CP437_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»\
+░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// Returns the character of the glyph having the given index in the fonts
+pub fn cp437(index: u8) -> char {
+    match index {
+        1..=31  => CP437_LOW.chars().nth(index as usize - 1).unwrap_or(' '),
+        0..=127 => index as char,
+        _ => CP437_HIGH.chars().nth(index as usize - 128).unwrap_or(' '),
+    }
+}
+
+/// Replaces the box drawing characters by ASCII ones
+fn ascii(glyph: char) -> char {
+    match glyph {
+        '┌' | '┐' | '└' | '┘' => '+',
+        '─' => '-',
+        '│' => '|',
+        _ => glyph,
+    }
+}
+
+fn byte(channel: f32) -> u8 {
+    (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+}
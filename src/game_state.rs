@@ -7,12 +7,12 @@
 //! Date:    March 2023
 //! Licence: MIT 
 
-use std::sync::mpsc::Receiver;
+use std::sync::{atomic::AtomicBool, mpsc::{Receiver, TryRecvError}, Arc};
 
 use legion::{world::World, Resources, Schedule};
-use crate::{pascman_protocol::Item, *};
+use crate::{pascman_protocol::{Item, HEIGHT, WIDTH}, *};
 
-use self::pascman_protocol::MessageType;
+use self::{animation::Sprites, config::{Colors, Config, HotSeat, KeyBindings}, frame::Frame, input::{HeldKeys, InputQueue, InputSettings}, motion::MotionSettings, pascman_protocol::{MessageType, ProtocolMode}, prediction::{Prediction, Verdict, CORRECTION_DURATION}, record::Recorder, replay::Replayer, systems::running::player_number, validator::{ValidationMode, Validator}, walls::{WallStyle, Walls}};
 
 #[derive(Debug, Clone, Copy)]
 pub enum GameStatus {
@@ -28,30 +28,227 @@ pub struct State {
     pub running: Schedule,
     pub over: Schedule,
     pub map_file: String,
+    /// The recorded session being replayed (if any)
+    pub replay: Option<Replayer>,
+    /// Whether the messages will never be received anymore
+    pub input_closed: bool,
+    /// Set (possibly from another thread) to close the game
+    pub quit: Arc<AtomicBool>,
 }
 
 impl State {
     pub fn new(channel: std::sync::mpsc::Receiver<pascman_protocol::Message>) -> Self {
+        Self::with_schedule(channel, run_game_schedule())
+    }
+
+    /// Creates a new state which is going to play the game offline: the rules
+    /// of the game are applied locally rather than by some server (the
+    /// messages of the offline game are sent on the given channel).
+    pub fn offline(channel: std::sync::mpsc::Receiver<pascman_protocol::Message>, game: OfflineGame) -> Self {
+        let mut state = Self::with_schedule(channel, offline_game_schedule());
+        state.resources.insert(game);
+        state
+    }
+
+    /// Creates a new state which replays a recorded session. In this mode, the
+    /// game is only watched: the keys control the replay rather than the hero.
+    pub fn replay(replayer: Replayer) -> Self {
+        let (_sx, rx) = std::sync::mpsc::channel();
+        let mut state = Self::with_schedule(rx, watch_game_schedule());
+        state.over   = watch_over_schedule();
+        state.replay = Some(replayer);
+        state
+    }
+
+    /// Creates a new state which executes the given schedule while the game is running
+    pub fn with_schedule(channel: std::sync::mpsc::Receiver<pascman_protocol::Message>, running: Schedule) -> Self {
         let ecs = World::default();
-        let running = run_game_schedule();
         let over = game_over_schedule();
         let mut resources = Resources::default();
         let rng = RandomNumberGenerator::new();
         resources.insert(rng);
         resources.insert(Player(0));
+        resources.insert(FrameTime::default());
+        resources.insert(Personality::Chaser);
+        resources.insert(Option::<Recorder>::None);
+        resources.insert(ProtocolMode::Binary);
+        resources.insert(DirectionSink::Stdout);
+        resources.insert(KeyBindings::default());
+        resources.insert(Option::<HotSeat>::None);
+        resources.insert(InputSettings::default());
+        resources.insert(InputQueue::default());
+        resources.insert(HeldKeys::default());
+        resources.insert(Prediction::default());
+        resources.insert(MotionSettings::default());
+        resources.insert(Sprites::default());
+        resources.insert(Colors::default());
+        resources.insert(WallStyle::default());
+        resources.insert(Validator::new(ValidationMode::Lenient));
         resources.insert(GameStatus::NotStarted);
-        resources.insert(Map{width: 30, height: 20, tiles: vec![TileType::Floor;30*20] });
+        resources.insert(Walls::new(&Map::new(WIDTH, HEIGHT)));
+        resources.insert(Map::new(WIDTH, HEIGHT));
+        resources.insert(Frame::new(WIDTH, HEIGHT));
         resources.insert(channel);
-        Self { ecs, resources, running, over, map_file: String::new() }
+        Self { ecs, resources, running, over, map_file: String::new(), replay: None, input_closed: false, quit: Arc::default() }
+    }
+
+    /// Forgets everything about the current game, as if the interface had just been started
+    pub fn reset(&mut self) {
+        self.ecs.clear();
+        if let Some(mut validator) = self.resources.get_mut::<Validator>() {
+            validator.reset();
+        }
+        let (width, height) = self.size();
+        self.resources.insert(Player(0));
+        self.resources.insert(GameStatus::NotStarted);
+        self.resources.insert(Walls::new(&Map::new(width, height)));
+        self.resources.insert(Map::new(width, height));
+        self.resources.insert(InputQueue::default());
+        if let Some(mut frame) = self.resources.get_mut::<Frame>() {
+            frame.invalidate_map();
+        }
+        if let Some(mut prediction) = self.resources.get_mut::<Prediction>() {
+            prediction.clear();
+        }
+    }
+
+    /// The size of the map (in tiles)
+    pub fn size(&self) -> (usize, usize) {
+        let map = self.resources.get::<Map>();
+        map.map_or((WIDTH, HEIGHT), |map| (map.width, map.height))
+    }
+
+    /// Changes the size of the map (in tiles). This is meant to be done before
+    /// the game starts, since everything about the current map is forgotten.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let mode = self.resources.get::<Validator>().map_or(ValidationMode::Lenient, |validator| validator.mode());
+        self.resources.insert(Walls::new(&Map::new(width, height)));
+        self.resources.insert(Map::new(width, height));
+        self.resources.insert(Frame::new(width, height));
+        self.resources.insert(Validator::with_size(mode, width, height));
     }
 
-    fn process_message(
-            ecs: &mut World, 
-            map: &mut Map, 
-            status: &mut GameStatus, 
-            player: &mut Player,
-            msg: pascman_protocol::Message
-    ) {
+    /// Applies the given configuration (the keys, the colors, the style of
+    /// the walls, the input and the motion settings) to the game
+    pub fn configure(&mut self, config: &Config) {
+        self.resources.insert(config.keys.clone());
+        self.resources.insert(config.colors);
+        self.resources.insert(config.walls);
+        self.resources.insert(config.input);
+        self.resources.insert(config.motion);
+        if let Some(mut frame) = self.resources.get_mut::<Frame>() {
+            frame.invalidate_map();
+        }
+    }
+
+    /// Tells which keys are currently held down (so that their direction can
+    /// be repeated). This is to be called before `update`.
+    pub fn hold_keys(&mut self, keys: impl IntoIterator<Item = VirtualKeyCode>) {
+        self.resources.insert(HeldKeys(keys.into_iter().collect()));
+    }
+
+    /// Runs one tick of the game: processes the messages which have been
+    /// received, runs the schedules and draws the result onto the frame. This
+    /// does not need any window, which is what the headless mode relies on.
+    pub fn update(&mut self, key: Option<VirtualKeyCode>, frame_time_ms: f32) {
+        if let Some(mut frame) = self.resources.get_mut::<Frame>() {
+            frame.clear();
+        }
+
+        // this keeps track of the key that has potentially been pressed and saves
+        // it as a resource in the game world.
+        // note: 
+        // Any two resources with the same type will be replaced by one another
+        // in the ecs. There is thus no need to think of duplicates in this context
+        self.resources.insert(key);
+        self.resources.insert(FrameTime(frame_time_ms));
+        
+        { // fetch messages
+            let ecs = &mut self.ecs;
+            let resources = &self.resources;
+            let mut rx = resources.get_mut::<Receiver<pascman_protocol::Message>>();
+            let rx = rx.as_deref_mut().unwrap();
+
+            let mut validator = resources.get_mut::<Validator>();
+            let validator = validator.as_deref_mut().unwrap();
+
+            loop {
+                match rx.try_recv() {
+                    Ok(msg) => if validator.check(&msg) {
+                        Self::process_message(ecs, resources, msg);
+                    },
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        self.input_closed = true;
+                        break;
+                    },
+                }
+            }
+        }
+
+        if let Some(mut replay) = self.replay.take() {
+            replay.update(self, key, frame_time_ms);
+            self.replay = Some(replay);
+        }
+
+        let status = self.resources.get::<GameStatus>().as_deref().copied().unwrap();
+        match status {
+            GameStatus::NotStarted => {
+                /* do nothing */
+            },
+            GameStatus::Registered => {
+                self.ecs.clear();
+                self.resources.insert(GameStatus::Running);
+            }
+            GameStatus::Running => {
+                self.running.execute(&mut self.ecs, &mut self.resources)},
+            GameStatus::Over { winner: _} => 
+                self.over.execute(&mut self.ecs, &mut self.resources),
+        }
+        // 
+
+        if let Some(mut frame) = self.resources.get_mut::<Frame>() {
+            if let Some(validator) = self.resources.get::<Validator>() {
+                validator.render(&mut frame);
+            }
+            if let Some(replay) = &self.replay {
+                replay.render(&mut frame);
+            }
+        }
+    }
+
+    /// Processes the given message right away
+    pub fn process(&mut self, msg: pascman_protocol::Message) {
+        let ecs = &mut self.ecs;
+        let resources = &self.resources;
+
+        let mut validator = resources.get_mut::<Validator>();
+        let validator = validator.as_deref_mut().unwrap();
+
+        if validator.check(&msg) {
+            Self::process_message(ecs, resources, msg);
+        }
+    }
+
+    fn process_message(ecs: &mut World, resources: &Resources, msg: pascman_protocol::Message) {
+        let mut map = resources.get_mut::<Map>();
+        let map = map.as_deref_mut().unwrap();
+
+        let mut status = resources.get_mut::<GameStatus>();
+        let status = status.as_deref_mut().unwrap();
+
+        let mut player = resources.get_mut::<Player>();
+        let player = player.as_deref_mut().unwrap();
+
+        let sprites = resources.get::<Sprites>();
+        let sprites = sprites.as_deref().unwrap();
+
+        let mut walls = resources.get_mut::<Walls>();
+        let walls = walls.as_deref_mut().unwrap();
+
+        let mut prediction = resources.get_mut::<Prediction>();
+        let prediction = prediction.as_deref_mut().unwrap();
+
         unsafe {
             match msg.msgt {
                 MessageType::REGISTRATION => {
@@ -64,10 +261,14 @@ impl State {
                         Item::FLOOR   => {
                             let idx = map.point2d_to_index(Point::new(spawn.pos.x, spawn.pos.y));
                             map.tiles[idx] = TileType::Floor;
+                            map.generation += 1;
+                            walls.update(map, Position { x: spawn.pos.x as usize, y: spawn.pos.y as usize });
                         },
                         Item::WALL    => {
                             let idx = map.point2d_to_index(Point::new(spawn.pos.x, spawn.pos.y));
                             map.tiles[idx] = TileType::Wall;
+                            map.generation += 1;
+                            walls.update(map, Position { x: spawn.pos.x as usize, y: spawn.pos.y as usize });
                         },
                         Item::FOOD    => {
                             spawn_seed(ecs, spawn.id, Position { x: spawn.pos.x as usize, y: spawn.pos.y as usize});
@@ -76,10 +277,10 @@ impl State {
                             spawn_superfood(ecs, spawn.id, Position { x: spawn.pos.x as usize, y: spawn.pos.y as usize});
                         },
                         Item::PLAYER1   => {
-                            spawn_player1(ecs, spawn.id, Position { x: spawn.pos.x as usize, y: spawn.pos.y as usize});
+                            spawn_player1(ecs, sprites, spawn.id, Position { x: spawn.pos.x as usize, y: spawn.pos.y as usize});
                         },
                         Item::PLAYER2   => {
-                            spawn_player2(ecs, spawn.id, Position { x: spawn.pos.x as usize, y: spawn.pos.y as usize});
+                            spawn_player2(ecs, sprites, spawn.id, Position { x: spawn.pos.x as usize, y: spawn.pos.y as usize});
                         },
                     }
                 },
@@ -89,17 +290,33 @@ impl State {
                     let entity = <(Entity, &Id)>::query()
                         .iter(ecs)
                         .find(|(_entity, id)| id.0 == mvmt.id)
-                        .map(|(entity, _)| *entity);
+                        .map(|(entity, id)| (*entity, player_number(id, map)));
 
-                    if let Some(entity) = entity {
+                    if let Some((entity, local)) = entity {
+                        // the moves of the local players are reconciled with
+                        // the predicted ones as they arrive: several of them
+                        // may arrive within a single tick
+                        let verdict = match local {
+                            Some(local) if prediction.enabled => prediction.confirm(local, pos),
+                            _ => Verdict::Unpredicted,
+                        };
                         if let Some(mut entry) = ecs.entry(entity) {
-                            entry.add_component(IntendsToMove(pos));
+                            match verdict {
+                                Verdict::Unpredicted  => entry.add_component(IntendsToMove(pos)),
+                                Verdict::Confirmed    => { /* the player already is where it should be */ },
+                                Verdict::Mispredicted => {
+                                    entry.add_component(IntendsToMove(pos));
+                                    entry.add_component(Corrected(CORRECTION_DURATION));
+                                },
+                            }
                         }
                     }
                 },
                 MessageType::EAT_FOOD => {
                     let food = msg.eat_food.food;
+                    // only the food can be eaten (whatever the id says)
                     let entity = <(Entity, &Id)>::query()
+                        .filter(component::<Food>() | component::<Superfood>())
                         .iter(ecs)
                         .find(|(_entity, id)| id.0 == food)
                         .map(|(entity, _)| *entity);
@@ -117,10 +334,14 @@ impl State {
     }
 }
 
+#[cfg(feature = "gui")]
 impl GameState for State {
     fn tick(&mut self, ctx: &mut bracket_lib::prelude::BTerm) {
-        ctx.set_active_console(0); // the map
-        ctx.cls();
+        if self.quit.load(std::sync::atomic::Ordering::Relaxed) {
+            ctx.quit();
+        }
+
+        // the map is only cleared when it is drawn again (see `Frame::draw`)
         ctx.set_active_console(1); // food
         ctx.cls();
         ctx.set_active_console(2); // characters
@@ -129,50 +350,13 @@ impl GameState for State {
         ctx.cls();
         ctx.set_all_alpha(0.0, 0.0); // by default the message console is transparent
 
-        // this keeps track of the key that has potentially been pressed and saves
-        // it as a resource in the game world.
-        // note: 
-        // Any two resources with the same type will be replaced by one another
-        // in the ecs. There is thus no need to think of duplicates in this context
-        self.resources.insert(ctx.key);
-        
-        { // fetch messages
-            let ecs = &mut self.ecs;
-            let resources = &self.resources;
-            let mut rx = resources.get_mut::<Receiver<pascman_protocol::Message>>();
-            let rx = rx.as_deref_mut().unwrap();
-
-            let mut map = resources.get_mut::<Map>();
-            let map = map.as_deref_mut().unwrap();
-
-            let mut status = resources.get_mut::<GameStatus>();
-            let status = status.as_deref_mut().unwrap();
-
-            let mut player = resources.get_mut::<Player>();
-            let player = player.as_deref_mut().unwrap();
+        self.hold_keys(bracket_lib::prelude::INPUT.lock().key_pressed_set().iter().copied());
+        self.update(ctx.key, ctx.frame_time_ms);
 
-            while let Ok(msg) = rx.try_recv() {
-                Self::process_message(ecs, map, status, player, msg);
-            }
-        }
-
-        let status = self.resources.get::<GameStatus>().as_deref().copied().unwrap();
-        match status {
-            GameStatus::NotStarted => {
-                /* do nothing */
-            },
-            GameStatus::Registered => {
-                self.ecs.clear();
-                self.resources.insert(GameStatus::Running);
-            }
-            GameStatus::Running => {
-                self.running.execute(&mut self.ecs, &mut self.resources)},
-            GameStatus::Over { winner: _} => 
-                self.over.execute(&mut self.ecs, &mut self.resources),
-        }
-        // 
-        
         // effectively draw everything on screen (in batch to be more efficient)
+        if let Some(mut frame) = self.resources.get_mut::<Frame>() {
+            frame.draw();
+        }
         render_draw_buffer(ctx).expect("could not render");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,60 @@
+//! The headless mode runs the very same schedules as the window, but without
+//! any window: the frames are composited into text grids (see the `frame`
+//! module) and dumped on some output. This makes it possible to run the game
+//! in a CI container without any display.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{io::{self, Write}, thread, time::Duration};
+
+use crate::{frame::{DumpFormat, Frame}, GameStatus, State};
+
+/// How the game is run without a window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Headless {
+    /// The format of the dumped frames
+    pub format: DumpFormat,
+    /// A frame is dumped every `every` ticks (only the last one when 0)
+    pub every: usize,
+    /// The game stops after that many ticks (if any)
+    pub max_frames: Option<usize>,
+    /// The (simulated) duration of one tick
+    pub frame_time: Duration,
+    /// Whether each tick really lasts `frame_time` (when a frame rate has
+    /// been requested) or the ticks follow each other as fast as possible
+    pub realtime: bool,
+}
+
+impl Headless {
+    /// Runs the game until it is over, the input is exhausted or the maximum
+    /// number of frames is reached. The frames are dumped to `out`.
+    pub fn run(&self, state: &mut State, out: &mut dyn Write) -> io::Result<()> {
+        let frame_time_ms = self.frame_time.as_secs_f32() * 1000.0;
+        for n in 1.. {
+            state.update(None, frame_time_ms);
+
+            let over     = matches!(state.resources.get::<GameStatus>().as_deref(), Some(GameStatus::Over { .. }));
+            let replayed = state.replay.as_ref().is_none_or(|replay| replay.is_finished());
+            let done     = over
+                || (state.input_closed && replayed)
+                || self.max_frames.is_some_and(|max| n >= max);
+
+            if done || (self.every > 0 && n % self.every == 0) {
+                if let Some(frame) = state.resources.get::<Frame>() {
+                    writeln!(out, "--- frame {n} ---")?;
+                    out.write_all(frame.dump(self.format).as_bytes())?;
+                    out.flush()?;
+                }
+            }
+            if done {
+                break;
+            }
+            if self.realtime {
+                thread::sleep(self.frame_time);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+//! This module turns the keys pressed (and held) by the user into the
+//! directions which are sent to the server. Rather than sending a direction
+//! only on the very tick a key is reported, it:
+//!
+//! * repeats the direction of a key which is held down (after a short delay,
+//!   at a configurable rate);
+//! * holds back the directions leading into a wall: the last turn which could
+//!   not be taken yet (e.g. a turn pressed slightly before reaching a
+//!   junction) is buffered and sent as soon as the player reaches a place
+//!   where `Map::can_enter` allows it. When `buffer_turns` is disabled, every
+//!   direction is sent and the server decides what to do with it;
+//! * never sends two directions closer in time than a minimum interval, so
+//!   that the server is not flooded.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use crate::{keys::VirtualKeyCode, Direction};
+
+/// How the keys are turned into directions (all durations are in milliseconds)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputSettings {
+    /// How long a key must be held before its direction is repeated
+    pub repeat_delay: f32,
+    /// The time between two repetitions of a key which is held down
+    pub repeat_interval: f32,
+    /// The minimum time between two directions sent to the server
+    pub min_interval: f32,
+    /// Whether the directions leading into a wall are held back (and the
+    /// turns which cannot be taken yet are buffered) rather than sent
+    pub buffer_turns: bool,
+    /// How long a turn which cannot be taken yet remains buffered
+    pub turn_buffer: f32,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self { repeat_delay: 250.0, repeat_interval: 100.0, min_interval: 50.0, buffer_turns: true, turn_buffer: 1_000.0 }
+    }
+}
+
+/// The keys which are currently held down
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeldKeys(pub Vec<VirtualKeyCode>);
+
+/// The input of the local players: one in a regular game, two in hot-seat mode
+#[derive(Debug, Clone, Default)]
+pub struct InputQueue {
+    pub players: [PlayerInput; 2],
+    /// The keys which were held down during the previous tick
+    held: Vec<VirtualKeyCode>,
+}
+
+impl InputQueue {
+    /// Returns the keys which have been pressed since the previous tick (those
+    /// which are held down now but were not back then). Unlike the single key
+    /// reported by the window, this tells all the keys pressed at once, e.g.
+    /// by the two players of a hot-seat game.
+    pub fn newly_pressed(&mut self, held: &[VirtualKeyCode]) -> Vec<VirtualKeyCode> {
+        let pressed = held.iter().copied().filter(|key| !self.held.contains(key)).collect();
+        self.held = held.to_vec();
+        pressed
+    }
+}
+
+/// What one local player is doing with the keyboard
+#[derive(Debug, Clone)]
+pub struct PlayerInput {
+    /// The key being held down (if any)
+    held: Option<Held>,
+    /// The turn to take as soon as possible (if any)
+    buffered: Option<Buffered>,
+    /// The time elapsed since the last direction was sent
+    since_sent: f32,
+}
+
+impl Default for PlayerInput {
+    fn default() -> Self {
+        // the very first direction is never delayed
+        Self { held: None, buffered: None, since_sent: f32::INFINITY }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Held {
+    key: VirtualKeyCode,
+    direction: Direction,
+    /// How long the key has been held
+    elapsed: f32,
+    /// When the direction is to be repeated next
+    next_repeat: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Buffered {
+    direction: Direction,
+    /// How long the turn has been buffered
+    age: f32,
+}
+
+impl PlayerInput {
+    /// Lets `dt` milliseconds elapse and returns the direction to send now (if
+    /// any). `pressed` is the key which has just been pressed by this player
+    /// (with its direction), `held` are all the keys held down and `can_go`
+    /// tells whether the player can currently go in some direction (which is
+    /// only taken into account when the turns are buffered).
+    pub fn update(
+        &mut self,
+        settings: &InputSettings,
+        pressed: Option<(VirtualKeyCode, Direction)>,
+        held: &[VirtualKeyCode],
+        dt: f32,
+        can_go: impl Fn(Direction) -> bool,
+    ) -> Option<Direction> {
+        // unless the turns are buffered, the server decides where one can go
+        let can_go = |direction: Direction| !settings.buffer_turns || can_go(direction);
+        self.since_sent += dt;
+        if let Some(buffered) = &mut self.buffered {
+            buffered.age += dt;
+            if buffered.age > settings.turn_buffer {
+                self.buffered = None;
+            }
+        }
+
+        let (fresh, repeated) = self.read_keys(settings, pressed, held, dt);
+        if let Some(direction) = fresh {
+            // a new key replaces whatever turn was waiting
+            self.buffered = Some(Buffered { direction, age: 0.0 });
+        }
+        if let Some(direction) = repeated.filter(|direction| !can_go(*direction)) {
+            // holding a turn before the junction keeps it buffered
+            if self.buffered.is_none_or(|buffered| buffered.direction == direction) {
+                self.buffered = Some(Buffered { direction, age: 0.0 });
+            }
+        }
+
+        let due = match self.buffered {
+            Some(buffered) if can_go(buffered.direction) => Some(buffered.direction),
+            _ => repeated.filter(|direction| can_go(*direction)),
+        }?;
+        if self.since_sent < settings.min_interval {
+            // a buffered turn waits for the next tick, a repetition is dropped
+            return None;
+        }
+        self.since_sent = 0.0;
+        if self.buffered.is_some_and(|buffered| buffered.direction == due) {
+            self.buffered = None;
+        }
+        Some(due)
+    }
+
+    /// Returns the direction of the key which has just been pressed and the
+    /// direction of the held key which is to be repeated now
+    fn read_keys(
+        &mut self,
+        settings: &InputSettings,
+        pressed: Option<(VirtualKeyCode, Direction)>,
+        held: &[VirtualKeyCode],
+        dt: f32,
+    ) -> (Option<Direction>, Option<Direction>) {
+        // the keyboard itself repeats the keys which are held down: these are
+        // not new keys
+        let pressed = pressed.filter(|(key, _)| self.held.is_none_or(|current| current.key != *key));
+        if let Some((key, direction)) = pressed {
+            self.held = Some(Held { key, direction, elapsed: 0.0, next_repeat: settings.repeat_delay });
+            return (Some(direction), None);
+        }
+
+        let Some(current) = &mut self.held else {
+            return (None, None);
+        };
+        if !held.contains(&current.key) {
+            self.held = None;
+            return (None, None);
+        }
+        current.elapsed += dt;
+        if current.elapsed >= current.next_repeat {
+            current.next_repeat = current.elapsed + settings.repeat_interval;
+            (None, Some(current.direction))
+        } else {
+            (None, None)
+        }
+    }
+}
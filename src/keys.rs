@@ -0,0 +1,50 @@
+//! The keys understood by the game. When the game is built with its windowed
+//! frontend (the `gui` feature), these are the keys reported by bracket-lib.
+//! Otherwise, no key is ever pressed (the game runs headless) and this module
+//! merely provides the keys the systems are looking for.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+#[cfg(feature = "gui")]
+pub use bracket_lib::terminal::VirtualKeyCode;
+
+/// A key of the keyboard (the same names as those of bracket-lib)
+#[cfg(not(feature = "gui"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VirtualKeyCode {
+    Left,
+    Right,
+    Up,
+    Down,
+    Space,
+    Return,
+    Home,
+    PageUp,
+    PageDown,
+    A, B, C, D, E, F, G, H, I, J, K, L, M,
+    N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+}
+
+/// Generates the function which finds a key from its name
+macro_rules! named_keys {
+    ($($key:ident),* $(,)?) => {
+        /// Returns the key having the given name: the name of the variant of
+        /// `VirtualKeyCode` (e.g. `Left` or `W`), regardless of the case
+        pub fn key_named(name: &str) -> Option<VirtualKeyCode> {
+            $(
+                if name.eq_ignore_ascii_case(stringify!($key)) {
+                    return Some(VirtualKeyCode::$key);
+                }
+            )*
+            None
+        }
+    };
+}
+
+named_keys!(
+    Left, Right, Up, Down, Space, Return, Home, PageUp, PageDown,
+    A, B, C, D, E, F, G, H, I, J, K, L, M,
+    N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+);
@@ -15,17 +15,74 @@ pub mod systems;
 pub mod game_state;
 /// how to spawn stuffs in the game
 pub mod spawn;
+/// how to turn a textual map into protocol messages
+pub mod loader;
+/// how the villains decide where to go
+pub mod ai;
+/// the authoritative rules of the game
+pub mod rules;
+/// scripted scenarios
+pub mod scenario;
+/// recording of the protocol streams
+pub mod record;
+/// replay of the recorded sessions
+pub mod replay;
+/// decoding of the raw message streams
+pub mod dump;
+/// conformance checks of the messages received by the interface
+pub mod validator;
+/// the report of the conformance test harness
+pub mod conformance;
+/// what is drawn upon each tick
+pub mod frame;
+/// running the game without any window
+pub mod headless;
+/// the keys understood by the game
+pub mod keys;
+/// the configuration file of the interface
+pub mod config;
+/// the validation of the command line arguments
+pub mod cli;
+/// how the keys are turned into directions
+pub mod input;
+/// the prediction of the moves of the local players
+pub mod prediction;
+/// the smooth motion of the characters
+pub mod motion;
+/// the animations of the characters
+pub mod animation;
+/// how the walls are drawn
+pub mod walls;
+/// the window of the game
+#[cfg(feature = "gui")]
+pub mod window;
+/// the C API of the game
+pub mod ffi;
+/// what the game writes on its standard error
+pub mod logging;
 
 /// the external protocol to interact with the game
-pub mod pascman_protocol;
+pub use pascman_protocol;
 
 pub use resources::*;
 pub use components::*;
 pub use systems::*;
 pub use game_state::*;
 pub use spawn::*;
+pub use loader::*;
 
+#[cfg(feature = "gui")]
 pub use bracket_lib::prelude::*;
+#[cfg(not(feature = "gui"))]
+pub use bracket_color::prelude::*;
+#[cfg(not(feature = "gui"))]
+pub use bracket_geometry::prelude::*;
+#[cfg(not(feature = "gui"))]
+pub use bracket_pathfinding::prelude::*;
+#[cfg(not(feature = "gui"))]
+pub use bracket_random::prelude::*;
+#[cfg(not(feature = "gui"))]
+pub use keys::VirtualKeyCode;
 pub use legion::*;
 pub use legion::world::*;
 pub use legion::systems::*;
\ No newline at end of file
@@ -0,0 +1,132 @@
+//! The loader turns the textual description of a map into the stream of
+//! messages a server would send to introduce that map in the game. It follows
+//! the very same conventions as the `load_map` function from `game.c`:
+//!
+//! * '#' is a wall
+//! * '.' is some floor with food on it
+//! * '*' is some floor with superfood on it
+//! * ' ' is some empty floor
+//! * '@' is the floor where the first player starts
+//! * '!' is the floor where the second player starts
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{fs, io, path::Path};
+
+use crate::pascman_protocol::{Item, Message, Position, HEIGHT, WIDTH};
+
+/// The file (in the resources directory) holding the map which is played offline
+pub const MAP_FILE: &str = "map.txt";
+
+/// Returns the identifier of the item of type `item` located at (x, y) on a
+/// map of WIDTH x HEIGHT tiles. This follows the id scheme which is described
+/// in `game.h`.
+pub fn item_id(x: u32, y: u32, item: Item) -> u32 {
+    item_id_with_size(WIDTH, HEIGHT, x, y, item)
+}
+
+/// Returns the identifier of the item of type `item` located at (x, y) on a
+/// map of `width` x `height` tiles: this is the id scheme of `game.h` where
+/// MAP_SIZE is the number of tiles of that map.
+pub fn item_id_with_size(width: usize, height: usize, x: u32, y: u32, item: Item) -> u32 {
+    let size   = (width * height) as u32;
+    let offset = y * width as u32 + x;
+    let [player1, player2] = player_ids(width, height);
+    match item {
+        Item::FOOD | Item::SUPERFOOD => offset,
+        Item::WALL | Item::FLOOR     => size + offset,
+        Item::PLAYER1                => player1,
+        Item::PLAYER2                => player2,
+    }
+}
+
+/// Returns the identifiers of the first and second players on a map of
+/// `width` x `height` tiles (these are PLAYER1_ID and PLAYER2_ID on a map of
+/// WIDTH x HEIGHT tiles)
+pub fn player_ids(width: usize, height: usize) -> [u32; 2] {
+    let size = (width * height) as u32;
+    [3 * size, 3 * size + 1]
+}
+
+/// Reads the textual map and returns the sequence of messages that must be
+/// sent to introduce it in the game. Just like in `game.c`, the game is
+/// immediately over (and won by the first player) when there is no food
+/// at all on the map.
+///
+/// Characters that would fall outside of the WIDTH x HEIGHT map are ignored.
+pub fn load_map(text: &str) -> Vec<Message> {
+    load_map_with_size(text, WIDTH, HEIGHT)
+}
+
+/// Same as `load_map` for a map of `width` x `height` tiles: the ids of the
+/// items depend on the size of the map, and the characters that would fall
+/// outside of it are ignored.
+pub fn load_map_with_size(text: &str, width: usize, height: usize) -> Vec<Message> {
+    // creates the spawn message that introduces an item of type `item` at (x, y)
+    let spawn_item = |x: u32, y: u32, item: Item| {
+        Message::spawn(item_id_with_size(width, height, x, y, item), item, Position { x, y })
+    };
+    let mut messages = vec![];
+    let mut food_count = 0;
+    let mut x = 0_u32;
+    let mut y = 0_u32;
+
+    for c in text.chars() {
+        if c == '\n' {
+            y += 1;
+            x  = 0;
+            continue;
+        }
+        if x as usize >= width || y as usize >= height {
+            continue;
+        }
+        match c {
+            '#' => {
+                messages.push(spawn_item(x, y, Item::WALL));
+            },
+            '.' => {
+                messages.push(spawn_item(x, y, Item::FLOOR));
+                messages.push(spawn_item(x, y, Item::FOOD));
+                food_count += 1;
+            },
+            '*' => {
+                messages.push(spawn_item(x, y, Item::FLOOR));
+                messages.push(spawn_item(x, y, Item::SUPERFOOD));
+                food_count += 1;
+            },
+            ' ' => {
+                messages.push(spawn_item(x, y, Item::FLOOR));
+            },
+            '@' => {
+                messages.push(spawn_item(x, y, Item::PLAYER1));
+                messages.push(spawn_item(x, y, Item::FLOOR));
+            },
+            '!' => {
+                messages.push(spawn_item(x, y, Item::PLAYER2));
+                messages.push(spawn_item(x, y, Item::FLOOR));
+            },
+            _ => {
+                // by default we simply do nothing (and the character
+                // does not take any room on the map)
+                continue;
+            }
+        }
+        x += 1;
+    }
+
+    if food_count == 0 {
+        messages.push(Message::game_over(1));
+    }
+
+    messages
+}
+
+/// Reads the map (of `width` x `height` tiles) stored in the file at `path`
+/// and returns the sequence of messages that must be sent to introduce it in
+/// the game.
+pub fn load_map_file<P: AsRef<Path>>(path: P, width: usize, height: usize) -> io::Result<Vec<Message>> {
+    let text = fs::read_to_string(path)?;
+    Ok(load_map_with_size(&text, width, height))
+}
@@ -0,0 +1,58 @@
+//! A minimal logging facility: the messages are written to the standard error
+//! when their level is enabled (see `--log-level`).
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{fmt, str::FromStr, sync::atomic::{AtomicU8, Ordering}};
+
+/// How verbose the game is on its standard error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Nothing is logged
+    Off   = 0,
+    /// Only the errors are logged
+    Error = 1,
+    /// The errors and the warnings (e.g. protocol violations) are logged
+    Warn  = 2,
+    /// Some information about what the game does is logged as well
+    Info  = 3,
+    /// Everything is logged (including every message received)
+    Debug = 4,
+}
+
+/// The current log level
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Warn as u8);
+
+/// Changes the log level of the whole game
+pub fn set_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Returns true iff the messages of the given level are logged
+pub fn enabled(level: LogLevel) -> bool {
+    level != LogLevel::Off && level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Writes the message to the standard error if its level is enabled
+pub fn log(level: LogLevel, message: fmt::Arguments) {
+    if enabled(level) {
+        eprintln!("{message}");
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off"   => Ok(LogLevel::Off),
+            "error" => Ok(LogLevel::Error),
+            "warn"  => Ok(LogLevel::Warn),
+            "info"  => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            _ => Err(format!("unknown log level '{s}' (expected off, error, warn, info or debug)")),
+        }
+    }
+}
@@ -1,41 +1,383 @@
-use std::env;
-use std::str::FromStr;
-use std::{io::{stdin, Read}, thread};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+use std::{fs, io::{self, stdin, BufRead, BufReader, Read, Write}, process, thread};
 
 use legion::Schedule;
-use pas_cman_ipl::{main_loop, render_map_system, BResult, BTermBuilder, State};
+use pas_cman_ipl::{dump, load_map_file, render_map_system, MAP_FILE, rules::GameRules, scenario, DirectionSink, OfflineGame, Personality, State};
+use pas_cman_ipl::animation::Sprites;
+use pas_cman_ipl::cli::{parse_dimension, parse_fps, parse_speed, parse_tile_size, parse_title, Transport};
+use pas_cman_ipl::config::{Config, FontConfig};
+#[cfg(feature = "gui")]
+use pas_cman_ipl::{main_loop, window::{self, WindowOptions}, BResult};
+use pas_cman_ipl::frame::DumpFormat;
+use pas_cman_ipl::headless::Headless;
+use pas_cman_ipl::logging::{self, LogLevel};
+use pas_cman_ipl::prediction::Prediction;
+use pas_cman_ipl::pascman_protocol::{header::c_header, Direction, DirectionLine, Message, ProtocolMode, MESSAGE_SIZE};
+use pas_cman_ipl::record::Recorder;
+use pas_cman_ipl::replay::{Replayer, Seek};
+use pas_cman_ipl::validator::{ValidationMode, Validator};
+use structopt::StructOpt;
+
+/// pas cman c'est pas pacman
+#[derive(Debug, StructOpt)]
+#[structopt(name = "pas-cman-ipl")]
+struct Opt {
+    /// Play the game alone, without any server: the hero is driven by the
+    /// arrow keys and the second player becomes a villain
+    #[structopt(long)]
+    offline: bool,
+    /// The map to load when playing offline (ignored otherwise), map.txt in
+    /// the resources directory by default
+    #[structopt(long, parse(from_os_str))]
+    map: Option<PathBuf>,
+    /// The personality of the villain when playing offline: chaser, ambusher or scatterer
+    #[structopt(long, default_value = "chaser")]
+    personality: Personality,
+    /// Record all the messages received (and the directions sent) by the
+    /// interface to the given replay file
+    #[structopt(long, parse(from_os_str))]
+    record: Option<PathBuf>,
+    /// How the messages and directions are encoded: binary (the bytes of the
+    /// `Message` union) or text (each line is a message, e.g. `SPAWN 12 FOOD
+    /// 3 4`, and the directions are written as `DIR LEFT`)
+    #[structopt(long, default_value = "binary", env = "PAS_PROTOCOL")]
+    protocol: ProtocolMode,
+    /// Shorthand for --protocol text
+    #[structopt(long)]
+    text_protocol: bool,
+    /// Where the messages come from and where the directions go: stdio (the
+    /// standard input and output) or tcp:HOST:PORT (a connection to a server)
+    #[structopt(long, default_value = "stdio", env = "PAS_TRANSPORT")]
+    transport: Transport,
+    /// The configuration file (keys, colors and font). When omitted, it is
+    /// looked for in $XDG_CONFIG_HOME/pas-cman/config.toml
+    #[structopt(long, parse(from_os_str), env = "PAS_CONFIG")]
+    config: Option<PathBuf>,
+    /// The directory where the fonts (and the sprite sets) are found
+    #[structopt(long, default_value = "resources/", env = "PAS_RESOURCES")]
+    resources: String,
+    /// The font holding the sprites of the game, in the resources directory
+    /// (overrides the configuration file, pas-cman-font-32.png by default)
+    #[structopt(long, env = "PAS_FONT")]
+    font: Option<String>,
+    /// The size of a tile in pixels (from 8 to 128), which must also be the
+    /// size of the sprites in the font (overrides the configuration file, 32
+    /// by default)
+    #[structopt(long, env = "PAS_TILE_SIZE", parse(try_from_str = parse_tile_size))]
+    tile_size: Option<u32>,
+    /// The width of the map in tiles (from 1 to 256)
+    #[structopt(long, default_value = "30", env = "PAS_WIDTH", parse(try_from_str = parse_dimension))]
+    width: usize,
+    /// The height of the map in tiles (from 1 to 256)
+    #[structopt(long, default_value = "20", env = "PAS_HEIGHT", parse(try_from_str = parse_dimension))]
+    height: usize,
+    /// The maximum number of frames per second (unlimited when omitted)
+    #[structopt(long, env = "PAS_FPS", parse(try_from_str = parse_fps))]
+    fps: Option<f32>,
+    /// The title of the window
+    #[structopt(long, default_value = "pas cman", env = "PAS_TITLE", parse(try_from_str = parse_title))]
+    title: String,
+    /// How much is written on the standard error: off, error, warn, info or debug
+    #[structopt(long, default_value = "warn", env = "PAS_LOG")]
+    log_level: LogLevel,
+    /// Let two players share the keyboard: WASD for player 1 and the arrow
+    /// keys for player 2 (unless configured otherwise). With the stdio
+    /// transport, each direction is preceded by the player who chose it (e.g.
+    /// `DIR 2 LEFT`); with the tcp transport, each player has its own
+    /// connection to the server
+    #[structopt(long, conflicts_with = "offline")]
+    hot_seat: bool,
+    /// Move the local player(s) as soon as a direction is sent, without
+    /// waiting for the server (which still has the last word)
+    #[structopt(long, conflicts_with = "offline")]
+    predict: bool,
+    /// Reject the messages which violate the protocol (by default, they are
+    /// only reported on screen and on the standard error)
+    #[structopt(long)]
+    strict: bool,
+    /// Run the game without any window and dump the frames as text on the
+    /// standard output (the game stops when it is over or when its input is
+    /// exhausted)
+    #[structopt(long)]
+    headless: bool,
+    /// The format of the frames dumped in headless mode: ascii or ansi
+    #[structopt(long, default_value = "ascii")]
+    dump: DumpFormat,
+    /// In headless mode, dump a frame every N ticks (only the last one when 0)
+    #[structopt(long, default_value = "0")]
+    dump_every: usize,
+    /// In headless mode, stop after N ticks
+    #[structopt(long)]
+    max_frames: Option<usize>,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Plays a scripted scenario (see the `scenario` module for its syntax)
+    PlayScenario {
+        /// The file describing the scenario
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// Write the messages on the standard output (as a binary stream or
+        /// as lines of text with --text-protocol) instead of showing them in
+        /// the interface
+        #[structopt(long)]
+        stdout: bool,
+    },
+    /// Replays a session which has been recorded with --record. While the
+    /// session is replayed, SPACE pauses/resumes, RIGHT/LEFT go one message
+    /// forward/backward, UP/DOWN change the speed, PAGE DOWN/PAGE UP go 10
+    /// seconds forward/backward and HOME restarts the replay
+    Replay {
+        /// The replay file
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// The speed of the replay (from 0.25 up to 8)
+        #[structopt(long, default_value = "1.0", parse(try_from_str = parse_speed))]
+        speed: f32,
+        /// Start the replay at the given time (in seconds)
+        #[structopt(long, conflicts_with = "at_index")]
+        at_time: Option<f64>,
+        /// Start the replay at the given message index
+        #[structopt(long)]
+        at_index: Option<usize>,
+        /// Start the replay paused
+        #[structopt(long)]
+        paused: bool,
+    },
+    /// Decodes a raw stream of messages and prints one line per message.
+    /// Invalid discriminants, positions lying outside of the map (see --width
+    /// and --height) and trailing partial records are flagged, and make the
+    /// program exit with status 1
+    Dump {
+        /// The file to read the messages from (the standard input when omitted)
+        #[structopt(parse(from_os_str))]
+        file: Option<PathBuf>,
+        /// Print one JSON object per message instead of a human readable line
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Generates the C header (pascman.h) describing the protocol
+    EmitHeader {
+        /// The file where the header is written (on the standard output when omitted)
+        #[structopt(parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+}
+
+#[cfg(not(feature = "gui"))]
+type BResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Connects to the server at the given address. Returns the connection (to
+/// read the messages from) and the channel on which the directions are sent
+fn connect(address: &str, mode: ProtocolMode) -> io::Result<(TcpStream, Sender<Direction>)> {
+    let input = TcpStream::connect(address)?;
+    let output = input.try_clone()?;
+    logging::log(LogLevel::Info, format_args!("connected to {address}"));
+
+    let (directions, rx) = channel();
+    thread::spawn(move || write_directions(output, rx, mode));
+    Ok((input, directions))
+}
+
+/// The duration of one tick in headless mode (unless a frame rate is requested)
+const HEADLESS_FRAME_TIME: Duration = Duration::from_millis(33);
+
+/// Forwards the message to the game (and records it if needed)
+fn forward(sx: &Sender<Message>, recorder: &Option<Recorder>, message: Message) {
+    if let Some(recorder) = recorder {
+        recorder.message(&message.to_bytes());
+    }
+    deliver(sx, message);
+}
+
+/// Forwards the message to the game
+fn deliver(sx: &Sender<Message>, message: Message) {
+    logging::log(LogLevel::Debug, format_args!("received {message}"));
+    // the interface might have been closed in the meantime
+    let _ = sx.send(message);
+}
+
+/// Reads the messages sent (in the given protocol mode) on the input and
+/// forwards them to the game
+fn read_messages(mut input: impl Read, sx: Sender<Message>, recorder: Option<Recorder>, mode: ProtocolMode) {
+    match mode {
+        ProtocolMode::Binary => {
+            let mut buffer = [0_u8; MESSAGE_SIZE];
+            while input.read_exact(&mut buffer).is_ok() {
+                // the bytes are recorded as they were received, before they are decoded
+                if let Some(recorder) = &recorder {
+                    recorder.message(&buffer);
+                }
+                match Message::from_bytes(&buffer) {
+                    Ok(message) => deliver(&sx, message),
+                    Err(e) => logging::log(LogLevel::Warn, format_args!("ignoring invalid message: {e}")),
+                }
+            }
+        },
+        ProtocolMode::Text => {
+            for (n, line) in BufReader::new(input).lines().map_while(Result::ok).enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match line.parse::<Message>() {
+                    Ok(message) => forward(&sx, &recorder, message),
+                    Err(e) => logging::log(LogLevel::Warn, format_args!("ignoring invalid message on line {}: {e}", n + 1)),
+                }
+            }
+        },
+    }
+    logging::log(LogLevel::Info, format_args!("no more messages will be received"));
+}
+
+/// Writes the directions chosen by the user (in the given protocol mode) on the output
+fn write_directions(mut output: impl Write, directions: Receiver<Direction>, mode: ProtocolMode) {
+    for direction in directions {
+        let written = match mode {
+            ProtocolMode::Binary => output.write_all(&(direction as u32).to_ne_bytes()),
+            ProtocolMode::Text   => writeln!(output, "{}", DirectionLine { player: None, direction }),
+        };
+        if let Err(e) = written.and_then(|_| output.flush()) {
+            logging::log(LogLevel::Error, format_args!("could not send the direction: {e}"));
+            break;
+        }
+    }
+}
 
 fn main() -> BResult<()> {
-    let w = 30;
-    let h = 20;
+    let mut opt = Opt::from_args();
+    let mode = if opt.text_protocol { ProtocolMode::Text } else { opt.protocol };
+    logging::set_level(opt.log_level);
 
-    let resources = env::var("PAS_RESOURCES").unwrap_or(String::from_str("resources/").unwrap());
+    if let Some(Command::EmitHeader { output }) = &opt.command {
+        let header = c_header();
+        match output {
+            Some(path) => fs::write(path, header)?,
+            None => io::stdout().write_all(header.as_bytes())?,
+        }
+        return Ok(());
+    }
+    if let Some(Command::Dump { file, json }) = &opt.command {
+        let mut input: Box<dyn Read> = match file {
+            Some(path) => Box::new(fs::File::open(path)?),
+            None => Box::new(stdin()),
+        };
+        let problems = dump::dump(&mut input, &mut io::stdout(), *json, opt.width, opt.height)?;
+        if problems > 0 {
+            eprintln!("{problems} problem(s) found");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // these modes do not talk to any server
+    let unused = match &opt.command {
+        Some(Command::PlayScenario { .. }) => Some("play-scenario"),
+        Some(Command::Replay { .. }) => Some("replay"),
+        None if opt.offline => Some("--offline"),
+        _ => None,
+    };
+    if let Some(mode) = unused {
+        opt.transport.check_unused(mode)?;
+    }
+
+    let config = Config::load(opt.config.as_deref())?;
+    let sprites = Sprites::load(Path::new(&opt.resources))?;
+    let recorder  = match &opt.record {
+        Some(path) => Some(Recorder::create(path, mode)?),
+        None => None,
+    };
     let (sx, rx) = std::sync::mpsc::channel();
-    let mut state = State::new(rx);
-    
-    thread::spawn(move || {
-        let mut buffer = [0_u8; std::mem::size_of::<pas_cman_ipl::pascman_protocol::Message>()];
-        while stdin().read_exact(&mut buffer).is_ok() {
-            unsafe {
-                let message = buffer.as_mut_ptr() as *mut pas_cman_ipl::pascman_protocol::Message;
-                sx.send(*message).expect("error sending message on the channel");
+    let mut state = match opt.command.take() {
+        Some(Command::PlayScenario { file, stdout: true }) => {
+            let steps = scenario::compile_file(file, opt.width, opt.height)?;
+            let mut out = io::stdout();
+            scenario::play(&steps, |message| {
+                let written = match mode {
+                    ProtocolMode::Binary => out.write_all(&message.to_bytes()),
+                    ProtocolMode::Text   => writeln!(out, "{message}"),
+                };
+                written.and_then(|_| out.flush()).expect("could not write to stdout");
+            });
+            return Ok(());
+        },
+        Some(Command::PlayScenario { file, stdout: false }) => {
+            let steps = scenario::compile_file(file, opt.width, opt.height)?;
+            let recorder = recorder.clone();
+            thread::spawn(move || {
+                scenario::play(&steps, |message| forward(&sx, &recorder, message));
+            });
+            State::new(rx)
+        },
+        Some(Command::Replay { file, speed, at_time, at_index, paused }) => {
+            let mut replayer = Replayer::open(file)?;
+            replayer.set_speed(speed);
+            replayer.set_paused(paused);
+            if let Some(time) = at_time {
+                replayer.seek(Seek::Time(Duration::from_secs_f64(time.max(0.0))));
             }
-        }
-    });
-
-    let context = BTermBuilder::new()
-        .with_title("pas cman")
-        .with_dimensions(w, h)
-        //.with_fps_cap(30.0)
-        .with_tile_dimensions(32, 32)
-        .with_resource_path(resources)
-        .with_font("pas-cman-font-32.png", 32, 32)
-        .with_font("terminal8x8.png", 8, 8)
-        .with_simple_console(w, h,"pas-cman-font-32.png")
-        .with_simple_console_no_bg(w, h,"pas-cman-font-32.png")
-        .with_simple_console_no_bg(w, h,"pas-cman-font-32.png")
-        .with_simple_console(w*2, h*2, "terminal8x8.png")
-        .build()?;
+            if let Some(index) = at_index {
+                replayer.seek(Seek::Index(index));
+            }
+            State::replay(replayer)
+        },
+        Some(Command::EmitHeader { .. }) => unreachable!("the header has already been emitted"),
+        Some(Command::Dump { .. }) => unreachable!("the stream has already been dumped"),
+        None if opt.offline => {
+            let map = opt.map.clone().unwrap_or_else(|| Path::new(&opt.resources).join(MAP_FILE));
+            let messages = load_map_file(map, opt.width, opt.height)?;
+            let rules = GameRules::with_size(&messages, opt.width, opt.height);
+            for message in messages {
+                forward(&sx, &recorder, message);
+            }
+            forward(&sx, &recorder, Message::registration(1));
+            let mut state = State::offline(rx, OfflineGame::new(rules, sx));
+            state.resources.insert(opt.personality);
+            state
+        },
+        None => {
+            let recorder = recorder.clone();
+            let mut state = State::new(rx);
+            match &opt.transport {
+                Transport::Stdio => {
+                    thread::spawn(move || read_messages(stdin(), sx, recorder, mode));
+                },
+                Transport::Tcp(address) if opt.hot_seat => {
+                    let players = [connect(address, mode)?, connect(address, mode)?];
+                    let [(first, player1), (second, player2)] = players;
+                    state.resources.insert(DirectionSink::PerPlayer([player1, player2]));
+                    // both connections receive the same messages: only those
+                    // of the first one are shown
+                    thread::spawn(move || read_messages(first, sx, recorder, mode));
+                    thread::spawn(move || io::copy(&mut &second, &mut io::sink()));
+                },
+                Transport::Tcp(address) => {
+                    let (input, directions) = connect(address, mode)?;
+                    state.resources.insert(DirectionSink::Channel(directions));
+                    thread::spawn(move || read_messages(input, sx, recorder, mode));
+                },
+            }
+            state
+        },
+    };
+
+    state.resize(opt.width, opt.height);
+    state.configure(&config);
+    state.resources.insert(sprites);
+    if opt.hot_seat {
+        state.resources.insert(Some(config.hot_seat.clone()));
+    }
+    state.resources.insert(Prediction::new(opt.predict));
+    state.resources.insert(recorder);
+    state.resources.insert(mode);
+    if opt.strict {
+        state.resources.insert(Validator::with_size(ValidationMode::Strict, opt.width, opt.height));
+    }
 
     // initialization systems
     Schedule::builder()
@@ -44,7 +386,42 @@ fn main() -> BResult<()> {
         .execute(&mut state.ecs, &mut state.resources)
         ;
 
+    if opt.headless {
+        let headless = Headless {
+            format: opt.dump,
+            every: opt.dump_every,
+            max_frames: opt.max_frames,
+            frame_time: opt.fps.map_or(HEADLESS_FRAME_TIME, |fps| Duration::from_secs_f32(1.0 / fps)),
+            realtime: opt.fps.is_some(),
+        };
+        headless.run(&mut state, &mut io::stdout())?;
+        return Ok(());
+    }
+
+    windowed(&opt, &config.font, state)
+}
+
+/// Opens the window of the game and runs it until it is closed
+#[cfg(feature = "gui")]
+fn windowed(opt: &Opt, font: &FontConfig, state: State) -> BResult<()> {
+    let defaults = WindowOptions::default();
+    let options = WindowOptions {
+        title: opt.title.clone(),
+        width: opt.width,
+        height: opt.height,
+        tile_size: opt.tile_size.or(font.tile_size).unwrap_or(defaults.tile_size),
+        font: opt.font.clone().or_else(|| font.file.clone()).unwrap_or(defaults.font),
+        resources: opt.resources.clone(),
+        fps_cap: opt.fps,
+    };
+    let context = window::open_window(&options)?;
     main_loop(context, state)?;
 
     Ok(())
 }
+
+/// Without the `gui` feature, there is no window to open
+#[cfg(not(feature = "gui"))]
+fn windowed(_opt: &Opt, _font: &FontConfig, _state: State) -> BResult<()> {
+    Err("pas-cman-ipl was built without the `gui` feature: use --headless".into())
+}
@@ -0,0 +1,81 @@
+//! The smooth motion of the characters. The server moves a character one tile
+//! at a time, and applying each `MOVEMENT` as is makes the character jump from
+//! one tile to the next. Instead, a character which steps onto a neighbouring
+//! tile glides there over a short (configurable) duration: its `Position` is
+//! updated right away, but it is drawn somewhere between the place it comes
+//! from and that position.
+//!
+//! Only the steps in a straight line are interpolated: a character which is
+//! teleported (e.g. when it respawns) or which turns before the end of its
+//! glide is drawn at its new position right away.
+//!
+//! A `Glide` only remembers where the character was drawn when it started
+//! and for how long it has been gliding: the systems of the running game
+//! start it when a character moves, let the time flow and ask it where the
+//! character is to be drawn.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use crate::Position;
+
+/// How the characters move from one tile to the next
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionSettings {
+    /// How long (in milliseconds) it takes to glide from one tile to the
+    /// next one (0 means the characters jump from tile to tile)
+    pub glide: f32,
+}
+
+impl Default for MotionSettings {
+    fn default() -> Self {
+        Self { glide: 120.0 }
+    }
+}
+
+/// This component is attached to a character which glides towards its position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glide {
+    /// Where the glide started (in tiles, not necessarily a whole tile)
+    pub from: (f32, f32),
+    /// How long (in milliseconds) the character has been gliding
+    pub elapsed: f32,
+}
+
+impl Glide {
+    /// Returns the glide of a character stepping from `from` to `to` (`glide`
+    /// is where it is drawn, if it was still gliding) or `None` when the step
+    /// is not to be interpolated
+    pub fn start(settings: &MotionSettings, glide: Option<&Glide>, from: Position, to: Position) -> Option<Glide> {
+        let step = (from.x.abs_diff(to.x), from.y.abs_diff(to.y));
+        if settings.glide <= 0.0 || !matches!(step, (0, 1) | (1, 0)) {
+            return None;
+        }
+        let from = match glide.filter(|glide| !glide.is_over(settings)) {
+            None => (from.x as f32, from.y as f32),
+            Some(glide) => {
+                let (x, y) = glide.at(settings, from);
+                // turning while still gliding would cut the corner
+                let straight = (x - to.x as f32).abs() < f32::EPSILON || (y - to.y as f32).abs() < f32::EPSILON;
+                if !straight {
+                    return None;
+                }
+                (x, y)
+            },
+        };
+        Some(Glide { from, elapsed: 0.0 })
+    }
+
+    /// Returns where the character gliding towards `to` is to be drawn
+    pub fn at(&self, settings: &MotionSettings, to: Position) -> (f32, f32) {
+        let t = if settings.glide > 0.0 { (self.elapsed / settings.glide).clamp(0.0, 1.0) } else { 1.0 };
+        let (x, y) = (to.x as f32, to.y as f32);
+        (self.from.0 + (x - self.from.0) * t, self.from.1 + (y - self.from.1) * t)
+    }
+
+    /// Returns true iff the character has reached its position
+    pub fn is_over(&self, settings: &MotionSettings) -> bool {
+        self.elapsed >= settings.glide
+    }
+}
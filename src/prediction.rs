@@ -0,0 +1,137 @@
+//! The client-side prediction of the moves of the local player(s). Over a slow
+//! link, waiting for the `MOVEMENT` sent back by the server makes the hero feel
+//! laggy. When the prediction is enabled, the hero is moved as soon as a
+//! direction is sent (provided that `Map::can_enter` allows it) and the moves
+//! which have been predicted are remembered until the server confirms them.
+//!
+//! Each authoritative `MOVEMENT` is then reconciled with the oldest predicted
+//! move: when they agree, the move is confirmed and nothing needs to be done.
+//! When they disagree (or when the server does not confirm a move in time),
+//! the prediction was wrong: the hero snaps back to the place the server has
+//! decided and the correction is briefly shown on screen.
+//!
+//! How long a move may wait for its confirmation depends on the link: the time
+//! it took the server to confirm the previous moves is a measure of the round
+//! trip time, and a move is given a few round trips (within sane bounds)
+//! before it is deemed lost.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{collections::VecDeque, ops::RangeInclusive};
+
+use crate::Position;
+
+/// How long (in milliseconds) a predicted move may wait for its confirmation
+/// as long as the round trip time is unknown
+pub const PREDICTION_TIMEOUT: f32 = 1_000.0;
+/// The bounds (in milliseconds) of the time a predicted move may wait for its
+/// confirmation once the round trip time is known
+pub const PREDICTION_TIMEOUTS: RangeInclusive<f32> = 200.0..=5_000.0;
+/// How many round trips a predicted move may wait for its confirmation
+const ROUND_TRIPS: f32 = 4.0;
+/// The weight of the latest measure in the estimated round trip time
+const RTT_SMOOTHING: f32 = 0.125;
+/// How long (in milliseconds) a correction remains visible on screen
+pub const CORRECTION_DURATION: f32 = 300.0;
+
+/// The moves of the local players (1 and 2) which the server has not confirmed yet
+#[derive(Debug, Clone, Default)]
+pub struct Prediction {
+    /// Whether the moves are predicted at all
+    pub enabled: bool,
+    players: [Predicted; 2],
+    /// The estimated round trip time (in milliseconds), once a move has been
+    /// confirmed
+    rtt: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Predicted {
+    /// The predicted positions (oldest first) and how long they have been waiting
+    pending: VecDeque<(Position, f32)>,
+    /// The last position decided by the server
+    confirmed: Option<Position>,
+}
+
+/// What an authoritative move means for the prediction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Nothing was predicted: the move is to be applied as usual
+    Unpredicted,
+    /// The move had been predicted: the player already is where it should be
+    Confirmed,
+    /// The prediction was wrong: the player must go where the server has decided
+    Mispredicted,
+}
+
+impl Prediction {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, ..Self::default() }
+    }
+
+    /// Remembers that the given player has been moved from `from` to `to`
+    /// ahead of the server
+    pub fn predict(&mut self, player: u32, from: Position, to: Position) {
+        let predicted = &mut self.players[index(player)];
+        if predicted.pending.is_empty() {
+            predicted.confirmed = Some(from);
+        }
+        predicted.pending.push_back((to, 0.0));
+    }
+
+    /// Reconciles the position the server has decided for the given player
+    /// with the oldest predicted one
+    pub fn confirm(&mut self, player: u32, authoritative: Position) -> Verdict {
+        let predicted = &mut self.players[index(player)];
+        predicted.confirmed = Some(authoritative);
+        match predicted.pending.front() {
+            None => Verdict::Unpredicted,
+            Some((position, age)) if *position == authoritative => {
+                let sample = *age;
+                predicted.pending.pop_front();
+                self.rtt = Some(self.rtt.map_or(sample, |rtt| rtt + RTT_SMOOTHING * (sample - rtt)));
+                Verdict::Confirmed
+            },
+            Some(_) => {
+                predicted.pending.clear();
+                Verdict::Mispredicted
+            },
+        }
+    }
+
+    /// Lets `dt` milliseconds elapse. When the server has not confirmed the
+    /// oldest move of the given player in time, the prediction is dropped and
+    /// the position where the player must go back to is returned.
+    pub fn expire(&mut self, player: u32, dt: f32) -> Option<Position> {
+        let timeout = self.timeout();
+        let predicted = &mut self.players[index(player)];
+        predicted.pending.iter_mut().for_each(|(_, age)| *age += dt);
+        match predicted.pending.front() {
+            Some((_, age)) if *age > timeout => {
+                predicted.pending.clear();
+                predicted.confirmed
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns how long (in milliseconds) a predicted move may currently wait
+    /// for its confirmation
+    pub fn timeout(&self) -> f32 {
+        self.rtt.map_or(PREDICTION_TIMEOUT, |rtt| {
+            (ROUND_TRIPS * rtt).clamp(*PREDICTION_TIMEOUTS.start(), *PREDICTION_TIMEOUTS.end())
+        })
+    }
+
+    /// Forgets all the moves which have been predicted (the round trip time
+    /// is kept: the link has not changed)
+    pub fn clear(&mut self) {
+        self.players = Default::default();
+    }
+}
+
+fn index(player: u32) -> usize {
+    if player == 2 { 1 } else { 0 }
+}
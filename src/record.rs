@@ -0,0 +1,271 @@
+//! Recording of the protocol streams. When the game is started with `--record`,
+//! every message received by the interface and every direction it sends are
+//! written (along with a timestamp) to a replay file. This makes it possible to
+//! find out exactly what the interface received from a misbehaving server.
+//!
+//! A replay file starts with a header (all numbers are little endian):
+//!
+//! | offset | size | content                                              |
+//! |--------|------|------------------------------------------------------|
+//! | 0      | 6    | the magic bytes `PCMREC`                             |
+//! | 6      | 2    | the version of the file format                       |
+//! | 8      | 4    | the width of the map                                 |
+//! | 12     | 4    | the height of the map                                |
+//! | 16     | 4    | the protocol mode (0 = binary, 1 = text)             |
+//! | 20     | 8    | the date of the recording (seconds since the epoch)  |
+//! | 28     | 4    | the player id (0 until the registration is received) |
+//!
+//! It is followed by a sequence of entries. Each of them is made of a kind
+//! (1 byte: 0 = inbound message, 1 = outbound direction, 2 = outbound direction
+//! of a hot-seat player), a timestamp (8 bytes: microseconds since the
+//! beginning of the recording) and a payload. The payload of an inbound message
+//! is made of the MESSAGE_SIZE bytes which have been read, exactly as they were
+//! received (even when they do not make a valid message). In text mode, it is
+//! the binary encoding of the line which has been read (the lines which cannot
+//! be parsed are not recorded). The payload of an outbound direction is its
+//! value (4 bytes), preceded by the player who chose it (4 bytes) in hot-seat
+//! mode. Version 1 of the format, which has no hot-seat entries, is still read.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{fs::{self, File}, io::{self, ErrorKind, Seek, SeekFrom, Write}, path::Path, sync::{Arc, Mutex}, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use crate::{logging::{self, LogLevel}, pascman_protocol::{Direction, Message, MessageType, ProtocolMode, HEIGHT, MESSAGE_SIZE, WIDTH}};
+
+/// The magic bytes every replay file starts with
+pub const MAGIC: &[u8; 6] = b"PCMREC";
+/// The current version of the replay file format
+pub const VERSION: u16 = 2;
+/// The size (in bytes) of the header of a replay file
+pub const HEADER_SIZE: usize = 32;
+/// The offset of the player id in the header
+const PLAYER_OFFSET: u64 = 28;
+
+/// The kind of an entry of the replay file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// A message received by the interface
+    Inbound = 0,
+    /// A direction sent by the interface
+    Outbound = 1,
+    /// A direction sent by the interface on behalf of a hot-seat player
+    PlayerOutbound = 2,
+}
+
+/// The header of a replay file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub version: u16,
+    pub width: u32,
+    pub height: u32,
+    pub mode: ProtocolMode,
+    /// The date of the recording (in seconds since the epoch)
+    pub date: u64,
+    /// The player id (0 when no registration was received)
+    pub player: u32,
+}
+
+/// What happened at some point of the recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A message has been received by the interface
+    Inbound(Message),
+    /// A direction has been sent by the interface (on behalf of the given
+    /// player in hot-seat mode)
+    Outbound(Option<u32>, Direction),
+}
+
+/// One entry of a replay file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    /// The time elapsed since the beginning of the recording
+    pub timestamp: Duration,
+    pub event: Event,
+}
+
+impl Header {
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..6] != MAGIC {
+            return Err(invalid("this is not a replay file"));
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i+1]]);
+        let u32_at = |i: usize| u32::from_le_bytes([bytes[i], bytes[i+1], bytes[i+2], bytes[i+3]]);
+        let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i+8].try_into().unwrap());
+
+        let version = u16_at(6);
+        if !(1..=VERSION).contains(&version) {
+            return Err(invalid(format!("unsupported replay file version: {version}")));
+        }
+        let mode = match u32_at(16) {
+            0 => ProtocolMode::Binary,
+            1 => ProtocolMode::Text,
+            x => return Err(invalid(format!("unknown protocol mode: {x}"))),
+        };
+        Ok(Header {
+            version,
+            width: u32_at(8),
+            height: u32_at(12),
+            mode,
+            date: u64_at(20),
+            player: u32_at(28),
+        })
+    }
+
+    fn to_bytes(self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0_u8; HEADER_SIZE];
+        bytes[0..6].copy_from_slice(MAGIC);
+        bytes[6..8].copy_from_slice(&self.version.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.width.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.height.to_le_bytes());
+        bytes[16..20].copy_from_slice(&(self.mode as u32).to_le_bytes());
+        bytes[20..28].copy_from_slice(&self.date.to_le_bytes());
+        bytes[28..32].copy_from_slice(&self.player.to_le_bytes());
+        bytes
+    }
+}
+
+/// The recorder writes everything that goes through the interface to a
+/// replay file. It can be cloned and shared between several threads.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    inner: Arc<Mutex<RecorderInner>>,
+}
+
+#[derive(Debug)]
+struct RecorderInner {
+    file: File,
+    start: Instant,
+}
+
+impl Recorder {
+    /// Creates a new replay file at the given path
+    pub fn create<P: AsRef<Path>>(path: P, mode: ProtocolMode) -> io::Result<Self> {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let header = Header {
+            version: VERSION,
+            width: WIDTH as u32,
+            height: HEIGHT as u32,
+            mode,
+            date,
+            player: 0,
+        };
+
+        let mut file = File::create(path)?;
+        file.write_all(&header.to_bytes())?;
+        let start = Instant::now();
+        Ok(Self { inner: Arc::new(Mutex::new(RecorderInner { file, start })) })
+    }
+
+    /// Records the bytes of a message received by the interface, whether they
+    /// make a valid message or not
+    pub fn message(&self, bytes: &[u8; MESSAGE_SIZE]) {
+        let result = self.record(EntryKind::Inbound, bytes)
+            .and_then(|_| unsafe {
+                match Message::from_bytes(bytes) {
+                    Ok(message) if message.msgt == MessageType::REGISTRATION => self.register(message.registration.player),
+                    _ => Ok(()),
+                }
+            });
+        if let Err(e) = result {
+            logging::log(LogLevel::Error, format_args!("could not record message: {e}"));
+        }
+    }
+
+    /// Records a direction sent by the interface (on behalf of the given
+    /// player in hot-seat mode)
+    pub fn direction(&self, player: Option<u32>, direction: Direction) {
+        let direction = (direction as u32).to_le_bytes();
+        let result = match player {
+            None => self.record(EntryKind::Outbound, &direction),
+            Some(player) => self.record(EntryKind::PlayerOutbound, &[player.to_le_bytes(), direction].concat()),
+        };
+        if let Err(e) = result {
+            logging::log(LogLevel::Error, format_args!("could not record direction: {e}"));
+        }
+    }
+
+    /// Appends one entry to the replay file
+    fn record(&self, kind: EntryKind, payload: &[u8]) -> io::Result<()> {
+        let mut inner = self.inner.lock().expect("poisoned recorder");
+        let timestamp = inner.start.elapsed().as_micros() as u64;
+
+        let mut entry = Vec::with_capacity(9 + payload.len());
+        entry.push(kind as u8);
+        entry.extend_from_slice(&timestamp.to_le_bytes());
+        entry.extend_from_slice(payload);
+        inner.file.write_all(&entry)
+    }
+
+    /// Updates the player id in the header of the replay file
+    fn register(&self, player: u32) -> io::Result<()> {
+        let mut inner = self.inner.lock().expect("poisoned recorder");
+        inner.file.seek(SeekFrom::Start(PLAYER_OFFSET))?;
+        inner.file.write_all(&player.to_le_bytes())?;
+        inner.file.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+}
+
+/// Reads the replay file at the given path. A truncated entry at the end of
+/// the file (e.g. because the interface crashed while writing it) is ignored.
+/// The entries whose payload is not a valid message or direction (e.g. because
+/// a misbehaving server sent them) are skipped and reported on the standard
+/// error.
+pub fn read_replay<P: AsRef<Path>>(path: P) -> io::Result<(Header, Vec<Entry>)> {
+    let bytes  = fs::read(path)?;
+    let header = Header::from_bytes(&bytes)?;
+
+    let mut entries = vec![];
+    let mut rest = &bytes[HEADER_SIZE..];
+    for n in 0.. {
+        if rest.len() < 9 {
+            break;
+        }
+        let kind = rest[0];
+        let timestamp = Duration::from_micros(u64::from_le_bytes(rest[1..9].try_into().unwrap()));
+        let payload = &rest[9..];
+        let (event, size) = match kind {
+            0 if payload.len() >= MESSAGE_SIZE => {
+                let event = Message::from_bytes(payload[..MESSAGE_SIZE].try_into().unwrap())
+                    .map(Event::Inbound)
+                    .map_err(|e| e.to_string());
+                (event, MESSAGE_SIZE)
+            },
+            1 if payload.len() >= 4 => {
+                let event = Direction::try_from(u32_at(payload, 0))
+                    .map(|direction| Event::Outbound(None, direction))
+                    .map_err(|x| format!("invalid direction: {x}"));
+                (event, 4)
+            },
+            2 if payload.len() >= 8 => {
+                let event = Direction::try_from(u32_at(payload, 4))
+                    .map(|direction| Event::Outbound(Some(u32_at(payload, 0)), direction))
+                    .map_err(|x| format!("invalid direction: {x}"));
+                (event, 8)
+            },
+            0..=2 => break,
+            x => return Err(invalid(format!("invalid entry kind: {x}"))),
+        };
+        match event {
+            Ok(event) => entries.push(Entry { timestamp, event }),
+            Err(e) => logging::log(LogLevel::Warn, format_args!(
+                "skipping entry #{n} of the replay (at {:.3}s): {e}", timestamp.as_secs_f64())),
+        }
+        rest = &payload[size..];
+    }
+    Ok((header, entries))
+}
+
+/// Reads the little endian u32 at the given offset
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, error)
+}
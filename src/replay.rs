@@ -0,0 +1,204 @@
+//! The replay of a recorded session (see the `record` module). The recorded
+//! messages are fed back to the game with their original timing, and the
+//! replay can be controlled with the keyboard:
+//!
+//! * SPACE        pauses/resumes the replay
+//! * RIGHT        delivers the next message only (and pauses the replay)
+//! * LEFT         goes back to the previous message (and pauses the replay)
+//! * UP / DOWN    doubles/halves the speed of the replay (from 0.25x up to 8x)
+//! * PAGE DOWN    goes 10 seconds forward
+//! * PAGE UP      goes 10 seconds backward
+//! * HOME         restarts the replay from the beginning
+//!
+//! Going backwards is implemented by a deterministic re-simulation: the game is
+//! reset and all the messages preceding the target are delivered at once.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{io, path::Path, time::Duration};
+
+use bracket_color::prelude::{ColorPair, BLACK, YELLOW};
+use bracket_geometry::prelude::Point;
+
+use crate::{frame::{Frame, MESSAGE_LAYER}, keys::VirtualKeyCode, pascman_protocol::{Direction, Message}, record::{read_replay, Entry, Event, Header}, State};
+
+/// The slowest speed at which a session can be replayed
+pub const MIN_SPEED: f32 = 0.25;
+/// The fastest speed at which a session can be replayed
+pub const MAX_SPEED: f32 = 8.0;
+/// How far a PAGE UP/PAGE DOWN moves in the replay
+const SEEK_STEP: Duration = Duration::from_secs(10);
+
+/// A position in the replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Seek {
+    /// The replay goes to the given time (all messages before that time are delivered)
+    Time(Duration),
+    /// The replay goes to the given message index (all messages before that index are delivered)
+    Index(usize),
+}
+
+/// The replayer owns the recorded session and decides which messages must be
+/// delivered to the game at each tick.
+#[derive(Debug, Clone)]
+pub struct Replayer {
+    pub header: Header,
+    /// The recorded messages (along with their timestamp)
+    messages: Vec<(Duration, Message)>,
+    /// The recorded directions (along with their timestamp and the hot-seat
+    /// player who chose them)
+    directions: Vec<(Duration, Option<u32>, Direction)>,
+    /// The index of the next message to deliver
+    cursor: usize,
+    /// The current time of the replay
+    clock: Duration,
+    speed: f32,
+    paused: bool,
+    /// A seek which must be performed upon the next update
+    pending: Option<Seek>,
+}
+
+impl Replayer {
+    pub fn new(header: Header, entries: Vec<Entry>) -> Self {
+        let mut messages   = vec![];
+        let mut directions = vec![];
+        for Entry { timestamp, event } in entries {
+            match event {
+                Event::Inbound(message)            => messages.push((timestamp, message)),
+                Event::Outbound(player, direction) => directions.push((timestamp, player, direction)),
+            }
+        }
+        Self { header, messages, directions, cursor: 0, clock: Duration::ZERO, speed: 1.0, paused: false, pending: None }
+    }
+
+    /// Opens the replay file at the given path
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let (header, entries) = read_replay(path)?;
+        Ok(Self::new(header, entries))
+    }
+
+    /// Sets the speed of the replay (clamped between MIN_SPEED and MAX_SPEED)
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Asks the replay to go to the given position upon the next update
+    pub fn seek(&mut self, seek: Seek) {
+        self.pending = Some(seek);
+    }
+
+    /// Returns the total duration of the replay
+    pub fn duration(&self) -> Duration {
+        self.messages.last().map(|(t, _)| *t).unwrap_or_default()
+    }
+
+    /// Updates the replay: reacts to the key that has been pressed, lets the
+    /// time flow and delivers all the messages that are due to the game.
+    pub fn update(&mut self, state: &mut State, key: Option<VirtualKeyCode>, frame_time_ms: f32) {
+        match key {
+            Some(VirtualKeyCode::Space)    => self.paused = !self.paused,
+            Some(VirtualKeyCode::Up)       => self.set_speed(self.speed * 2.0),
+            Some(VirtualKeyCode::Down)     => self.set_speed(self.speed / 2.0),
+            Some(VirtualKeyCode::Home)     => self.seek(Seek::Index(0)),
+            Some(VirtualKeyCode::PageDown) => self.seek(Seek::Time(self.clock + SEEK_STEP)),
+            Some(VirtualKeyCode::PageUp)   => self.seek(Seek::Time(self.clock.saturating_sub(SEEK_STEP))),
+            Some(VirtualKeyCode::Right)    => {
+                self.paused = true;
+                self.seek(Seek::Index(self.cursor + 1));
+            },
+            Some(VirtualKeyCode::Left)     => {
+                self.paused = true;
+                self.seek(Seek::Index(self.cursor.saturating_sub(1)));
+            },
+            _ => { /* do nothing */ },
+        }
+
+        if let Some(seek) = self.pending.take() {
+            self.goto(state, seek);
+        } else if !self.paused {
+            self.clock += Duration::from_secs_f32(frame_time_ms.max(0.0) * self.speed / 1000.0);
+            self.deliver_until(state, self.clock);
+        }
+    }
+
+    /// Draws the status of the replay on the last line of the message layer
+    pub fn render(&self, frame: &mut Frame) {
+        let status = if self.paused { "PAUSED" } else { "PLAYING" };
+        let last_direction = self.directions.iter()
+            .take_while(|(t, _, _)| *t <= self.clock)
+            .last()
+            .map(|(_, player, d)| match player {
+                None => format!("{d:?}"),
+                Some(player) => format!("P{player}:{d:?}"),
+            })
+            .unwrap_or_else(|| "-".to_string());
+        let text = format!(
+            "REPLAY {status} x{} #{}/{} {:.1}s/{:.1}s sent:{last_direction}",
+            self.speed,
+            self.cursor,
+            self.messages.len(),
+            self.clock.as_secs_f32(),
+            self.duration().as_secs_f32(),
+        );
+
+        let layer = &mut frame.layers[MESSAGE_LAYER];
+        let y = layer.height - 1;
+        layer.print(Point::new(0, y), &text, ColorPair::new(YELLOW, BLACK));
+    }
+
+    /// Returns the index of the next message to deliver
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns true iff all the messages have been delivered
+    pub fn is_finished(&self) -> bool {
+        self.cursor == self.messages.len()
+    }
+
+    /// Goes to the given position in the replay. When going backwards, the
+    /// game is reset and all the messages are delivered again from the start.
+    fn goto(&mut self, state: &mut State, seek: Seek) {
+        let (index, clock) = match seek {
+            Seek::Index(index) => {
+                let index = index.min(self.messages.len());
+                let clock = index.checked_sub(1)
+                    .map(|i| self.messages[i].0)
+                    .unwrap_or_default();
+                (index, clock)
+            },
+            Seek::Time(clock) => {
+                let clock = clock.min(self.duration());
+                let index = self.messages.partition_point(|(t, _)| *t <= clock);
+                (index, clock)
+            },
+        };
+
+        if index < self.cursor {
+            state.reset();
+            self.cursor = 0;
+        }
+        while self.cursor < index {
+            state.process(self.messages[self.cursor].1);
+            self.cursor += 1;
+        }
+        self.clock = clock;
+    }
+
+    /// Delivers all the messages which are due at the given time
+    fn deliver_until(&mut self, state: &mut State, clock: Duration) {
+        while let Some((t, message)) = self.messages.get(self.cursor) {
+            if *t > clock {
+                break;
+            }
+            state.process(*message);
+            self.cursor += 1;
+        }
+    }
+}
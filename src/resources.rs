@@ -5,12 +5,30 @@
 //! Date:    March 2023
 //! Licence: MIT 
 
-use std::ops::Index;
+use std::{ops::Index, sync::mpsc::Sender};
 
-use bracket_lib::{pathfinding::{Algorithm2D, BaseMap, SmallVec}, terminal::{DistanceAlg, Point}};
+use bracket_geometry::prelude::{DistanceAlg, Point};
+use bracket_pathfinding::prelude::{Algorithm2D, BaseMap, SmallVec};
 
 use crate::Position;
 
+/// Where the directions chosen by the user are sent
+#[derive(Debug, Clone, Default)]
+pub enum DirectionSink {
+    /// Written on the standard output (in the protocol mode of the game)
+    #[default]
+    Stdout,
+    /// Sent on a channel (to the C API or to the tcp transport)
+    Channel(Sender<crate::pascman_protocol::Direction>),
+    /// Sent on the channel of the player who chose them (in hot-seat mode,
+    /// each player has its own connection to the server)
+    PerPlayer([Sender<crate::pascman_protocol::Direction>; 2]),
+}
+
+/// The number of milliseconds that elapsed since the previous game tick
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameTime(pub f32);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TileType {
     Wall,
@@ -21,7 +39,10 @@ pub enum TileType {
 pub struct Map{
     pub width: usize,
     pub height: usize,
-    pub tiles: Vec<TileType>
+    pub tiles: Vec<TileType>,
+    /// Bumped whenever a tile is changed (so that the map is only drawn
+    /// again when it has changed)
+    pub generation: u64,
 }
 
 impl Index<Position> for Map {
@@ -41,6 +62,11 @@ impl Index<Point> for Map {
 }
 
 impl Map {
+    /// Creates a map of the given size (in tiles) which is only made of floor
+    pub fn new(width: usize, height: usize) -> Self {
+        Map { width, height, tiles: vec![TileType::Floor; width * height], generation: 0 }
+    }
+
     /// Returns true iff the entity is allowed to move on to the next position (x,y)
     pub fn can_enter(&self, dest: Point) -> bool {
         self.in_bounds(dest) && self[dest] == TileType::Floor
@@ -63,7 +89,7 @@ impl Algorithm2D for Map {
     fn index_to_point2d(&self, idx: usize) -> Point {
         let x = idx % self.width;
         let y = idx / self.width;
-        bracket_lib::prelude::Point::new(x, y)
+        Point::new(x, y)
     }
 }
 
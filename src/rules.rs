@@ -0,0 +1,317 @@
+//! The authoritative rules of the game. This is a port of the game logic from
+//! `game.c` (see `process_user_command`): it decides where a player goes, what
+//! it eats, how many points it earns and who wins the game.
+//!
+//! The rules engine does not perform any I/O: it takes the current state and
+//! the direction chosen by a player, and returns the list of messages that
+//! must be broadcast to the clients. This is why it can be shared by anything
+//! that needs to apply the rules (a server, a bot, a test harness...).
+//!
+//! Besides the duel of `game.c`, the rules also support a hunt, in which the
+//! second player is a villain hunting the first one (this is how the game is
+//! played offline, see the `offline` systems).
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use crate::{item_id_with_size, load_map, Map, TileType, pascman_protocol::{Direction, Item, Message, MessageType, Position, HEIGHT, WIDTH}};
+
+/// The number of players taking part in a game
+pub const NB_PLAYERS: usize = 2;
+/// The number of points earned when eating some food
+pub const FOOD_POINTS: u32 = 1;
+/// The number of points earned when eating some superfood
+pub const SUPERFOOD_POINTS: u32 = 17;
+/// The number of points earned by the hero when eating a frightened villain
+pub const VILLAIN_POINTS: u32 = 50;
+
+/// How the two players face each other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Both players compete for the food, as in `game.c`: when one of them
+    /// bumps into the other, the game is over and the best score wins
+    #[default]
+    Duel,
+    /// The second player is a villain hunting the first one (the hero). The
+    /// villain eats nothing and wins as soon as it catches the hero, while the
+    /// hero wins by eating all the food. When the hero eats some superfood,
+    /// the villain is frightened (until it is calmed down): if the hero and
+    /// the villain meet in the meantime, the villain is eaten and goes back
+    /// to where it started.
+    Hunt,
+}
+
+/// The state of the game as it is maintained by the server
+/// (this is the same as `struct GameState` in `game.h`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRules {
+    /// The item located at each position of the map (players excluded)
+    map: Vec<Item>,
+    /// The size of the map (in tiles)
+    width: usize,
+    height: usize,
+    /// The score of each player
+    scores: [u32; NB_PLAYERS],
+    /// The number of items which can still be eaten on the map
+    food_count: usize,
+    /// The position of each player
+    positions: [Position; NB_PLAYERS],
+    /// Is the game over ?
+    game_over: bool,
+    /// How the players face each other
+    mode: Mode,
+    /// The position where each player started
+    homes: [Position; NB_PLAYERS],
+    /// Is the villain frightened ? (in a hunt only)
+    frightened: bool,
+}
+
+impl GameRules {
+    /// Creates the game state corresponding to the map introduced by the
+    /// given messages (typically, those produced by `load_map`).
+    pub fn new(messages: &[Message]) -> Self {
+        Self::with_size(messages, WIDTH, HEIGHT)
+    }
+
+    /// Creates the game state corresponding to the map of `width` x `height`
+    /// tiles introduced by the given messages (typically, those produced by
+    /// `load_map_with_size`).
+    pub fn with_size(messages: &[Message], width: usize, height: usize) -> Self {
+        let mut rules = GameRules {
+            map: vec![Item::WALL; width * height],
+            width,
+            height,
+            scores: [0; NB_PLAYERS],
+            food_count: 0,
+            positions: [Position { x: 0, y: 0 }; NB_PLAYERS],
+            game_over: false,
+            mode: Mode::Duel,
+            homes: [Position { x: 0, y: 0 }; NB_PLAYERS],
+            frightened: false,
+        };
+
+        for message in messages {
+            unsafe {
+                match message.msgt {
+                    MessageType::SPAWN => {
+                        let spawn = message.spawn;
+                        let Some(index) = rules.index(spawn.pos) else {
+                            continue;
+                        };
+                        match spawn.item {
+                            Item::WALL | Item::FLOOR => {
+                                rules.map[index] = spawn.item;
+                            },
+                            Item::FOOD | Item::SUPERFOOD => {
+                                rules.map[index] = spawn.item;
+                                rules.food_count += 1;
+                            },
+                            Item::PLAYER1 => rules.positions[0] = spawn.pos,
+                            Item::PLAYER2 => rules.positions[1] = spawn.pos,
+                        }
+                    },
+                    MessageType::GAME_OVER => rules.game_over = true,
+                    _ => { /* do nothing */ },
+                }
+            }
+        }
+        rules.homes = rules.positions;
+        rules
+    }
+
+    /// Changes how the players face each other
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Loads the textual map and returns both the game state and the
+    /// messages which must be sent to introduce that map to the clients.
+    pub fn from_map(text: &str) -> (Self, Vec<Message>) {
+        let messages = load_map(text);
+        (Self::new(&messages), messages)
+    }
+
+    /// Returns true iff the game is over
+    pub fn is_over(&self) -> bool {
+        self.game_over
+    }
+
+    /// Returns the number of items which can still be eaten on the map
+    pub fn food_count(&self) -> usize {
+        self.food_count
+    }
+
+    /// Returns the score of the given player (None when the item is not
+    /// PLAYER1 or PLAYER2)
+    pub fn score(&self, player: Item) -> Option<u32> {
+        player_offset(player).map(|offset| self.scores[offset])
+    }
+
+    /// Returns the position of the given player (None when the item is not
+    /// PLAYER1 or PLAYER2)
+    pub fn position(&self, player: Item) -> Option<Position> {
+        player_offset(player).map(|offset| self.positions[offset])
+    }
+
+    /// Returns true iff the villain is frightened (in a hunt)
+    pub fn is_frightened(&self) -> bool {
+        self.frightened
+    }
+
+    /// Calms the villain down: it is not frightened anymore
+    pub fn calm(&mut self) {
+        self.frightened = false;
+    }
+
+    /// Returns the size of the map (in tiles)
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the item located at the given position (players excluded).
+    /// Everything outside of the map is a wall.
+    pub fn item_at(&self, pos: Position) -> Item {
+        self.index(pos).map_or(Item::WALL, |index| self.map[index])
+    }
+
+    /// Returns the offset of the given position in the map (if it lies on the map)
+    fn index(&self, pos: Position) -> Option<usize> {
+        let (x, y) = (pos.x as usize, pos.y as usize);
+        (x < self.width && y < self.height).then_some(y * self.width + x)
+    }
+
+    /// Returns the position one would reach by going one step in the given
+    /// direction without leaving the map (see `next_position`)
+    pub fn step(&self, pos: Position, dir: Direction) -> Position {
+        step(pos, dir, self.width, self.height)
+    }
+
+    /// Returns the identifier of the given item located at `pos`
+    fn id(&self, pos: Position, item: Item) -> u32 {
+        item_id_with_size(self.width, self.height, pos.x, pos.y, item)
+    }
+
+    /// Returns the map as it is seen by the villains driven by the computer
+    /// (see the `ai` module): everything but the walls is some floor
+    pub fn to_map(&self) -> Map {
+        let mut map = Map::new(self.width, self.height);
+        for (tile, item) in map.tiles.iter_mut().zip(self.map.iter()) {
+            if *item == Item::WALL {
+                *tile = TileType::Wall;
+            }
+        }
+        map
+    }
+
+    /// Returns the number (1 or 2) of the player who is currently winning the
+    /// game. Just like in `game.c`, the second player wins a duel in case of a
+    /// tie. In a hunt, the hero only wins once all the food has been eaten.
+    pub fn winner(&self) -> u32 {
+        match self.mode {
+            Mode::Duel => if self.scores[0] > self.scores[1] { 1 } else { 2 },
+            Mode::Hunt => if self.food_count == 0 { 1 } else { 2 },
+        }
+    }
+
+    /// Processes one command from the given player (PLAYER1 or PLAYER2). This
+    /// updates the state and returns the messages which must be broadcast to
+    /// the clients (there are none when the item is not a player).
+    pub fn play(&mut self, player: Item, dir: Direction) -> Vec<Message> {
+        let Some(offset) = player_offset(player) else {
+            return vec![];
+        };
+        if self.game_over {
+            return vec![Message::game_over(self.winner())];
+        }
+
+        let next   = self.step(self.positions[offset], dir);
+        let other  = self.positions[(offset + 1) % NB_PLAYERS];
+
+        let mut messages = vec![];
+        if next == other {
+            if self.mode == Mode::Hunt && self.frightened {
+                // the villain is eaten and goes back home
+                let home = self.homes[1];
+                self.frightened = false;
+                self.scores[0] += VILLAIN_POINTS;
+                self.positions[1] = home;
+                messages.push(Message::movement(self.id(home, Item::PLAYER2), home));
+                if player == Item::PLAYER2 {
+                    return messages;
+                }
+            } else {
+                // when the other player is on the destination, the game is over
+                self.game_over = true;
+                return vec![Message::game_over(self.winner())];
+            }
+        }
+
+        let Some(index) = self.index(next) else {
+            return messages;
+        };
+        let at_next = self.map[index];
+        let eats = !(self.mode == Mode::Hunt && player == Item::PLAYER2);
+        match at_next {
+            Item::FLOOR => {
+                self.positions[offset] = next;
+                messages.push(Message::movement(self.id(next, player), next));
+            },
+            Item::FOOD | Item::SUPERFOOD if !eats => {
+                self.positions[offset] = next;
+                messages.push(Message::movement(self.id(next, player), next));
+            },
+            Item::FOOD | Item::SUPERFOOD => {
+                self.map[index] = Item::FLOOR;
+                self.positions[offset] = next;
+                self.scores[offset] += if at_next == Item::FOOD { FOOD_POINTS } else { SUPERFOOD_POINTS };
+                self.food_count -= 1;
+                if self.mode == Mode::Hunt && at_next == Item::SUPERFOOD {
+                    self.frightened = true;
+                }
+                if self.food_count == 0 {
+                    self.game_over = true;
+                }
+                messages.push(Message::movement(self.id(next, player), next));
+                messages.push(Message::eat_food(self.id(next, player), self.id(next, at_next)));
+            },
+            _ => { /* do nothing */ },
+        }
+
+        if self.game_over {
+            messages.push(Message::game_over(self.winner()));
+        }
+        messages
+    }
+}
+
+/// Returns the offset of the given player in the arrays of the game state
+/// (None when the item is not a player)
+fn player_offset(player: Item) -> Option<usize> {
+    match player {
+        Item::PLAYER1 => Some(0),
+        Item::PLAYER2 => Some(1),
+        _ => None,
+    }
+}
+
+/// Returns the position one would reach by going one step in the given
+/// direction without leaving the WIDTH x HEIGHT map. Note: the returned
+/// position might be impossible to reach (e.g. because it is a wall).
+pub fn next_position(pos: Position, dir: Direction) -> Position {
+    step(pos, dir, WIDTH, HEIGHT)
+}
+
+/// Returns the position one would reach by going one step in the given
+/// direction without leaving the map of `width` x `height` tiles
+fn step(pos: Position, dir: Direction, width: usize, height: usize) -> Position {
+    let mut next = pos;
+    match dir {
+        Direction::UP    => if next.y > 0 { next.y -= 1 },
+        Direction::DOWN  => if (next.y as usize) < height - 1 { next.y += 1 },
+        Direction::LEFT  => if next.x > 0 { next.x -= 1 },
+        Direction::RIGHT => if (next.x as usize) < width - 1 { next.x += 1 },
+    }
+    next
+}
@@ -0,0 +1,241 @@
+//! A tiny scripting language to describe scenarios. A scenario is compiled
+//! into the timed sequence of messages a server would send to the game, which
+//! makes it easy to demo or test the interface without writing any C code.
+//!
+//! A scenario is a text file with one command per line:
+//!
+//! ```text
+//! # this is a comment
+//! load map.txt            # introduces the map (path relative to the scenario)
+//! register 1              # tells the interface it plays as the first player
+//! wait 500ms              # waits a little before sending the next message
+//! pace 250ms              # time between two steps of a move (default 250ms)
+//! move p1 right x4        # player 1 goes 4 times right (following the rules)
+//! spawn 12 FOOD 3 4       # introduces some item in the game
+//! movement 1800 3 4       # moves an item (without checking any rule)
+//! eat p1 12               # tells that someone ate some food
+//! game_over 1             # tells that the game is over
+//! ```
+//!
+//! The `move` command applies the rules of the game (see the `rules` module),
+//! so it yields the very same MOVEMENT, EAT_FOOD and GAME_OVER messages as a
+//! server would. The other commands send their message as is.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{fmt, fs, path::Path, thread, time::Duration};
+
+use crate::{load_map_with_size, pascman_protocol::{Direction, Item, Message, Position, HEIGHT, WIDTH}, player_ids, rules::GameRules};
+
+/// The time between two steps of a move when the scenario does not say otherwise
+const DEFAULT_PACE: Duration = Duration::from_millis(250);
+
+/// One message of a scenario along with the time to wait before sending it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub delay: Duration,
+    pub message: Message,
+}
+
+/// The error which is raised when a scenario cannot be compiled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioError {
+    /// The line (starting at 1) where the error occurred
+    pub line: usize,
+    pub reason: String,
+}
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+impl std::error::Error for ScenarioError {}
+
+/// Reads the scenario stored in the file at `path` and compiles it for a map
+/// of `width` x `height` tiles
+pub fn compile_file<P: AsRef<Path>>(path: P, width: usize, height: usize) -> Result<Vec<Step>, Box<dyn std::error::Error + Send + Sync>> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    Ok(compile_with_size(&text, base, width, height)?)
+}
+
+/// Compiles the given scenario into a sequence of timed messages. The maps
+/// loaded by the scenario are looked up relatively to the `base` directory.
+pub fn compile(text: &str, base: &Path) -> Result<Vec<Step>, ScenarioError> {
+    compile_with_size(text, base, WIDTH, HEIGHT)
+}
+
+/// Same as `compile` for a map of `width` x `height` tiles (the ids of the
+/// items depend on the size of the map)
+pub fn compile_with_size(text: &str, base: &Path, width: usize, height: usize) -> Result<Vec<Step>, ScenarioError> {
+    let mut compiler = Compiler { base, width, height, steps: vec![], rules: None, delay: Duration::ZERO, pace: DEFAULT_PACE };
+    for (n, line) in text.lines().enumerate() {
+        compiler.line(line)
+            .map_err(|reason| ScenarioError { line: n + 1, reason })?;
+    }
+    Ok(compiler.steps)
+}
+
+/// Sends all the messages of the scenario (in order and with the right
+/// timing) to the given function
+pub fn play<F: FnMut(Message)>(steps: &[Step], mut send: F) {
+    for step in steps {
+        thread::sleep(step.delay);
+        send(step.message);
+    }
+}
+
+/// The state of the compilation of a scenario
+struct Compiler<'a> {
+    /// The directory from which maps are loaded
+    base: &'a Path,
+    /// The size of the map (in tiles)
+    width: usize,
+    height: usize,
+    /// The steps that have been compiled so far
+    steps: Vec<Step>,
+    /// The rules of the game (available once a map has been loaded)
+    rules: Option<GameRules>,
+    /// The time to wait before sending the next message
+    delay: Duration,
+    /// The time between two steps of a move
+    pace: Duration,
+}
+
+impl Compiler<'_> {
+    /// Compiles one line of the scenario
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        let line = line.split('#').next().unwrap_or_default();
+        let words = line.split_whitespace().collect::<Vec<_>>();
+
+        match words.as_slice() {
+            [] => {},
+            ["load", path] => {
+                let text = fs::read_to_string(self.base.join(path))
+                    .map_err(|e| format!("cannot load '{path}': {e}"))?;
+                let messages = load_map_with_size(&text, self.width, self.height);
+                self.rules = Some(GameRules::with_size(&messages, self.width, self.height));
+                messages.into_iter().for_each(|m| self.push(m));
+            },
+            ["register", player] => {
+                let player = number(player)?;
+                self.push(Message::registration(player));
+            },
+            ["wait", duration] => {
+                self.delay += parse_duration(duration)?;
+            },
+            ["pace", duration] => {
+                self.pace = parse_duration(duration)?;
+            },
+            ["move", player, direction] => {
+                self.play(player, direction, 1)?;
+            },
+            ["move", player, direction, times] => {
+                let times = times.strip_prefix('x')
+                    .ok_or_else(|| format!("expected a repetition such as 'x4', got '{times}'"))?;
+                self.play(player, direction, number(times)?)?;
+            },
+            ["spawn", id, item, x, y] => {
+                let pos = Position { x: number(x)?, y: number(y)? };
+                self.push(Message::spawn(self.id_of(id)?, parse_item(item)?, pos));
+            },
+            ["movement", id, x, y] => {
+                let pos = Position { x: number(x)?, y: number(y)? };
+                self.push(Message::movement(self.id_of(id)?, pos));
+            },
+            ["eat", eater, food] => {
+                self.push(Message::eat_food(self.id_of(eater)?, self.id_of(food)?));
+            },
+            ["game_over", winner] => {
+                self.push(Message::game_over(number(winner)?));
+            },
+            [command, ..] => {
+                return Err(format!("unknown command or wrong number of arguments: '{command}'"));
+            },
+        }
+        Ok(())
+    }
+
+    /// Parses an identifier: either a number or a player (p1, p2)
+    fn id_of(&self, word: &str) -> Result<u32, String> {
+        let [player1, player2] = player_ids(self.width, self.height);
+        match word {
+            "p1" => Ok(player1),
+            "p2" => Ok(player2),
+            _    => number(word),
+        }
+    }
+
+    /// Appends a message to the scenario
+    fn push(&mut self, message: Message) {
+        self.steps.push(Step { delay: self.delay, message });
+        self.delay = Duration::ZERO;
+    }
+
+    /// Lets a player move `times` times in the given direction
+    fn play(&mut self, player: &str, direction: &str, times: u32) -> Result<(), String> {
+        let player = parse_player(player)?;
+        let direction = parse_direction(direction)?;
+        for _ in 0..times {
+            let rules = self.rules.as_mut()
+                .ok_or_else(|| "a map must be loaded before anyone moves".to_string())?;
+            let messages = rules.play(player, direction);
+            self.delay += self.pace;
+            messages.into_iter().for_each(|m| self.push(m));
+        }
+        Ok(())
+    }
+}
+
+fn number(word: &str) -> Result<u32, String> {
+    word.parse().map_err(|_| format!("expected a number, got '{word}'"))
+}
+
+fn parse_player(word: &str) -> Result<Item, String> {
+    match word {
+        "p1" => Ok(Item::PLAYER1),
+        "p2" => Ok(Item::PLAYER2),
+        _    => Err(format!("expected a player (p1 or p2), got '{word}'")),
+    }
+}
+
+/// Parses a direction such as 'up' or 'left'
+pub fn parse_direction(word: &str) -> Result<Direction, String> {
+    match word {
+        "up"    => Ok(Direction::UP),
+        "down"  => Ok(Direction::DOWN),
+        "left"  => Ok(Direction::LEFT),
+        "right" => Ok(Direction::RIGHT),
+        _       => Err(format!("expected a direction (up, down, left, right), got '{word}'")),
+    }
+}
+
+fn parse_item(word: &str) -> Result<Item, String> {
+    match word {
+        "WALL"      => Ok(Item::WALL),
+        "FLOOR"     => Ok(Item::FLOOR),
+        "FOOD"      => Ok(Item::FOOD),
+        "SUPERFOOD" => Ok(Item::SUPERFOOD),
+        "PLAYER1"   => Ok(Item::PLAYER1),
+        "PLAYER2"   => Ok(Item::PLAYER2),
+        _           => Err(format!("unknown item '{word}'")),
+    }
+}
+
+/// Parses a duration such as '500ms' or '2s'
+pub fn parse_duration(word: &str) -> Result<Duration, String> {
+    let invalid = || format!("expected a duration such as '500ms' or '2s', got '{word}'");
+    if let Some(ms) = word.strip_suffix("ms") {
+        ms.parse::<u64>().map(Duration::from_millis).map_err(|_| invalid())
+    } else if let Some(s) = word.strip_suffix('s') {
+        s.parse::<f64>().ok()
+            .filter(|s| s.is_finite() && *s >= 0.0)
+            .map(Duration::from_secs_f64)
+            .ok_or_else(invalid)
+    } else {
+        Err(invalid())
+    }
+}
@@ -1,25 +1,20 @@
 use legion::World;
 
-use crate::*;
+use crate::{animation::Sprites, *};
 
-static PLAYER_MARKS : [[char; 4]; 2] = [
-    ['@', 'P', '`', 'p'], // hero
-    ['!', '1', 'A', '!'], // 'villain 1'
-];
-
-pub fn spawn_player1 (ecs : &mut World, id: u32, pos : Position) {
+pub fn spawn_player1 (ecs : &mut World, sprites: &Sprites, id: u32, pos : Position) {
     ecs.push((
         Id(id),
-        Character(&PLAYER_MARKS[0]),
+        Character::new(sprites.hero()),
         Hero,
         pos,
         Direction::Down,
     ));
 }
-pub fn spawn_player2 (ecs : &mut World, id: u32, pos : Position) {
+pub fn spawn_player2 (ecs : &mut World, sprites: &Sprites, id: u32, pos : Position) {
     ecs.push((
         Id(id),
-        Character(&PLAYER_MARKS[1]),
+        Character::new(sprites.villain()),
         Hero,
         pos,
         Direction::Down,
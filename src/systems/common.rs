@@ -6,10 +6,9 @@
 //! Date:    March 2023
 //! Licence: MIT 
 
-use bracket_lib::prelude::*;
 use legion::system;
 
-use crate::GameStatus;
+use crate::{keys::VirtualKeyCode, GameStatus};
 
 #[system]
 pub fn proceed_to_restart(#[resource] key: &Option<VirtualKeyCode>, #[resource] status: &mut GameStatus) {
@@ -10,7 +10,10 @@ pub mod common;
 pub mod running;
 /// The logic for when the game is over
 pub mod over;
+/// The logic for when the game is played offline
+pub mod offline;
 
 pub use common::*;
 pub use running::*;
-pub use over::*;
\ No newline at end of file
+pub use over::*;
+pub use offline::*;
\ No newline at end of file
@@ -0,0 +1,239 @@
+//! The systems are the bits of code providing the game logic.
+//! This module provides the systems which are used when the game is played
+//! offline (without any server). In that mode, the hero is driven by the arrow
+//! keys and the second player is turned into a villain hunting the hero.
+//!
+//! The rules are those of the `rules` module (played as a hunt): they are
+//! applied locally and the messages they produce are sent to the interface
+//! just as if they came from a server. The systems below only decide what the
+//! players do and how long the villain remains frightened or dying.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::sync::mpsc::Sender;
+
+use crate::{
+    config::{Colors, KeyBindings},
+    frame::{Frame, MESSAGE_LAYER},
+    pascman_protocol::{Item, Message},
+    rules::{GameRules, Mode},
+    *,
+};
+
+/// The number of milliseconds it takes for a villain to take one step
+const VILLAIN_PACE: f32 = 250.0;
+/// The number of milliseconds the villains remain edible after the hero ate some superfood
+const FRIGHT_DURATION: f32 = 7_000.0;
+/// The number of milliseconds an eaten villain takes to die before it hunts again
+const DEATH_DURATION: f32 = 1_000.0;
+
+/// The game which is played offline: its rules, and the channel on which the
+/// messages they produce are sent to the interface
+pub struct OfflineGame {
+    pub rules: GameRules,
+    outbox: Sender<Message>,
+    /// How many milliseconds the villain remains frightened
+    fright: f32,
+}
+
+impl OfflineGame {
+    /// Creates the offline game with the given rules (which are played as a
+    /// hunt). Its messages are sent on the given channel.
+    pub fn new(rules: GameRules, outbox: Sender<Message>) -> Self {
+        Self { rules: rules.with_mode(Mode::Hunt), outbox, fright: 0.0 }
+    }
+
+    /// Applies the direction chosen by the given player and sends the
+    /// resulting messages to the interface. Returns true iff the villain has
+    /// been eaten.
+    pub fn play(&mut self, player: Item, dir: pascman_protocol::Direction) -> bool {
+        let target = self.rules.position(player).map(|pos| self.rules.step(pos, dir));
+        let superfood = target.is_some_and(|target| self.rules.item_at(target) == Item::SUPERFOOD);
+        let frightened = self.rules.is_frightened();
+
+        for message in self.rules.play(player, dir) {
+            // the interface might have been closed in the meantime
+            let _ = self.outbox.send(message);
+        }
+
+        if superfood && target.is_some_and(|target| self.rules.item_at(target) == Item::FLOOR) {
+            self.fright = FRIGHT_DURATION;
+        }
+        frightened && !self.rules.is_frightened()
+    }
+
+    /// Lets `dt` milliseconds elapse: the villain calms down once the fright wears off
+    pub fn wear_off(&mut self, dt: f32) {
+        if self.fright > 0.0 {
+            self.fright -= dt;
+            if self.fright <= 0.0 {
+                self.rules.calm();
+            }
+        }
+    }
+}
+
+/// This function creates the ECS schedule which decides when a given system
+/// should be run while the game is played offline
+pub fn offline_game_schedule() -> Schedule {
+    Schedule::builder()
+        .add_system(recruit_villains_system())
+        .flush()
+        .add_system(offline_input_system())
+        .add_system(move_villains_system())
+        .add_system(wear_off_fright_system())
+        .add_system(recover_system())
+        .add_system(render_map_system())
+        .flush()
+        .add_system(move_to_next_place_system())
+        .flush()
+        .add_system(glide_system())
+        .add_system(animate_system())
+        .add_system(render_food_system())
+        .add_system(render_characters_system())
+        .add_system(render_score_system())
+        .flush()
+        .add_system(remove_gone_system())
+        .build()
+}
+
+/// This system turns the second player into a villain which is controlled
+/// by the computer. The villain is given the personality which has been
+/// selected for the game.
+#[system]
+#[read_component(Id)]
+#[read_component(Hero)]
+pub fn recruit_villains(
+    ecs: &SubWorld,
+    cmd: &mut CommandBuffer,
+    #[resource] personality: &Personality,
+    #[resource] map: &Map,
+) {
+    let [_, player2] = player_ids(map.width, map.height);
+    <(Entity, &Id)>::query()
+        .filter(component::<Hero>())
+        .iter(ecs)
+        .filter(|(_entity, id)| id.0 == player2)
+        .for_each(|(entity, _id)| {
+            cmd.remove_component::<Hero>(*entity);
+            cmd.add_component(*entity, Villain);
+            cmd.add_component(*entity, Pace::new(VILLAIN_PACE));
+            cmd.add_component(*entity, *personality);
+        });
+}
+
+/// This system plays the direction chosen by the user on behalf of the hero
+#[system]
+#[read_component(Villain)]
+pub fn offline_input(
+    ecs: &SubWorld,
+    cmd: &mut CommandBuffer,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] keys: &KeyBindings,
+    #[resource] game: &mut OfflineGame,
+) {
+    let Some(direction) = key.and_then(|key| keys.direction(key)) else {
+        return;
+    };
+
+    if game.play(Item::PLAYER1, direction.into()) {
+        <Entity>::query()
+            .filter(component::<Villain>())
+            .iter(ecs)
+            .for_each(|entity| cmd.add_component(*entity, Dying(DEATH_DURATION)));
+    }
+}
+
+/// This system lets each villain take one step according to its personality
+/// (see the `ai` module for the details).
+#[system]
+#[read_component(Hero)]
+#[read_component(Villain)]
+#[read_component(Frightened)]
+#[read_component(Dying)]
+#[read_component(Direction)]
+#[write_component(Pace)]
+#[write_component(Personality)]
+pub fn move_villains(
+    ecs: &mut SubWorld,
+    cmd: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] frame_time: &FrameTime,
+    #[resource] game: &mut OfflineGame,
+) {
+    let heading = <&Direction>::query()
+        .filter(component::<Hero>())
+        .iter(ecs)
+        .next()
+        .copied();
+    let position = |player: Item| game.rules.position(player).map(|pos| Position { x: pos.x as usize, y: pos.y as usize });
+    let (Some(heading), Some(hero), Some(me)) = (heading, position(Item::PLAYER1), position(Item::PLAYER2)) else {
+        return;
+    };
+
+    <(Entity, &mut Pace, &mut Personality, Option<&Frightened>)>::query()
+        .filter(component::<Villain>() & !component::<Dying>())
+        .iter_mut(ecs)
+        .for_each(|(entity, pace, personality, frightened)| {
+            if !pace.tick(frame_time.0) {
+                return;
+            }
+
+            let next = ai::next_step(map, personality, me, hero, heading, frightened.is_some());
+            if let Some(direction) = next.and_then(|next| me.direction_to(next)) {
+                if game.play(Item::PLAYER2, direction.into()) {
+                    cmd.add_component(*entity, Dying(DEATH_DURATION));
+                }
+            }
+        });
+}
+
+/// This system makes the villains edible for a limited time only
+#[system]
+#[read_component(Villain)]
+pub fn wear_off_fright(
+    ecs: &SubWorld,
+    cmd: &mut CommandBuffer,
+    #[resource] frame_time: &FrameTime,
+    #[resource] game: &mut OfflineGame,
+) {
+    game.wear_off(frame_time.0);
+    <Entity>::query()
+        .filter(component::<Villain>())
+        .iter(ecs)
+        .for_each(|entity| {
+            if game.rules.is_frightened() {
+                cmd.add_component(*entity, Frightened(game.fright));
+            } else {
+                cmd.remove_component::<Frightened>(*entity);
+            }
+        });
+}
+
+/// This system lets the villains which have been eaten (and sent back home)
+/// hunt again once they are done dying
+#[system]
+#[write_component(Dying)]
+pub fn recover(
+    ecs: &mut SubWorld,
+    cmd: &mut CommandBuffer,
+    #[resource] frame_time: &FrameTime,
+) {
+    <(Entity, &mut Dying)>::query()
+        .iter_mut(ecs)
+        .for_each(|(entity, dying)| {
+            dying.0 -= frame_time.0;
+            if dying.0 <= 0.0 {
+                cmd.remove_component::<Dying>(*entity);
+            }
+        });
+}
+
+/// This system shows the score of the hero in the top left corner
+#[system]
+pub fn render_score(#[resource] game: &OfflineGame, #[resource] colors: &Colors, #[resource] frame: &mut Frame) {
+    let score = game.rules.score(Item::PLAYER1).unwrap_or_default();
+    frame.layers[MESSAGE_LAYER].print(Point::new(1, 0), &format!("Score: {score}"), colors.messages);
+}
@@ -8,9 +8,10 @@
 
 use std::process::exit;
 
-use bracket_lib::prelude::*;
+use bracket_color::prelude::{ColorPair, RED, TAN, YELLOW};
+use bracket_geometry::prelude::Rect;
 use legion::{Schedule, system};
-use crate::{proceed_to_restart_system, GameStatus, Map, Player};
+use crate::{config::{Colors, HotSeat}, frame::{Frame, MESSAGE_LAYER}, keys::VirtualKeyCode, proceed_to_restart_system, GameStatus, Map, Player};
 
 pub fn game_over_schedule() -> Schedule {
     Schedule::builder()
@@ -19,6 +20,14 @@ pub fn game_over_schedule() -> Schedule {
         .build()
 }
 
+/// This function creates the ECS schedule for a game which is over but was
+/// merely watched (e.g. when replaying a recorded session): the keys are not
+/// used to restart the game.
+pub fn watch_over_schedule() -> Schedule {
+    Schedule::builder()
+        .add_system(render_gameover_screen_system())
+        .build()
+}
 
 #[system]
 pub fn render_gameover_screen(
@@ -26,6 +35,9 @@ pub fn render_gameover_screen(
     #[resource] player: &Player,
     #[resource] status: &GameStatus,
     #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] colors: &Colors,
+    #[resource] hot_seat: &Option<HotSeat>,
+    #[resource] frame: &mut Frame,
 ) {
     if let &GameStatus::Over { winner } = status {
         if let Some(VirtualKeyCode::Return) = key {
@@ -34,23 +46,24 @@ pub fn render_gameover_screen(
 
         let me = player.0;
 
-        let mut batch = DrawBatch::new();
-        batch.target(3);
-        batch.set_all_alpha(1.0, 1.0);
+        let layer = &mut frame.layers[MESSAGE_LAYER];
+        layer.opaque = true;
 
         let w = map.width * 2;
         let h = map.height* 2;
         
-        batch.draw_box(Rect::with_size(w/4, h/4, w/2, h/2), ColorPair::new(WHITE, BLACK));
+        let bg = colors.messages.bg;
+        layer.draw_box(Rect::with_size(w/4, h/4, w/2, h/2), colors.messages);
 
-        if me == winner {
-            batch.print_color_centered(h/2-2, "Congratulations, you won !", ColorPair::new(YELLOW, BLACK));
+        if hot_seat.is_some() {
+            // both players are in front of the screen
+            layer.print_centered(h/2-2, &format!("Player {winner} won !"), ColorPair::new(YELLOW, bg));
+        } else if me == winner {
+            layer.print_centered(h/2-2, "Congratulations, you won !", ColorPair::new(YELLOW, bg));
         } else {
-            batch.print_color_centered(h/2-2, "Too bad, you lost :( ",      ColorPair::new(RED, BLACK));
+            layer.print_centered(h/2-2, "Too bad, you lost :( ",      ColorPair::new(RED, bg));
         }
 
-        batch.print_color_centered(h/2 + 2, "Press ENTER to end", ColorPair::new(TAN, BLACK));
-
-        batch.submit(5_000).expect("error submitting draw batch");
+        layer.print_centered(h/2 + 2, "Press ENTER to end", ColorPair::new(TAN, bg));
     }
 }
\ No newline at end of file
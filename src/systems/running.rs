@@ -7,17 +7,33 @@
 
 use std::io::{self, Write};
 
-use bracket_lib::{color::{ColorPair, BLACK, WHITE}, terminal::{to_cp437, DrawBatch, Point}};
-use crate::*;
+use bracket_color::prelude::{ColorPair, ORANGE, RGBA};
+use bracket_geometry::prelude::Point;
+use crate::{
+    config::{Colors, HotSeat, KeyBindings},
+    frame::{Figure, Frame, CHARACTER_LAYER, FOOD_LAYER, MAP_LAYER},
+    input::{HeldKeys, InputQueue, InputSettings},
+    motion::{Glide, MotionSettings},
+    pascman_protocol::{DirectionLine, ProtocolMode},
+    prediction::{Prediction, CORRECTION_DURATION},
+    record::Recorder,
+    walls::{WallStyle, Walls},
+    *,
+};
 
 /// This function creates the ECS schedule which decides when a given system should be run
 pub fn run_game_schedule() -> Schedule {
     Schedule::builder()
+        .add_system(expire_predictions_system())
+        .add_system(wear_off_correction_system())
+        .flush()
         .add_system(user_input_system())
         .add_system(render_map_system())
         .flush()
         .add_system(move_to_next_place_system())
         .flush()
+        .add_system(glide_system())
+        .add_system(animate_system())
         .add_system(render_food_system())
         .add_system(render_characters_system())
         .flush()
@@ -25,103 +41,300 @@ pub fn run_game_schedule() -> Schedule {
         .build()
 }
 
-fn _direction_to_stdout(direction: Direction) {
+/// This function creates the ECS schedule for a game which is merely watched
+/// (e.g. when replaying a recorded session): it is the same as the one of a
+/// running game, except that it does not deal with the user input.
+pub fn watch_game_schedule() -> Schedule {
+    Schedule::builder()
+        .add_system(render_map_system())
+        .flush()
+        .add_system(move_to_next_place_system())
+        .flush()
+        .add_system(glide_system())
+        .add_system(animate_system())
+        .add_system(render_food_system())
+        .add_system(render_characters_system())
+        .flush()
+        .add_system(remove_gone_system())
+        .build()
+}
+
+/// Writes the direction on the standard output. In hot-seat mode, the
+/// direction is preceded by the player who chose it (as an u32 in binary mode,
+/// e.g. `DIR 2 LEFT` in text mode).
+fn _direction_to_stdout(player: Option<u32>, direction: Direction, mode: ProtocolMode) {
     let mut stdout = io::stdout();
 
-    let dir: [u8; 4] = direction.into();
-    stdout.write_all(&dir).expect("could not write to stdout");
+    match mode {
+        ProtocolMode::Binary => {
+            if let Some(player) = player {
+                stdout.write_all(&player.to_ne_bytes()).expect("could not write to stdout");
+            }
+            let dir: [u8; 4] = direction.into();
+            stdout.write_all(&dir).expect("could not write to stdout");
+        },
+        ProtocolMode::Text => {
+            let line = DirectionLine { player, direction: direction.into() };
+            writeln!(stdout, "{line}").expect("could not write to stdout");
+        },
+    }
     stdout.flush().expect("could not flush stdout");
 }
 
-/// This system deals with the user input
+/// Sends the direction chosen by the given local player (which is only
+/// known in hot-seat mode) to the sink
+fn send_direction(sink: &DirectionSink, player: Option<u32>, direction: Direction, mode: ProtocolMode) {
+    // the other end might have been closed in the meantime
+    match sink {
+        DirectionSink::Stdout => _direction_to_stdout(player, direction, mode),
+        DirectionSink::Channel(sx) => { let _ = sx.send(direction.into()); },
+        DirectionSink::PerPlayer(sxs) => {
+            let index = player.unwrap_or(1) as usize - 1;
+            let _ = sxs[index].send(direction.into());
+        },
+    }
+}
+
+/// Returns the local player (1 or 2) who is represented by the entity having
+/// the given id (the ids of the players depend on the size of the map)
+pub(crate) fn player_number(id: &Id, map: &Map) -> Option<u32> {
+    let [player1, player2] = player_ids(map.width, map.height);
+    match id.0 {
+        id if id == player1 => Some(1),
+        id if id == player2 => Some(2),
+        _ => None,
+    }
+}
+
+/// This system gives up on the moves which have been predicted for the local
+/// players when the server does not confirm them in time (the moves decided
+/// by the server are reconciled with the predicted ones as soon as they are
+/// received, see the `prediction` module)
+#[system]
+#[read_component(Id)]
+pub fn expire_predictions(
+    ecs: &SubWorld,
+    cmd: &mut CommandBuffer,
+    #[resource] prediction: &mut Prediction,
+    #[resource] frame_time: &FrameTime,
+    #[resource] map: &Map,
+) {
+    if !prediction.enabled {
+        return;
+    }
+    <(Entity, &Id)>::query()
+        .iter(ecs)
+        .filter_map(|(entity, id)| player_number(id, map).map(|player| (entity, player)))
+        .for_each(|(entity, player)| {
+            if let Some(confirmed) = prediction.expire(player, frame_time.0) {
+                cmd.add_component(*entity, IntendsToMove(confirmed));
+                cmd.add_component(*entity, Corrected(CORRECTION_DURATION));
+            }
+        });
+}
+
+/// This system lets the corrections of the predicted moves fade away
+#[system]
+#[write_component(Corrected)]
+pub fn wear_off_correction(ecs: &mut SubWorld, cmd: &mut CommandBuffer, #[resource] frame_time: &FrameTime) {
+    <(Entity, &mut Corrected)>::query()
+        .iter_mut(ecs)
+        .for_each(|(entity, corrected)| {
+            corrected.0 -= frame_time.0;
+            if corrected.0 <= 0.0 {
+                cmd.remove_component::<Corrected>(*entity);
+            }
+        });
+}
+
+/// This system deals with the user input: the keys pressed and held by the
+/// local player(s) are turned into directions (see the `input` module). In
+/// hot-seat mode, the key tells which of the two local players chose the
+/// direction (both of them may press a key on the same tick). When the
+/// prediction is enabled, the player is moved right away.
 #[system]
+#[read_component(Id)]
+#[read_component(Position)]
+#[read_component(IntendsToMove)]
 pub fn user_input(
+    ecs: &SubWorld,
+    cmd: &mut CommandBuffer,
     #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] held: &HeldKeys,
+    #[resource] keys: &KeyBindings,
+    #[resource] hot_seat: &Option<HotSeat>,
+    #[resource] player: &Player,
+    #[resource] map: &Map,
+    #[resource] frame_time: &FrameTime,
+    #[resource] settings: &InputSettings,
+    #[resource] queue: &mut InputQueue,
+    #[resource] prediction: &mut Prediction,
+    #[resource] recorder: &Option<Recorder>,
+    #[resource] mode: &ProtocolMode,
+    #[resource] sink: &DirectionSink,
 ) {
-    match key {
-        Some(VirtualKeyCode::Left)  => { _direction_to_stdout(Direction::Left);  },
-        Some(VirtualKeyCode::Right) => { _direction_to_stdout(Direction::Right); },
-        Some(VirtualKeyCode::Up)    => { _direction_to_stdout(Direction::Up);    },
-        Some(VirtualKeyCode::Down)  => { _direction_to_stdout(Direction::Down);  },
-        _                           => {/* do nothing */ }
+    // the window only reports one key per tick: the keys held down tell
+    // those which have been pressed at once (e.g. by both hot-seat players)
+    let newly_pressed = queue.newly_pressed(&held.0);
+    // the local players, the key each of them just pressed (if any) and its direction
+    let players = match hot_seat {
+        None => {
+            let pressed = key.and_then(|key| keys.direction(key).map(|direction| (key, direction)));
+            vec![(None, player.0, pressed)]
+        },
+        Some(hot_seat) => (1..=2).map(|local| {
+            let pressed = key.iter().chain(&newly_pressed).find_map(|&key| hot_seat.direction(key)
+                .filter(|(player, _)| *player == local)
+                .map(|(_, direction)| (key, direction)));
+            (Some(local), local, pressed)
+        }).collect(),
+    };
+
+    for (local, number, pressed) in players {
+        // where the player is (or is about to be)
+        let found = <(Entity, &Id, &Position, Option<&IntendsToMove>)>::query()
+            .iter(ecs)
+            .find(|(_, id, _, _)| player_number(id, map) == Some(number))
+            .map(|(entity, _, position, intention)| (*entity, intention.map_or(*position, |intention| intention.0)));
+        let position = found.map(|(_, position)| position);
+        // as long as the player is nowhere to be seen, the server decides
+        let can_go = |direction: Direction| position.is_none_or(|position| {
+            direction.next(position).is_some_and(|next| map.can_enter(next.into_point()))
+        });
+
+        let input = &mut queue.players[local.unwrap_or(1) as usize - 1];
+        if let Some(direction) = input.update(settings, pressed, &held.0, frame_time.0, can_go) {
+            send_direction(sink, local, direction, *mode);
+            if let Some(recorder) = recorder {
+                recorder.direction(local, direction.into());
+            }
+            // a direction leading into a wall may be sent, but it is not a move
+            let predicted = found.and_then(|(entity, from)| direction.next(from).map(|to| (entity, from, to)));
+            if let Some((entity, from, to)) = predicted.filter(|_| prediction.enabled && can_go(direction)) {
+                cmd.add_component(entity, IntendsToMove(to));
+                prediction.predict(number, from, to);
+            }
+        }
     }
 }
 
-/// This system renders the world map
+/// This system renders the world map (the glyphs of the walls depend on
+/// their neighbours unless they are drawn as blocks, see the `walls` module).
+/// Since the map hardly ever changes, it is only drawn again when its
+/// generation has changed.
 #[system]
-pub fn render_map(#[resource] map: &Map) {
-    let mut drawbatch = DrawBatch::new();
-    drawbatch.target(0);
+pub fn render_map(
+    #[resource] map: &Map,
+    #[resource] walls: &Walls,
+    #[resource] style: &WallStyle,
+    #[resource] colors: &Colors,
+    #[resource] frame: &mut Frame,
+) {
+    if frame.map_generation() == Some(map.generation) {
+        return;
+    }
+    frame.set_map_generation(map.generation);
+    let layer = &mut frame.layers[MAP_LAYER];
+    layer.clear();
 
     for y in 0..map.height {
         for x in 0..map.width {
             let pos   = Position{x, y};
-            let glyph = match map[pos] {
-                TileType::Wall  => to_cp437('0'),
-                TileType::Floor => to_cp437(' '),
-            };
-            drawbatch.set(
+            let glyph = walls.glyph(pos, *style).unwrap_or(' ');
+            layer.set(
                 Point::new(x,y), 
-                ColorPair::new(WHITE, BLACK), 
+                colors.map, 
                 glyph);
         }
     }
-
-    drawbatch.submit(0).expect("draw error");
 }
 
 /// This system renders all entities in the world
 #[system]
 #[read_component(Food)]
 #[read_component(Position)]
-pub fn render_food(ecs: &SubWorld) {
-    let mut batch = DrawBatch::new();
-    batch.target(1);
+pub fn render_food(ecs: &SubWorld, #[resource] map: &Map, #[resource] colors: &Colors, #[resource] frame: &mut Frame) {
+    let layer = &mut frame.layers[FOOD_LAYER];
 
     <(&Position, &Food)>::query()
         .iter(ecs)
-        .filter(|(pos, _food)| pos.is_valid())
+        .filter(|(pos, _food)| pos.is_valid(map))
         .for_each(|(pos, food)| {
-            batch.set(
+            layer.set(
                 pos.into_point(),
-                ColorPair::new(WHITE, BLACK),
-                to_cp437(food.0),
+                colors.food,
+                food.0,
             );
         });
+}
 
-    batch.submit(5_000).expect("draw entity error");
+/// This system lets the characters play the animation matching what they
+/// are doing (see the `animation` module)
+#[system]
+#[write_component(Character)]
+#[read_component(Position)]
+#[read_component(Direction)]
+#[read_component(Frightened)]
+#[read_component(Dying)]
+pub fn animate(ecs: &mut SubWorld, #[resource] frame_time: &FrameTime) {
+    <(&mut Character, &Position, &Direction, Option<&Frightened>, Option<&Dying>)>::query()
+        .iter_mut(ecs)
+        .for_each(|(character, pos, direction, frightened, dying)| {
+            character.animate(*pos, *direction, frightened.is_some(), dying.is_some(), frame_time.0);
+        });
 }
 
+/// This system renders the characters, between two tiles when they are
+/// gliding. A local player whose predicted move has just been corrected is
+/// briefly tinted. Each sprite tells whether it represents the hero (first
+/// player) or the villain (second player).
 #[system]
+#[read_component(Id)]
 #[read_component(Character)]
 #[read_component(Position)]
-#[read_component(Direction)]
-pub fn render_characters(ecs: &SubWorld) {
-    let mut batch = DrawBatch::new();
-    batch.target(2);
+#[read_component(Corrected)]
+#[read_component(Glide)]
+pub fn render_characters(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] colors: &Colors,
+    #[resource] motion: &MotionSettings,
+    #[resource] frame: &mut Frame,
+) {
+    let layer = &mut frame.layers[CHARACTER_LAYER];
 
-    <(&Position, &Character, &Direction)>::query()
+    <(&Position, &Character, Option<&Id>, Option<&Corrected>, Option<&Glide>)>::query()
         .iter(ecs)
-        .filter(|(pos, _character, _direction)| pos.is_valid())
-        .for_each(|(pos, character, direction)| {
-            batch.set(
-                pos.into_point(),
-                ColorPair::new(WHITE, BLACK),
-                to_cp437(character.0[*direction as usize]),
-            );
+        .filter(|(pos, _character, _id, _corrected, _glide)| pos.is_valid(map))
+        .for_each(|(pos, character, id, corrected, glide)| {
+            let color = match corrected {
+                None => colors.characters,
+                Some(Corrected(left)) => {
+                    let tint = (left / CORRECTION_DURATION).clamp(0.0, 1.0);
+                    ColorPair::new(colors.characters.fg.lerp(RGBA::from(ORANGE), tint), colors.characters.bg)
+                },
+            };
+            let (x, y) = glide.map_or((pos.x as f32, pos.y as f32), |glide| glide.at(motion, *pos));
+            match id.and_then(|id| player_number(id, map)) {
+                Some(1) => layer.set_figure(x, y, color, character.glyph(), Figure::Hero),
+                Some(_) => layer.set_figure(x, y, color, character.glyph(), Figure::Villain),
+                None    => layer.set_fancy(x, y, color, character.glyph()),
+            }
         });
-
-    batch.submit(10_000).expect("draw entity error");
 }
 
+/// This system moves the entities to the place they intend to go to. The
+/// characters stepping onto a neighbouring tile glide there (see the
+/// `motion` module), the others are simply teleported.
 #[system]
 #[write_component(Position)]
 #[write_component(Direction)]
 #[write_component(IntendsToMove)]
-pub fn move_to_next_place(ecs: &mut SubWorld, cmd: &mut CommandBuffer) {
-    <(Entity, &mut Position, &mut Direction, &IntendsToMove)>::query()
+#[read_component(Glide)]
+pub fn move_to_next_place(ecs: &mut SubWorld, cmd: &mut CommandBuffer, #[resource] motion: &MotionSettings) {
+    <(Entity, &mut Position, &mut Direction, &IntendsToMove, Option<&Glide>)>::query()
         .iter_mut(ecs)
-        .for_each(|(entity, position, direction, intention)| {
+        .for_each(|(entity, position, direction, intention, glide)| {
             cmd.remove_component::<IntendsToMove>(*entity);
             let Position { x, y } = intention.0;
 
@@ -134,11 +347,34 @@ pub fn move_to_next_place(ecs: &mut SubWorld, cmd: &mut CommandBuffer) {
             } else if y < position.y {
                 *direction = Direction::Up;
             }
-            
+
+            match Glide::start(motion, glide, *position, intention.0) {
+                Some(glide) => cmd.add_component(*entity, glide),
+                None        => cmd.remove_component::<Glide>(*entity),
+            }
             *position  = intention.0;
         });
 }
 
+/// This system lets the characters glide towards their position
+#[system]
+#[write_component(Glide)]
+pub fn glide(
+    ecs: &mut SubWorld,
+    cmd: &mut CommandBuffer,
+    #[resource] motion: &MotionSettings,
+    #[resource] frame_time: &FrameTime,
+) {
+    <(Entity, &mut Glide)>::query()
+        .iter_mut(ecs)
+        .for_each(|(entity, glide)| {
+            glide.elapsed += frame_time.0;
+            if glide.is_over(motion) {
+                cmd.remove_component::<Glide>(*entity);
+            }
+        });
+}
+
 #[system]
 #[read_component(LeftGame)]
 pub fn remove_gone(ecs: &mut SubWorld, cmd: &mut CommandBuffer) {
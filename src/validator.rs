@@ -0,0 +1,309 @@
+//! A conformance validator for the messages received by the interface. It
+//! keeps track of what the server has told so far and checks that every new
+//! message makes sense in that context:
+//!
+//! * the map is sent before the registration (and the registration only once),
+//! * all positions lie on the map,
+//! * the ids are unique (a tile may only be spawned again at its own position),
+//! * only the players move, only onto the floor and one tile at a time,
+//! * only a player eats, only food, and only where the player stands,
+//! * nothing but GAME_OVER is sent once the game is over.
+//!
+//! In lenient mode, the violations are reported but the messages are processed
+//! anyway (unless they would crash the interface or remove something which is
+//! not food). In strict mode, the messages which violate the protocol are
+//! rejected. In both cases, the violations are written to the standard error
+//! and shown on screen, so that one can see exactly what the server did wrong.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::collections::HashMap;
+
+use bracket_color::prelude::{ColorPair, BLACK, ORANGE, RED};
+use bracket_geometry::prelude::Point;
+
+use crate::{frame::{Frame, MESSAGE_LAYER}, logging::{self, LogLevel}, pascman_protocol::{EatFood, Item, Message, MessageType, Position, HEIGHT, WIDTH}};
+
+/// The number of violations which are shown on screen
+const SHOWN_VIOLATIONS: usize = 4;
+
+/// What the validator does with the messages which violate the protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// The violations are reported and the messages are processed anyway
+    Lenient,
+    /// The violations are reported and the messages are rejected
+    Strict,
+}
+
+/// A message which did not conform to the protocol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The index (starting at 0) of the message in the stream
+    pub index: usize,
+    pub message: Message,
+    pub reason: String,
+}
+
+/// The validator tracks the state of the game as described by the server
+#[derive(Debug, Clone)]
+pub struct Validator {
+    mode: ValidationMode,
+    /// The index of the next message
+    index: usize,
+    /// The tiles that have been spawned so far
+    tiles: Vec<Option<Item>>,
+    /// The ids of the tiles (along with their offset on the map)
+    tile_ids: HashMap<u32, usize>,
+    /// The food, superfood and players (along with their position)
+    entities: HashMap<u32, (Item, Position)>,
+    registered: bool,
+    over: bool,
+    violations: Vec<Violation>,
+    /// Whether the violations are written to the standard error
+    log: bool,
+    /// The size of the map (in tiles)
+    width: usize,
+    height: usize,
+}
+
+impl Validator {
+    pub fn new(mode: ValidationMode) -> Self {
+        Self::with_size(mode, WIDTH, HEIGHT)
+    }
+
+    /// Creates a validator for a map of the given size (in tiles)
+    pub fn with_size(mode: ValidationMode, width: usize, height: usize) -> Self {
+        Self {
+            mode,
+            index: 0,
+            tiles: vec![None; width * height],
+            tile_ids: HashMap::default(),
+            entities: HashMap::default(),
+            registered: false,
+            over: false,
+            violations: vec![],
+            log: true,
+            width,
+            height,
+        }
+    }
+
+    /// Creates a validator which does not write the violations to the standard error
+    pub fn silent(mode: ValidationMode) -> Self {
+        Self { log: false, ..Self::new(mode) }
+    }
+
+    /// Forgets everything about the messages that have been checked so far
+    pub fn reset(&mut self) {
+        *self = Self { log: self.log, ..Self::with_size(self.mode, self.width, self.height) };
+    }
+
+    pub fn mode(&self) -> ValidationMode {
+        self.mode
+    }
+
+    /// All the violations that have been found so far
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Returns true iff the position lies on the map
+    fn on_map(&self, pos: Position) -> bool {
+        (pos.x as usize) < self.width && (pos.y as usize) < self.height
+    }
+
+    /// Returns the offset of the given position on the map (if it lies on the map)
+    fn offset_of(&self, pos: Position) -> Option<usize> {
+        self.on_map(pos).then(|| pos.y as usize * self.width + pos.x as usize)
+    }
+
+    /// Checks the given message and reports its violations (if any). This
+    /// function returns true iff the interface should process the message.
+    pub fn check(&mut self, message: &Message) -> bool {
+        let index    = self.index;
+        let problems = self.problems(message);
+        self.index  += 1;
+
+        for reason in problems.iter() {
+            if self.log {
+                logging::log(LogLevel::Warn, format_args!("protocol violation: #{index} {message}: {reason}"));
+            }
+            self.violations.push(Violation { index, message: *message, reason: reason.clone() });
+        }
+
+        let accepted = problems.is_empty()
+            || (self.mode == ValidationMode::Lenient && self.is_safe(message));
+        if accepted {
+            self.apply(message);
+        }
+        accepted
+    }
+
+    /// Draws the latest violations at the top of the message layer (every
+    /// other line, to keep them readable)
+    pub fn render(&self, frame: &mut Frame) {
+        if self.violations.is_empty() {
+            return;
+        }
+
+        let layer = &mut frame.layers[MESSAGE_LAYER];
+        let mode  = if self.mode == ValidationMode::Strict { "rejected" } else { "reported" };
+        let title = format!("{} PROTOCOL VIOLATION(S) ({mode})", self.violations.len());
+        layer.print(Point::new(0, 0), &title, ColorPair::new(RED, BLACK));
+
+        let first = self.violations.len().saturating_sub(SHOWN_VIOLATIONS);
+        for (line, violation) in self.violations[first..].iter().enumerate() {
+            let text = format!("#{} {}: {}", violation.index, violation.message, violation.reason);
+            layer.print(Point::new(0, 2 * (line + 1)), &text, ColorPair::new(ORANGE, BLACK));
+        }
+    }
+
+    /// Returns true iff the message can be processed without crashing the
+    /// interface (or deleting an entity which is not food)
+    fn is_safe(&self, message: &Message) -> bool {
+        unsafe {
+            match message.msgt {
+                MessageType::SPAWN    => self.on_map(message.spawn.pos),
+                MessageType::MOVEMENT => self.on_map(message.movement.pos),
+                MessageType::EAT_FOOD => self.entities.get(&message.eat_food.food).is_some_and(|(item, _)| is_food(*item)),
+                _ => true,
+            }
+        }
+    }
+
+    /// Lists all the reasons why the message does not conform to the protocol
+    fn problems(&self, message: &Message) -> Vec<String> {
+        let mut problems = vec![];
+        unsafe {
+            if self.over && message.msgt != MessageType::GAME_OVER {
+                problems.push("message received after the game is over".to_string());
+            }
+
+            match message.msgt {
+                MessageType::REGISTRATION => {
+                    if self.tile_ids.is_empty() {
+                        problems.push("registration received before the map".to_string());
+                    }
+                    if self.registered {
+                        problems.push("registration received twice".to_string());
+                    }
+                },
+                MessageType::SPAWN => {
+                    let spawn = message.spawn;
+                    if !self.on_map(spawn.pos) {
+                        problems.push(format!("position ({},{}) lies outside of the map", spawn.pos.x, spawn.pos.y));
+                    }
+                    let tile = matches!(spawn.item, Item::WALL | Item::FLOOR);
+                    if let Some((item, pos)) = self.entities.get(&spawn.id) {
+                        problems.push(format!("id {} is already used by the {item} at ({},{})", spawn.id, pos.x, pos.y));
+                    } else if let Some(offset) = self.tile_ids.get(&spawn.id) {
+                        if !tile || Some(*offset) != self.offset_of(spawn.pos) {
+                            let (x, y) = (offset % self.width, offset / self.width);
+                            problems.push(format!("id {} is already used by the tile at ({x},{y})", spawn.id));
+                        }
+                    }
+                },
+                MessageType::MOVEMENT => {
+                    let movement = message.movement;
+                    let to = movement.pos;
+                    if self.tile_ids.is_empty() {
+                        problems.push("movement received before the map".to_string());
+                    }
+                    if !self.registered {
+                        problems.push("movement received before the registration".to_string());
+                    }
+                    if !self.on_map(to) {
+                        problems.push(format!("position ({},{}) lies outside of the map", to.x, to.y));
+                    }
+                    match self.entities.get(&movement.id) {
+                        None => problems.push(format!("unknown id {}", movement.id)),
+                        Some((item, _)) if !is_player(*item) => problems.push(format!("a {item} cannot move")),
+                        Some((_, from)) => {
+                            if from.x.abs_diff(to.x) + from.y.abs_diff(to.y) > 1 {
+                                problems.push(format!("moves from ({},{}) to ({},{}): more than one tile at a time", from.x, from.y, to.x, to.y));
+                            }
+                        },
+                    }
+                    match self.offset_of(to).and_then(|offset| self.tiles[offset]) {
+                        Some(Item::FLOOR) => {},
+                        Some(Item::WALL)  => problems.push(format!("moves into the wall at ({},{})", to.x, to.y)),
+                        _ if self.on_map(to) => problems.push(format!("moves onto ({},{}) which is not known to be floor", to.x, to.y)),
+                        _ => { /* already reported */ },
+                    }
+                },
+                MessageType::EAT_FOOD => {
+                    let EatFood { eater, food, .. } = message.eat_food;
+                    if self.tile_ids.is_empty() {
+                        problems.push("food eaten before the map".to_string());
+                    }
+                    let eater = match self.entities.get(&eater) {
+                        None => { problems.push(format!("unknown eater {eater}")); None },
+                        Some((item, _)) if !is_player(*item) => { problems.push(format!("a {item} cannot eat")); None },
+                        Some((_, pos)) => Some(*pos),
+                    };
+                    let food = match self.entities.get(&food) {
+                        None => { problems.push(format!("unknown food {food}")); None },
+                        Some((item, _)) if !is_food(*item) => { problems.push(format!("a {item} cannot be eaten")); None },
+                        Some((_, pos)) => Some(*pos),
+                    };
+                    if let (Some(eater), Some(food)) = (eater, food) {
+                        if eater != food {
+                            problems.push(format!("the eater at ({},{}) is not on the food at ({},{})", eater.x, eater.y, food.x, food.y));
+                        }
+                    }
+                },
+                MessageType::GAME_OVER => {
+                    let winner = message.game_over.winner;
+                    if !(1..=2).contains(&winner) {
+                        problems.push(format!("invalid winner {winner}"));
+                    }
+                },
+            }
+        }
+        problems
+    }
+
+    /// Updates the state of the game as described by the message
+    fn apply(&mut self, message: &Message) {
+        unsafe {
+            match message.msgt {
+                MessageType::REGISTRATION => self.registered = true,
+                MessageType::SPAWN => {
+                    let spawn = message.spawn;
+                    match spawn.item {
+                        Item::WALL | Item::FLOOR => {
+                            if let Some(offset) = self.offset_of(spawn.pos) {
+                                self.tiles[offset] = Some(spawn.item);
+                                self.tile_ids.insert(spawn.id, offset);
+                            }
+                        },
+                        _ => {
+                            self.entities.insert(spawn.id, (spawn.item, spawn.pos));
+                        },
+                    }
+                },
+                MessageType::MOVEMENT => {
+                    let movement = message.movement;
+                    if let Some((_, pos)) = self.entities.get_mut(&movement.id) {
+                        *pos = movement.pos;
+                    }
+                },
+                MessageType::EAT_FOOD => {
+                    self.entities.remove(&message.eat_food.food);
+                },
+                MessageType::GAME_OVER => self.over = true,
+            }
+        }
+    }
+}
+
+fn is_player(item: Item) -> bool {
+    matches!(item, Item::PLAYER1 | Item::PLAYER2)
+}
+
+fn is_food(item: Item) -> bool {
+    matches!(item, Item::FOOD | Item::SUPERFOOD)
+}
@@ -0,0 +1,125 @@
+//! The walls are drawn as the lines of a maze rather than as blocks. The
+//! glyph of a wall depends on the walls it is connected to among its four
+//! neighbours: it may be a corner, an edge, a T-junction, a crossing or an
+//! end cap (using the double line glyphs of the font).
+//!
+//! A wall is not connected to a neighbouring wall when there are walls on
+//! both sides of that connection (the diagonal neighbours are taken into
+//! account): this way, a thick wall is only drawn as its outline, and the
+//! walls lying deep inside such a thick wall are drawn as a solid block.
+//!
+//! Since the map hardly ever changes, the glyphs are computed once and only
+//! updated around the tiles which are changed by a `SPAWN`.
+//!
+//! The walls can still be drawn as they used to be, every wall being a block
+//! (the `0` glyph of the font): see `WallStyle`.
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use crate::{Map, Position, TileType};
+
+/// The glyph of a wall connected to its neighbours (up = 1, right = 2,
+/// down = 4 and left = 8)
+const GLYPHS: [char; 16] = [
+    '■', '╨', '╞', '╚', '╥', '║', '╔', '╠',
+    '╡', '╝', '═', '╩', '╗', '╣', '╦', '╬',
+];
+/// The glyph of a wall which is surrounded by walls
+const INTERIOR: char = '█';
+/// The glyph of every wall when the walls are drawn as blocks
+pub const BLOCK: char = '0';
+
+/// How the walls are drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WallStyle {
+    /// As the lines of a maze, after their neighbours
+    #[default]
+    Lines,
+    /// As blocks, all of them with the same glyph
+    Blocks,
+}
+
+/// The glyphs of the walls of the map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walls {
+    width: usize,
+    height: usize,
+    glyphs: Vec<Option<char>>,
+}
+
+impl Walls {
+    /// Computes the glyphs of all the walls of the map
+    pub fn new(map: &Map) -> Self {
+        let glyphs = (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .map(|(x, y)| wall_glyph(map, x, y))
+            .collect();
+        Self { width: map.width, height: map.height, glyphs }
+    }
+
+    /// Returns the glyph of the wall at the given position (if there is a wall)
+    pub fn get(&self, Position { x, y }: Position) -> Option<char> {
+        if x < self.width && y < self.height {
+            self.glyphs[y * self.width + x]
+        } else {
+            None
+        }
+    }
+
+    /// Returns the glyph to draw for the wall at the given position (if there
+    /// is a wall) when the walls are drawn in the given style
+    pub fn glyph(&self, position: Position, style: WallStyle) -> Option<char> {
+        match style {
+            WallStyle::Lines  => self.get(position),
+            WallStyle::Blocks => self.get(position).map(|_| BLOCK),
+        }
+    }
+
+    /// Updates the glyphs around the given tile, which has just been changed
+    pub fn update(&mut self, map: &Map, Position { x, y }: Position) {
+        if (self.width, self.height) != (map.width, map.height) {
+            *self = Self::new(map);
+            return;
+        }
+        for ny in y.saturating_sub(1)..=(y + 1).min(map.height.saturating_sub(1)) {
+            for nx in x.saturating_sub(1)..=(x + 1).min(map.width.saturating_sub(1)) {
+                self.glyphs[ny * self.width + nx] = wall_glyph(map, nx, ny);
+            }
+        }
+    }
+}
+
+/// Returns true iff the glyph is one of those used to draw the walls
+pub fn is_wall_glyph(glyph: char) -> bool {
+    glyph == INTERIOR || glyph == BLOCK || GLYPHS.contains(&glyph)
+}
+
+/// Returns the glyph of the wall at the given position (or `None` when there
+/// is no wall at that position)
+pub fn wall_glyph(map: &Map, x: usize, y: usize) -> Option<char> {
+    let wall = |dx: i32, dy: i32| {
+        let (x, y) = (x as i32 + dx, y as i32 + dy);
+        x >= 0 && y >= 0 && (x as usize) < map.width && (y as usize) < map.height
+            && map.tiles[y as usize * map.width + x as usize] == TileType::Wall
+    };
+    if !wall(0, 0) {
+        return None;
+    }
+    if (-1..=1).all(|dy| (-1..=1).all(|dx| wall(dx, dy))) {
+        return Some(INTERIOR);
+    }
+    // a connection between two walls is hidden when it has walls on both sides
+    let connected = |dx: i32, dy: i32| {
+        let (px, py) = (dy, dx);
+        let side = |s: i32| wall(s * px, s * py) && wall(dx + s * px, dy + s * py);
+        wall(dx, dy) && !(side(1) && side(-1))
+    };
+    let mask = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+        .iter()
+        .enumerate()
+        .filter(|(_, (dx, dy))| connected(*dx, *dy))
+        .fold(0, |mask, (bit, _)| mask | 1 << bit);
+    Some(GLYPHS[mask])
+}
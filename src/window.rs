@@ -0,0 +1,83 @@
+//! The window of the game (only available with the `gui` feature).
+//!
+//! Author:  X. Gillard
+//! Date:    March 2023
+//! Licence: MIT
+
+use std::{env, path::Path};
+
+use bracket_lib::prelude::{BResult, BTerm, BTermBuilder};
+
+use crate::pascman_protocol::{HEIGHT, WIDTH};
+
+/// The font used to write the messages on top of the map
+const MESSAGE_FONT: &str = "terminal8x8.png";
+
+/// How the window of the game looks like
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowOptions {
+    /// The title of the window
+    pub title: String,
+    /// The width of the map (in tiles)
+    pub width: usize,
+    /// The height of the map (in tiles)
+    pub height: usize,
+    /// The size of a tile (in pixels), which is also the size of the sprites in the font
+    pub tile_size: u32,
+    /// The font holding the sprites of the game (in the resources directory)
+    pub font: String,
+    /// The directory where the fonts are found
+    pub resources: String,
+    /// The maximum number of frames per second (if any)
+    pub fps_cap: Option<f32>,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self {
+            title: String::from("pas cman"),
+            width: WIDTH,
+            height: HEIGHT,
+            tile_size: 32,
+            font: String::from("pas-cman-font-32.png"),
+            resources: default_resources(),
+            fps_cap: None,
+        }
+    }
+}
+
+/// The directory where the fonts are looked for when none is given: the
+/// `PAS_RESOURCES` environment variable or `resources/` by default
+pub fn default_resources() -> String {
+    env::var("PAS_RESOURCES").unwrap_or_else(|_| String::from("resources/"))
+}
+
+/// Opens the window of the game
+pub fn open_window(options: &WindowOptions) -> BResult<BTerm> {
+    for font in [options.font.as_str(), MESSAGE_FONT] {
+        let path = Path::new(&options.resources).join(font);
+        if !path.is_file() {
+            return Err(format!("font not found: {} (see --resources and --font)", path.display()).into());
+        }
+    }
+
+    let (w, h) = (options.width as u32, options.height as u32);
+    let size   = options.tile_size;
+    let font   = options.font.as_str();
+    let mut builder = BTermBuilder::new()
+        .with_title(&options.title)
+        .with_dimensions(w, h)
+        .with_tile_dimensions(size, size)
+        .with_resource_path(&options.resources)
+        .with_font(font, size, size)
+        .with_font(MESSAGE_FONT, 8, 8)
+        .with_simple_console(w, h, font)
+        .with_simple_console_no_bg(w, h, font)
+        // the characters glide between the tiles
+        .with_fancy_console(w, h, font)
+        .with_simple_console(w*2, h*2, MESSAGE_FONT);
+    if let Some(fps) = options.fps_cap {
+        builder = builder.with_fps_cap(fps);
+    }
+    builder.build()
+}
@@ -0,0 +1,46 @@
+//! The villains driven by the computer.
+
+use pas_cman_ipl::{
+    ai::Bot,
+    pascman_protocol::{Direction, Item},
+    rules::GameRules,
+    Corner, Personality,
+};
+
+#[test]
+fn a_chaser_follows_the_shortest_path_to_the_hero() {
+    let rules = GameRules::from_map("\
+#######
+#@....#
+#.###.#
+#.#..!#
+#######
+").0;
+    let mut bot = Bot::new(Item::PLAYER2, Personality::Chaser, &rules);
+    assert_eq!(bot.play(&rules, Direction::DOWN, false), Some(Direction::UP));
+}
+
+#[test]
+fn a_frightened_villain_runs_away_from_the_hero() {
+    let rules = GameRules::from_map("\
+#######
+#@..!.#
+#######
+").0;
+    let mut bot = Bot::new(Item::PLAYER2, Personality::Chaser, &rules);
+    assert_eq!(bot.play(&rules, Direction::RIGHT, true), Some(Direction::RIGHT));
+}
+
+#[test]
+fn a_scatterer_chases_the_hero_when_its_corner_is_out_of_reach() {
+    // the top left corner is walled in
+    let rules = GameRules::from_map("\
+#########
+#.#.....#
+###.....#
+#@.....!#
+#########
+").0;
+    let mut bot = Bot::new(Item::PLAYER2, Personality::Scatterer(Corner::TopLeft), &rules);
+    assert_eq!(bot.play(&rules, Direction::DOWN, false), Some(Direction::LEFT));
+}
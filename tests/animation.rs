@@ -0,0 +1,139 @@
+//! Loading the sprite sets and playing their animations.
+
+use pas_cman_ipl::{animation::{Pose, SpriteSet, Sprites}, frame::cp437, Direction};
+
+#[test]
+fn the_default_sprites_are_those_of_the_font() {
+    let sprites = Sprites::default();
+    let hero = sprites.hero();
+    assert_eq!(hero.frame(Pose::Walking(Direction::Down), 0.0), '@');
+    assert_eq!(hero.frame(Pose::Walking(Direction::Right), 0.0), 'P');
+    assert_eq!(hero.frame(Pose::Walking(Direction::Left), 0.0), '`');
+    assert_eq!(hero.frame(Pose::Walking(Direction::Up), 0.0), 'p');
+    assert_eq!(sprites.villain().frame(Pose::Walking(Direction::Down), 0.0), '!');
+}
+
+/// The frames of the walking cycle and the idle pose of the character for
+/// each direction
+fn frames(set: &SpriteSet) -> Vec<(Vec<char>, char)> {
+    let period = set.period as f32;
+    [Direction::Down, Direction::Right, Direction::Left, Direction::Up].into_iter()
+        .map(|d| {
+            let walking = (0..4).map(|i| set.frame(Pose::Walking(d), i as f32 * period)).collect::<Vec<_>>();
+            (walking, set.frame(Pose::Idle(d), 0.0))
+        })
+        .collect()
+}
+
+#[test]
+fn the_characters_walk_through_frames_of_their_own_and_stand_still_in_other_poses() {
+    let sprites = Sprites::default();
+    for set in [sprites.hero(), sprites.villain()] {
+        let frames = frames(&set);
+        let walking = frames.iter().flat_map(|(walking, _)| walking.clone()).collect::<Vec<_>>();
+        for (i, (cycle, idle)) in frames.iter().enumerate() {
+            assert!(cycle.iter().any(|frame| *frame != cycle[0]), "{cycle:?} is not animated");
+            assert!(!walking.contains(idle), "{idle} is also a walking frame");
+            for (other, _) in &frames[i + 1..] {
+                assert!(cycle.iter().all(|frame| !other.contains(frame)), "{cycle:?} and {other:?} share frames");
+            }
+        }
+    }
+
+    let hero = sprites.hero();
+    assert_ne!(hero.frame(Pose::Idle(Direction::Left), 0.0), hero.frame(Pose::Idle(Direction::Right), 0.0));
+
+    let villain = sprites.villain();
+    assert_eq!(villain.frame(Pose::Idle(Direction::Right), 0.0), '☺');
+    assert_eq!(villain.frame(Pose::Frightened, 0.0), '☻');
+    assert_eq!(villain.frame(Pose::Frightened, 450.0), '♥');
+}
+
+#[test]
+fn animations_loop_but_the_death_is_played_once() {
+    let sprites = r#"
+        [hero]
+        period = 100
+        down   = [1, 2, 3]
+        right  = [4]
+        left   = [5]
+        up     = [6]
+        death  = [7, 8]
+
+        [villain]
+        period = 100
+        down   = [9]
+        right  = [9]
+        left   = [9]
+        up     = [9]
+    "#.parse::<Sprites>().unwrap();
+
+    let hero = sprites.hero();
+    let down = |elapsed| hero.frame(Pose::Walking(Direction::Down), elapsed);
+    assert_eq!([down(0.0), down(150.0), down(250.0), down(300.0)], [1, 2, 3, 1].map(cp437));
+    assert_eq!(hero.frame(Pose::Dying, 50.0), cp437(7));
+    assert_eq!(hero.frame(Pose::Dying, 5_000.0), cp437(8));
+    // the sets which are not given are those of the character going down
+    assert_eq!(hero.frame(Pose::Frightened, 0.0), cp437(1));
+    // standing still, the hero shows the frames of the direction it faces
+    assert_eq!(hero.frame(Pose::Idle(Direction::Down), 150.0), cp437(2));
+    assert_eq!(hero.frame(Pose::Idle(Direction::Left), 150.0), cp437(5));
+}
+
+#[test]
+fn the_idle_frames_can_be_given_for_each_direction() {
+    let sprites = r#"
+        [hero]
+        period  = 100
+        down    = [1]
+        right   = [2]
+        left    = [3]
+        up      = [4]
+        idle    = [5]
+        idle_up = [6]
+
+        [villain]
+        period = 100
+        down   = [9]
+        right  = [9]
+        left   = [9]
+        up     = [9]
+    "#.parse::<Sprites>().unwrap();
+
+    let hero = sprites.hero();
+    assert_eq!(hero.frame(Pose::Idle(Direction::Down), 0.0), cp437(5));
+    assert_eq!(hero.frame(Pose::Idle(Direction::Right), 0.0), cp437(5));
+    assert_eq!(hero.frame(Pose::Idle(Direction::Up), 0.0), cp437(6));
+}
+
+#[test]
+fn the_glyphs_above_127_are_those_of_the_code_page_437() {
+    let sprites = r#"
+        [hero]
+        period = 100
+        down   = [201, 205]
+        right  = [186]
+        left   = [186]
+        up     = [186]
+
+        [villain]
+        period = 100
+        down   = [128]
+        right  = [128]
+        left   = [128]
+        up     = [128]
+    "#.parse::<Sprites>().unwrap();
+
+    assert_eq!(sprites.hero().down.frames, vec!['╔', '═']);
+    assert_eq!(sprites.villain().down.frames, vec!['Ç']);
+}
+
+#[test]
+fn invalid_sprite_sets_are_explained() {
+    let error = |text: &str| text.parse::<Sprites>().unwrap_err();
+    let set = |name: &str, down: &str| format!("[{name}]\nperiod = 100\ndown = {down}\nright = [1]\nleft = [1]\nup = [1]\n");
+
+    assert!(error(&set("hero", "[1]")).contains("the sprite set 'villain' is missing"));
+    assert!(error(&(set("hero", "[]") + &set("villain", "[1]"))).contains("hero.down: at least one frame is needed"));
+    assert!(error(&(set("hero", "[256]") + &set("villain", "[1]"))).contains("line 3"));
+}
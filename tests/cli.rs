@@ -0,0 +1,94 @@
+//! The validation of the command line arguments.
+
+use std::process::Command;
+
+use pas_cman_ipl::{
+    cli::{parse_dimension, parse_fps, parse_speed, parse_tile_size, parse_title, Transport, MAX_DIMENSION},
+    config::TILE_SIZES,
+    replay::{MAX_SPEED, MIN_SPEED},
+};
+
+#[test]
+fn a_dimension_is_a_number_of_tiles_from_1_to_the_maximum() {
+    assert_eq!(parse_dimension("1"), Ok(1));
+    assert_eq!(parse_dimension("30"), Ok(30));
+    assert_eq!(parse_dimension(&MAX_DIMENSION.to_string()), Ok(MAX_DIMENSION));
+    for invalid in ["0", "257", "-3", "2.5", "thirty", ""] {
+        let error = parse_dimension(invalid).unwrap_err();
+        assert_eq!(error, format!("expected a number of tiles from 1 to 256, got '{invalid}'"));
+    }
+}
+
+#[test]
+fn a_tile_size_is_a_number_of_pixels_within_the_supported_sizes() {
+    assert_eq!(parse_tile_size(&TILE_SIZES.start().to_string()), Ok(*TILE_SIZES.start()));
+    assert_eq!(parse_tile_size("32"), Ok(32));
+    assert_eq!(parse_tile_size(&TILE_SIZES.end().to_string()), Ok(*TILE_SIZES.end()));
+    for invalid in [TILE_SIZES.start() - 1, TILE_SIZES.end() + 1] {
+        assert!(parse_tile_size(&invalid.to_string()).is_err(), "{invalid}");
+    }
+    assert_eq!(parse_tile_size("big"), Err("expected a number of pixels from 8 to 128, got 'big'".to_string()));
+}
+
+#[test]
+fn the_frame_rate_is_a_positive_finite_number() {
+    assert_eq!(parse_fps("30"), Ok(30.0));
+    assert_eq!(parse_fps("0.5"), Ok(0.5));
+    for invalid in ["0", "-30", "inf", "NaN", "fast"] {
+        assert_eq!(parse_fps(invalid), Err(format!("expected a positive number of frames per second, got '{invalid}'")));
+    }
+}
+
+#[test]
+fn the_replay_speed_is_a_number_within_the_supported_speeds() {
+    assert_eq!(parse_speed("1"), Ok(1.0));
+    assert_eq!(parse_speed(&MIN_SPEED.to_string()), Ok(MIN_SPEED));
+    assert_eq!(parse_speed(&MAX_SPEED.to_string()), Ok(MAX_SPEED));
+    for invalid in ["0.1", "16", "-1", "NaN", "inf", "fast"] {
+        assert_eq!(parse_speed(invalid), Err(format!("expected a speed from 0.25 to 8, got '{invalid}'")));
+    }
+}
+
+#[test]
+fn the_title_cannot_be_blank() {
+    assert_eq!(parse_title("pas cman"), Ok("pas cman".to_string()));
+    assert_eq!(parse_title("  padded  "), Ok("  padded  ".to_string()));
+    for invalid in ["", "   ", "\t\n"] {
+        assert_eq!(parse_title(invalid), Err("the title of the window cannot be empty".to_string()));
+    }
+}
+
+#[test]
+fn the_transport_is_either_stdio_or_a_tcp_address() {
+    assert_eq!("stdio".parse(), Ok(Transport::Stdio));
+    assert_eq!("tcp:localhost:9090".parse(), Ok(Transport::Tcp("localhost:9090".to_string())));
+    assert_eq!("tcp:[::1]:9090".parse(), Ok(Transport::Tcp("[::1]:9090".to_string())));
+    for invalid in ["", "udp:localhost:9090", "tcp:localhost", "tcp::9090", "tcp:localhost:99999", "localhost:9090"] {
+        assert_eq!(
+            invalid.parse::<Transport>(),
+            Err(format!("unknown transport '{invalid}' (expected stdio or tcp:HOST:PORT)"))
+        );
+    }
+    assert_eq!(Transport::Tcp("localhost:9090".to_string()).to_string(), "tcp:localhost:9090");
+}
+
+#[test]
+fn a_tcp_transport_is_rejected_by_the_modes_which_talk_to_no_server() {
+    assert_eq!(Transport::Stdio.check_unused("--offline"), Ok(()));
+    assert_eq!(
+        Transport::Tcp("localhost:9090".to_string()).check_unused("replay"),
+        Err("the transport tcp:localhost:9090 cannot be used with replay".to_string())
+    );
+}
+
+#[test]
+fn the_interface_refuses_to_play_offline_over_tcp() {
+    let output = Command::new(env!("CARGO_BIN_EXE_pas-cman-ipl"))
+        .args(["--offline", "--headless", "--transport", "tcp:localhost:9090"])
+        .env_remove("PAS_TRANSPORT")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("the transport tcp:localhost:9090 cannot be used with --offline"), "{stderr}");
+}
@@ -0,0 +1,117 @@
+//! Loading and checking the configuration file.
+
+use pas_cman_ipl::{config::{Colors, Config, HotSeat, KeyBindings}, keys::VirtualKeyCode, walls::WallStyle, Direction};
+
+#[test]
+fn an_empty_file_is_the_default_configuration() {
+    let config = "".parse::<Config>().unwrap();
+    assert_eq!(config, Config::default());
+    assert_eq!(config.keys, KeyBindings::default());
+    assert_eq!(config.colors, Colors::default());
+}
+
+#[test]
+fn several_keys_can_be_bound_to_a_direction() {
+    let config = r#"
+        [keys]
+        up   = ["Up", "W"]
+        left = ["a", "Q"]
+    "#.parse::<Config>().unwrap();
+
+    assert!(matches!(config.keys.direction(VirtualKeyCode::W), Some(Direction::Up)));
+    assert!(matches!(config.keys.direction(VirtualKeyCode::Up), Some(Direction::Up)));
+    assert!(matches!(config.keys.direction(VirtualKeyCode::A), Some(Direction::Left)));
+    assert!(matches!(config.keys.direction(VirtualKeyCode::Q), Some(Direction::Left)));
+    // the directions which are not configured keep their arrow key
+    assert!(matches!(config.keys.direction(VirtualKeyCode::Down), Some(Direction::Down)));
+    assert!(config.keys.direction(VirtualKeyCode::Left).is_none());
+    assert!(config.keys.direction(VirtualKeyCode::Space).is_none());
+}
+
+#[test]
+fn colors_and_font_are_configured() {
+    let config = r##"
+        [colors]
+        map  = { fg = "#ff8000", bg = "Black" }
+        food = { fg = "yellow" }
+
+        [font]
+        file      = "my-font-16.png"
+        tile_size = 16
+    "##.parse::<Config>().unwrap();
+
+    let fg = config.colors.map.fg;
+    assert_eq!((fg.r, fg.g, fg.b), (1.0, 128.0 / 255.0, 0.0));
+    assert_eq!(config.colors.food.bg, Colors::default().food.bg);
+    assert_ne!(config.colors.food.fg, Colors::default().food.fg);
+    assert_eq!(config.font.file.as_deref(), Some("my-font-16.png"));
+    assert_eq!(config.font.tile_size, Some(16));
+}
+
+#[test]
+fn invalid_entries_are_explained() {
+    let error = |text: &str| text.parse::<Config>().unwrap_err();
+
+    assert!(error("[keys]\nup = [\"Up\", \"Banana\"]").contains("keys.up: unknown key 'Banana'"));
+    assert!(error("[keys]\ndown = []").contains("keys.down: at least one key is needed"));
+    assert!(error("[keys]\nup = [\"W\"]\nleft = [\"W\"]").contains("W is bound to both Up and Left"));
+    assert!(error("[colors]\nfood = { fg = \"#12345\" }").contains("colors.food.fg: unknown color '#12345'"));
+    assert!(error("[colors]\nmap = { bg = \"mauve\" }").contains("colors.map.bg: unknown color 'mauve'"));
+    assert!(error("[font]\ntile_size = 4").contains("font.tile_size"));
+    assert!(error("[font]\nfile = \"\"").contains("font.file"));
+    assert!(error("[sound]\nvolume = 3").contains("sound"));
+    assert!(error("[keys]\nup = \"W\"").contains("line 2"));
+}
+
+#[test]
+fn in_hot_seat_mode_the_key_tells_the_player() {
+    let hot_seat = HotSeat::default();
+    assert!(matches!(hot_seat.direction(VirtualKeyCode::W), Some((1, Direction::Up))));
+    assert!(matches!(hot_seat.direction(VirtualKeyCode::D), Some((1, Direction::Right))));
+    assert!(matches!(hot_seat.direction(VirtualKeyCode::Up), Some((2, Direction::Up))));
+    assert!(matches!(hot_seat.direction(VirtualKeyCode::Left), Some((2, Direction::Left))));
+    assert!(hot_seat.direction(VirtualKeyCode::Space).is_none());
+}
+
+#[test]
+fn the_keys_of_the_hot_seat_players_are_configured() {
+    let config = r#"
+        [keys]
+        up = ["Up", "W"]
+
+        [hot_seat.player1]
+        up = ["Z"]
+
+        [hot_seat.player2]
+        left = ["J"]
+    "#.parse::<Config>().unwrap();
+
+    let hot_seat = &config.hot_seat;
+    assert!(matches!(hot_seat.direction(VirtualKeyCode::Z), Some((1, Direction::Up))));
+    assert!(hot_seat.direction(VirtualKeyCode::W).is_none());
+    assert!(matches!(hot_seat.direction(VirtualKeyCode::J), Some((2, Direction::Left))));
+    // the directions which are not configured keep their default keys
+    assert!(matches!(hot_seat.direction(VirtualKeyCode::D), Some((1, Direction::Right))));
+    assert!(matches!(hot_seat.direction(VirtualKeyCode::Up), Some((2, Direction::Up))));
+    assert!(hot_seat.direction(VirtualKeyCode::Left).is_none());
+
+    let error = |text: &str| text.parse::<Config>().unwrap_err();
+    assert!(error("[hot_seat.player2]\nup = [\"W\"]").contains("hot_seat: W is bound to both player1 and player2"));
+    assert!(error("[hot_seat.player1]\ndown = [\"Nope\"]").contains("hot_seat.player1.down: unknown key 'Nope'"));
+}
+
+#[test]
+fn the_turns_are_buffered_unless_disabled() {
+    assert!("".parse::<Config>().unwrap().input.buffer_turns);
+    let config = "[input]\nbuffer_turns = false\nturn_buffer = 500".parse::<Config>().unwrap();
+    assert!(!config.input.buffer_turns);
+    assert_eq!(config.input.turn_buffer, 500.0);
+}
+
+#[test]
+fn the_walls_are_drawn_as_lines_unless_blocks_are_asked_for() {
+    assert_eq!("".parse::<Config>().unwrap().walls, WallStyle::Lines);
+    assert_eq!("[walls]\nstyle = \"blocks\"".parse::<Config>().unwrap().walls, WallStyle::Blocks);
+    let error = "[walls]\nstyle = \"bricks\"".parse::<Config>().unwrap_err();
+    assert!(error.contains("walls.style: unknown style 'bricks' (expected lines or blocks)"), "{error}");
+}
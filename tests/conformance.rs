@@ -0,0 +1,179 @@
+//! The conformance test harness (pas-cman-check).
+
+use std::{io::Write, process::{Command, Stdio}};
+
+use pas_cman_ipl::{
+    conformance::{analyze, report, Check},
+    item_id,
+    pascman_protocol::{Direction, Item, Message, Position, PLAYER1_ID, PLAYER2_ID},
+    rules::{GameRules, FOOD_POINTS, SUPERFOOD_POINTS},
+};
+
+/// Player 1 on the left, player 2 on the right and three foods in between
+const CORRIDOR: &str = "\
+#########
+#@. *. !#
+#########
+";
+
+/// The stream a conforming server sends when player 1 eats all the food
+fn good_stream() -> Vec<Message> {
+    let (mut rules, mut messages) = GameRules::from_map(CORRIDOR);
+    messages.push(Message::registration(1));
+    while !rules.is_over() {
+        messages.extend(rules.play(Item::PLAYER1, Direction::RIGHT));
+    }
+    messages
+}
+
+fn analyze_all(messages: &[Message]) -> Vec<Check> {
+    analyze(&messages.iter().map(|m| Ok(*m)).collect::<Vec<_>>())
+}
+
+/// The failures of the check with the given name
+fn failures<'a>(checks: &'a [Check], name: &str) -> &'a [(Option<usize>, String)] {
+    &checks.iter().find(|c| c.name == name).expect("no such check").failures
+}
+
+/// Returns the names of the checks which failed
+fn failed(checks: &[Check]) -> Vec<&'static str> {
+    checks.iter().filter(|c| !c.failures.is_empty()).map(|c| c.name).collect()
+}
+
+#[test]
+fn a_conforming_stream_passes_every_check() {
+    let stream = good_stream();
+    assert_eq!(stream.last(), Some(&Message::game_over(1)));
+    let checks = analyze_all(&stream);
+    assert!(failed(&checks).is_empty(), "{:?}", failed(&checks));
+
+    let mut out = vec![];
+    assert!(report(&mut out, stream.len(), &checks).unwrap());
+    assert!(String::from_utf8(out).unwrap().ends_with("RESULT: PASS\n"));
+}
+
+#[test]
+fn the_messages_which_cannot_be_decoded_are_reported() {
+    let mut messages = good_stream().into_iter().map(Ok).collect::<Vec<_>>();
+    messages.insert(3, Err("invalid message type: 42".to_string()));
+    let checks = analyze(&messages);
+    assert_eq!(failed(&checks), vec!["every message can be decoded"]);
+    assert_eq!(failures(&checks, "every message can be decoded"), &[(Some(3), "invalid message type: 42".to_string())]);
+}
+
+#[test]
+fn the_protocol_violations_point_to_the_offending_message() {
+    let mut stream = good_stream();
+    let index = stream.len() - 1;
+    stream.insert(index, Message::movement(PLAYER2_ID, Position { x: 8, y: 1 }));
+    let checks = analyze_all(&stream);
+    assert_eq!(
+        failures(&checks, "every message conforms to the protocol"),
+        &[(Some(index), "moves into the wall at (8,1)".to_string())]
+    );
+}
+
+#[test]
+fn a_tile_spawned_twice_is_reported() {
+    let mut stream = good_stream();
+    stream.insert(1, Message::spawn(item_id(0, 0, Item::WALL), Item::WALL, Position { x: 0, y: 0 }));
+    let checks = analyze_all(&stream);
+    assert_eq!(
+        failures(&checks, "every tile is spawned exactly once"),
+        &[(Some(1), "the tile at (0,0) has already been spawned by #0".to_string())]
+    );
+}
+
+#[test]
+fn the_client_is_registered_exactly_once() {
+    let stream = good_stream().into_iter()
+        .filter(|m| *m != Message::registration(1))
+        .collect::<Vec<_>>();
+    let checks = analyze_all(&stream);
+    assert_eq!(failures(&checks, "the client is registered exactly once"), &[(None, "no REGISTRATION received".to_string())]);
+
+    let mut stream = good_stream();
+    stream.insert(stream.len() - 1, Message::registration(2));
+    let checks = analyze_all(&stream);
+    assert_eq!(failures(&checks, "the client is registered exactly once"), &[(Some(stream.len() - 2), "registered more than once".to_string())]);
+}
+
+#[test]
+fn both_players_are_spawned() {
+    let stream = good_stream().into_iter()
+        .filter(|m| *m != Message::spawn(PLAYER2_ID, Item::PLAYER2, Position { x: 7, y: 1 }))
+        .collect::<Vec<_>>();
+    let checks = analyze_all(&stream);
+    assert_eq!(failures(&checks, "both players are spawned exactly once"), &[(None, "player 2 has been spawned 0 time(s)".to_string())]);
+}
+
+#[test]
+fn the_food_is_only_eaten_once() {
+    let mut stream = good_stream();
+    let eaten = stream.iter().position(|m| *m == Message::eat_food(PLAYER1_ID, item_id(2, 1, Item::FOOD))).unwrap();
+    stream.insert(eaten + 1, stream[eaten]);
+    let checks = analyze_all(&stream);
+    assert!(failures(&checks, "the food counts are consistent").iter()
+        .any(|(index, _)| *index == Some(eaten + 1)));
+}
+
+#[test]
+fn the_game_over_announces_a_plausible_winner() {
+    let mut stream = good_stream();
+    let last = stream.len() - 1;
+    stream[last] = Message::game_over(2);
+    let checks = analyze_all(&stream);
+    let score = 2 * FOOD_POINTS + SUPERFOOD_POINTS;
+    assert_eq!(
+        failures(&checks, "exactly one GAME_OVER with a plausible winner"),
+        &[(Some(last), format!("the winner is 2 but the scores are {score} vs 0 (expected 1)"))]
+    );
+}
+
+#[test]
+fn a_missing_game_over_is_reported() {
+    let mut stream = good_stream();
+    stream.pop();
+    let checks = analyze_all(&stream);
+    assert_eq!(failed(&checks), vec!["the food counts are consistent", "exactly one GAME_OVER with a plausible winner"]);
+
+    let mut out = vec![];
+    assert!(!report(&mut out, stream.len(), &checks).unwrap());
+    assert!(String::from_utf8(out).unwrap().ends_with("RESULT: FAIL (2 of 7 checks failed)\n"));
+}
+
+/// Runs pas-cman-check on the given stream (on its standard input) and
+/// returns its exit code along with its report
+fn check(stream: &[Message]) -> (Option<i32>, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pas-cman-check"))
+        .args(["--timeout", "1s"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for message in stream {
+        stdin.write_all(&message.to_bytes()).unwrap();
+    }
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    (output.status.code(), String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn the_harness_exits_with_status_0_when_every_check_passes() {
+    let (code, report) = check(&good_stream());
+    assert_eq!(code, Some(0), "{report}");
+    assert!(report.contains("RESULT: PASS"));
+}
+
+#[test]
+fn the_harness_exits_with_status_1_when_some_check_fails() {
+    let mut stream = good_stream();
+    stream.push(Message::game_over(1));
+    let (code, report) = check(&stream);
+    assert_eq!(code, Some(1), "{report}");
+    assert!(report.contains("more than one GAME_OVER"));
+    assert!(report.contains("RESULT: FAIL"));
+}
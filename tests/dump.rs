@@ -0,0 +1,106 @@
+//! Decoding the raw message streams.
+
+use pas_cman_ipl::{
+    dump::{decode, dump, to_json, Record},
+    pascman_protocol::{Item, Message, Position, MESSAGE_SIZE},
+};
+use serde_json::{json, Value};
+
+/// Dumps the given stream (for a 30x20 map) and returns the lines which have
+/// been written along with the number of problems
+fn run(stream: &[u8], json: bool) -> (Vec<String>, usize) {
+    let mut output = vec![];
+    let problems = dump(&mut &stream[..], &mut output, json, 30, 20).unwrap();
+    let lines = String::from_utf8(output).unwrap().lines().map(String::from).collect();
+    (lines, problems)
+}
+
+fn stream(messages: &[Message]) -> Vec<u8> {
+    messages.iter().flat_map(|m| m.to_bytes()).collect()
+}
+
+#[test]
+fn a_valid_stream_is_decoded_one_line_per_message() {
+    let bytes = stream(&[
+        Message::registration(1),
+        Message::spawn(600, Item::WALL, Position { x: 0, y: 0 }),
+        Message::movement(1800, Position { x: 12, y: 9 }),
+        Message::eat_food(1800, 42),
+        Message::game_over(1),
+    ]);
+    let (lines, problems) = run(&bytes, false);
+    assert_eq!(problems, 0);
+    assert_eq!(lines, vec![
+        "#0 REGISTRATION player=1",
+        "#1 SPAWN id=600 WALL at (0,0)",
+        "#2 MOVEMENT id=1800 -> (12,9)",
+        "#3 EAT_FOOD eater=1800 food=42",
+        "#4 GAME_OVER winner=1",
+    ]);
+}
+
+#[test]
+fn the_invalid_records_are_flagged_and_the_stream_goes_on() {
+    let mut unknown = [0_u8; MESSAGE_SIZE];
+    unknown[0] = 42;
+    let mut bytes = unknown.to_vec();
+    bytes.extend(Message::registration(2).to_bytes());
+
+    let (lines, problems) = run(&bytes, false);
+    assert_eq!(problems, 1);
+    assert!(lines[0].starts_with("#0 INVALID invalid message type: 42 [2a 00 00 00"), "{}", lines[0]);
+    assert_eq!(lines[1], "#1 REGISTRATION player=2");
+}
+
+#[test]
+fn the_positions_are_checked_against_the_size_of_the_map() {
+    let outside = Message::movement(1800, Position { x: 30, y: 3 }).to_bytes();
+    assert!(decode(&outside, 30, 20).is_problem());
+    assert!(!decode(&outside, 31, 20).is_problem());
+
+    let (lines, problems) = run(&outside, false);
+    assert_eq!(problems, 1);
+    assert_eq!(lines, vec!["#0 MOVEMENT id=1800 -> (30,3)  !! position out of range"]);
+}
+
+#[test]
+fn the_trailing_bytes_are_flagged() {
+    let mut bytes = Message::game_over(2).to_bytes().to_vec();
+    bytes.extend([1, 2, 3]);
+
+    let (lines, problems) = run(&bytes, false);
+    assert_eq!(problems, 1);
+    assert_eq!(lines, vec!["#0 GAME_OVER winner=2", "#1 TRUNCATED 3 trailing bytes [01 02 03]"]);
+}
+
+#[test]
+fn each_record_is_written_as_a_json_object() {
+    let mut bytes = stream(&[
+        Message::spawn(12, Item::FOOD, Position { x: 3, y: 4 }),
+        Message::movement(1800, Position { x: 99, y: 0 }),
+    ]);
+    let mut garbage = [0_u8; MESSAGE_SIZE];
+    garbage[0] = 1;
+    garbage[8] = 9;
+    bytes.extend(garbage);
+    bytes.push(7);
+
+    let (lines, problems) = run(&bytes, true);
+    assert_eq!(problems, 3);
+    let values = lines.iter().map(|l| serde_json::from_str::<Value>(l).unwrap()).collect::<Vec<_>>();
+    assert_eq!(values, vec![
+        json!({"index": 0, "kind": "VALID", "warnings": [],
+               "message": {"msgt": "SPAWN", "id": 12, "item": "FOOD", "pos": {"x": 3, "y": 4}}}),
+        json!({"index": 1, "kind": "VALID", "warnings": ["position out of range"],
+               "message": {"msgt": "MOVEMENT", "id": 1800, "pos": {"x": 99, "y": 0}}}),
+        json!({"index": 2, "kind": "INVALID", "error": "invalid item: 9", "bytes": garbage.to_vec()}),
+        json!({"index": 3, "kind": "TRUNCATED", "bytes": [7]}),
+    ]);
+}
+
+#[test]
+fn the_json_strings_are_escaped() {
+    let record = Record::Invalid { error: String::from("a \"quoted\"\nerror"), bytes: vec![] };
+    let value = serde_json::from_str::<Value>(&to_json(5, &record)).unwrap();
+    assert_eq!(value["error"], "a \"quoted\"\nerror");
+}
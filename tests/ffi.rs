@@ -0,0 +1,80 @@
+//! Drives the interface through its C API (in headless mode).
+
+use std::{ptr, thread, time::Duration};
+
+use pas_cman_ipl::{
+    ffi::*,
+    frame::Frame,
+    keys::VirtualKeyCode,
+    load_map,
+    pascman_protocol::{Direction, Item, Message, MessageType, MESSAGE_SIZE},
+};
+
+const HEADLESS: PascmanGuiOptions = PascmanGuiOptions { resources: ptr::null(), headless: true, strict: false };
+
+/// A message as a C program could write it (with the alignment of the union)
+fn raw(words: &[u32]) -> [u32; MESSAGE_SIZE / 4] {
+    let mut raw = [0; MESSAGE_SIZE / 4];
+    raw[..words.len()].copy_from_slice(words);
+    raw
+}
+
+/// The interface is global: everything is checked in a single test
+#[test]
+fn the_interface_is_driven_through_the_c_api() {
+    unsafe {
+        let mut direction = Direction::UP;
+        assert_eq!(pascman_gui_push(&Message::registration(1)), -1, "not open yet");
+        assert_eq!(pascman_gui_poll_direction(&mut direction), -1, "not open yet");
+        assert_eq!(pascman_gui_run(), -1, "not open yet");
+
+        assert_eq!(pascman_gui_open(&HEADLESS), 0);
+        assert_eq!(pascman_gui_open(&HEADLESS), -1, "already open");
+
+        for message in load_map("#####\n#@.!#\n#####\n") {
+            assert_eq!(pascman_gui_push(&message), 0);
+        }
+        assert_eq!(pascman_gui_push(&Message::registration(1)), 0);
+        assert_eq!(pascman_gui_push(ptr::null()), -1);
+
+        let invalid = raw(&[42]);
+        assert_eq!(pascman_gui_push(invalid.as_ptr().cast()), -1, "invalid message type");
+        let invalid = raw(&[MessageType::SPAWN as u32, 12, 7, 1, 1]);
+        assert_eq!(pascman_gui_push(invalid.as_ptr().cast()), -1, "invalid item");
+        let food = raw(&[MessageType::SPAWN as u32, 12, Item::FOOD as u32, 2, 1]);
+        assert_eq!(pascman_gui_push(food.as_ptr().cast()), 0);
+
+        // the pushed messages are applied by the interface
+        let ascii = with_state(|state| {
+            state.update(None, 33.0);
+            state.resources.get::<Frame>().unwrap().to_ascii()
+        });
+        assert_eq!(ascii.unwrap().lines().nth(1), Some("##@ . ! ##"));
+
+        // nobody presses any key...
+        assert_eq!(pascman_gui_poll_direction(&mut direction), 0);
+        assert_eq!(pascman_gui_poll_direction(ptr::null_mut()), -1);
+        // ... until the user goes right
+        with_state(|state| state.update(Some(VirtualKeyCode::Right), 33.0)).unwrap();
+        assert_eq!(pascman_gui_poll_direction(&mut direction), 1);
+        assert_eq!(direction, Direction::RIGHT);
+        assert_eq!(pascman_gui_poll_direction(&mut direction), 0);
+
+        // the interface runs on this thread until it is closed from another one
+        let player = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(100));
+            assert_eq!(pascman_gui_push(&Message::game_over(1)), 0);
+            pascman_gui_close();
+        });
+        assert_eq!(pascman_gui_run(), 0);
+        player.join().unwrap();
+
+        assert_eq!(pascman_gui_push(&Message::game_over(1)), -1, "closed");
+        assert_eq!(pascman_gui_run(), -1, "closed");
+
+        // it can be opened again once it has been closed
+        assert_eq!(pascman_gui_open(&HEADLESS), 0);
+        pascman_gui_close();
+        assert_eq!(pascman_gui_run(), -1, "closed before running");
+    }
+}
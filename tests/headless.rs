@@ -0,0 +1,274 @@
+//! Snapshot tests of what the game draws and of the way it reacts to the
+//! input (using the headless rendering).
+
+use std::{sync::mpsc::channel, time::{Duration, Instant}};
+
+use pas_cman_ipl::{
+    animation::Sprites,
+    config::HotSeat,
+    frame::{DumpFormat, Frame, MAP_LAYER},
+    headless::Headless,
+    keys::VirtualKeyCode,
+    load_map, player_ids,
+    pascman_protocol::{Direction, Item, Message, Position, HEIGHT, WIDTH},
+    prediction::{Prediction, CORRECTION_DURATION},
+    ColorPair, Corrected, DirectionSink, Id, IntoQuery, Point, State, BLACK, WHITE,
+};
+
+/// A tiny map: a corridor with the two players and some food
+const CORRIDOR: &str = "\
+#####
+#@.!#
+#####
+";
+
+/// Sends the messages to a fresh game, runs one tick and dumps the frame
+fn frame_after(messages: &[Message]) -> String {
+    let (sx, rx) = channel();
+    let mut state = State::new(rx);
+    for message in messages {
+        sx.send(*message).unwrap();
+    }
+    state.update(None, 33.0);
+
+    let frame = state.resources.get::<Frame>().unwrap();
+    frame.to_ascii()
+}
+
+fn corridor() -> Vec<Message> {
+    let mut messages = load_map(CORRIDOR);
+    messages.push(Message::registration(1));
+    messages
+}
+
+#[test]
+fn nothing_is_drawn_before_the_registration() {
+    let ascii = frame_after(&load_map(CORRIDOR));
+    assert!(ascii.lines().all(|line| line.is_empty()));
+}
+
+#[test]
+fn the_map_food_and_characters_are_composited() {
+    let ascii = frame_after(&corridor());
+    let lines = ascii.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 20);
+    assert_eq!(lines[0], "##########");
+    assert_eq!(lines[1], "##@ . ! ##");
+    assert_eq!(lines[2], "##########");
+    assert!(lines[3..].iter().all(|line| line.is_empty()));
+}
+
+#[test]
+fn the_characters_are_shown_as_in_the_map_whatever_their_sprites() {
+    let (sx, rx) = channel();
+    let mut state = State::new(rx);
+    let sprites = "[hero]\nperiod = 100\ndown = [90]\nright = [90]\nleft = [90]\nup = [90]\n\n\
+                   [villain]\nperiod = 100\ndown = [90]\nright = [90]\nleft = [90]\nup = [90]\n";
+    state.resources.insert(sprites.parse::<Sprites>().unwrap());
+    corridor().into_iter().for_each(|m| sx.send(m).unwrap());
+    state.update(None, 33.0);
+
+    let ascii = state.resources.get::<Frame>().unwrap().to_ascii();
+    assert_eq!(ascii.lines().nth(1), Some("##@ . ! ##"));
+}
+
+#[test]
+fn the_game_over_screen_hides_the_map() {
+    let mut messages = corridor();
+    messages.push(Message::game_over(1));
+    let ascii = frame_after(&messages);
+    assert!(!ascii.contains('#'));
+    assert!(ascii.contains("Congratulations, you won !"));
+    assert!(ascii.contains("Press ENTER to end"));
+}
+
+#[test]
+fn the_protocol_violations_are_shown_on_top_of_the_map() {
+    let mut messages = corridor();
+    messages.push(Message::movement(4242, Position { x: 1, y: 1 }));
+    let ascii = frame_after(&messages);
+    assert!(ascii.lines().next().unwrap().starts_with("1 PROTOCOL VIOLATION(S) (reported)"));
+}
+
+#[test]
+fn the_ticks_do_not_wait_unless_a_frame_rate_is_requested() {
+    let (sx, rx) = channel();
+    let mut state = State::new(rx);
+    corridor().into_iter().for_each(|m| sx.send(m).unwrap());
+
+    let headless = Headless {
+        format: DumpFormat::Ascii,
+        every: 0,
+        max_frames: Some(100),
+        frame_time: Duration::from_secs(1),
+        realtime: false,
+    };
+    let start = Instant::now();
+    let mut out = vec![];
+    headless.run(&mut state, &mut out).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert!(String::from_utf8(out).unwrap().starts_with("--- frame 100 ---"));
+}
+
+#[test]
+fn the_hot_seat_players_may_press_a_key_at_once() {
+    let (sx, rx) = channel();
+    let mut state = State::new(rx);
+    corridor().into_iter().for_each(|m| sx.send(m).unwrap());
+    let (player1, directions1) = channel();
+    let (player2, directions2) = channel();
+    state.resources.insert(Some(HotSeat::default()));
+    state.resources.insert(DirectionSink::PerPlayer([player1, player2]));
+    state.update(None, 33.0);
+
+    // the window only reports the key pressed by the second player
+    state.hold_keys([VirtualKeyCode::D, VirtualKeyCode::Left]);
+    state.update(Some(VirtualKeyCode::Left), 33.0);
+    assert_eq!(directions1.try_recv(), Ok(Direction::RIGHT));
+    assert_eq!(directions2.try_recv(), Ok(Direction::LEFT));
+}
+
+/// Where the first player is and whether its position has just been corrected
+fn player1(state: &State) -> ((usize, usize), bool) {
+    let [player1, _] = player_ids(WIDTH, HEIGHT);
+    <(&Id, &pas_cman_ipl::Position, Option<&Corrected>)>::query()
+        .iter(&state.ecs)
+        .find(|(id, _, _)| id.0 == player1)
+        .map(|(_, position, corrected)| ((position.x, position.y), corrected.is_some()))
+        .unwrap()
+}
+
+#[test]
+fn the_schedule_reconciles_the_predicted_moves_and_wears_off_the_corrections() {
+    let (sx, rx) = channel();
+    let mut state = State::new(rx);
+    load_map("######\n#@ .!#\n######\n").into_iter().for_each(|m| sx.send(m).unwrap());
+    sx.send(Message::registration(1)).unwrap();
+    let (directions, _sent) = channel();
+    state.resources.insert(Prediction::new(true));
+    state.resources.insert(DirectionSink::Channel(directions));
+    state.update(None, 33.0);
+    assert_eq!(player1(&state), ((1, 1), false));
+
+    // the move is predicted, then confirmed by the server
+    state.update(Some(VirtualKeyCode::Right), 33.0);
+    assert_eq!(player1(&state), ((2, 1), false));
+    let [id, _] = player_ids(WIDTH, HEIGHT);
+    sx.send(Message::movement(id, Position { x: 2, y: 1 })).unwrap();
+    state.update(None, 33.0);
+    assert_eq!(player1(&state), ((2, 1), false));
+
+    // the next move is never confirmed: once it expires, the player goes back
+    state.update(Some(VirtualKeyCode::Left), 33.0);
+    assert_eq!(player1(&state), ((1, 1), false));
+    let timeout = state.resources.get::<Prediction>().unwrap().timeout();
+    state.update(None, timeout + 1.0);
+    assert_eq!(player1(&state), ((2, 1), true));
+
+    // the correction remains visible for a while
+    state.update(None, CORRECTION_DURATION / 2.0);
+    assert_eq!(player1(&state), ((2, 1), true));
+    state.update(None, CORRECTION_DURATION / 2.0 + 1.0);
+    assert_eq!(player1(&state), ((2, 1), false));
+}
+
+#[test]
+fn the_moves_confirmed_within_a_single_tick_are_all_reconciled() {
+    let (sx, rx) = channel();
+    let mut state = State::new(rx);
+    load_map("#######\n#@  .!#\n#######\n").into_iter().for_each(|m| sx.send(m).unwrap());
+    sx.send(Message::registration(1)).unwrap();
+    let (directions, _sent) = channel();
+    state.resources.insert(Prediction::new(true));
+    state.resources.insert(DirectionSink::Channel(directions));
+    state.update(None, 33.0);
+
+    // two moves are predicted...
+    state.update(Some(VirtualKeyCode::Right), 33.0);
+    state.update(None, 33.0);
+    state.update(Some(VirtualKeyCode::Right), 33.0);
+    assert_eq!(player1(&state), ((3, 1), false));
+
+    // ... and the server confirms both of them at once
+    let [id, _] = player_ids(WIDTH, HEIGHT);
+    sx.send(Message::movement(id, Position { x: 2, y: 1 })).unwrap();
+    sx.send(Message::movement(id, Position { x: 3, y: 1 })).unwrap();
+    state.update(None, 33.0);
+    assert_eq!(player1(&state), ((3, 1), false));
+
+    // nothing is left to expire
+    let timeout = state.resources.get::<Prediction>().unwrap().timeout();
+    state.update(None, timeout + 1.0);
+    assert_eq!(player1(&state), ((3, 1), false));
+}
+
+/// Scribbles on a floor tile of the map layer: the scribble remains until the
+/// map layer is drawn again
+fn scribble(state: &State) {
+    let mut frame = state.resources.get_mut::<Frame>().unwrap();
+    frame.layers[MAP_LAYER].set(Point::new(1, 1), ColorPair::new(WHITE, BLACK), 'X');
+}
+
+fn scribbled(state: &State) -> bool {
+    let frame = state.resources.get::<Frame>().unwrap();
+    frame.layers[MAP_LAYER].get(1, 1).is_some_and(|cell| cell.glyph == 'X')
+}
+
+fn map_generation(state: &State) -> Option<u64> {
+    state.resources.get::<Frame>().unwrap().map_generation()
+}
+
+#[test]
+fn the_map_layer_is_only_redrawn_when_the_map_changes() {
+    let (sx, rx) = channel();
+    let mut state = State::new(rx);
+    for message in corridor() {
+        sx.send(message).unwrap();
+    }
+    state.update(None, 33.0);
+    let drawn = map_generation(&state);
+    assert!(drawn.is_some());
+
+    // nothing changed: the map layer is kept as it is
+    scribble(&state);
+    state.update(None, 33.0);
+    assert!(scribbled(&state));
+    assert_eq!(map_generation(&state), drawn);
+
+    // a wall appears: the whole map layer is drawn again
+    sx.send(Message::spawn(0, Item::WALL, Position { x: 3, y: 5 })).unwrap();
+    state.update(None, 33.0);
+    assert!(!scribbled(&state));
+    assert!(map_generation(&state) > drawn);
+    let frame = state.resources.get::<Frame>().unwrap();
+    assert!(frame.layers[MAP_LAYER].get(3, 5).is_some_and(|cell| cell.glyph == '■'));
+    drop(frame);
+
+    // so does a floor replacing that wall
+    let drawn = map_generation(&state);
+    scribble(&state);
+    sx.send(Message::spawn(0, Item::FLOOR, Position { x: 3, y: 5 })).unwrap();
+    state.update(None, 33.0);
+    assert!(!scribbled(&state));
+    assert!(map_generation(&state) > drawn);
+    let frame = state.resources.get::<Frame>().unwrap();
+    assert!(frame.layers[MAP_LAYER].get(3, 5).is_some_and(|cell| cell.glyph == ' '));
+}
+
+#[test]
+fn the_map_layer_is_redrawn_once_invalidated() {
+    let (sx, rx) = channel();
+    let mut state = State::new(rx);
+    for message in corridor() {
+        sx.send(message).unwrap();
+    }
+    state.update(None, 33.0);
+    let drawn = map_generation(&state);
+
+    scribble(&state);
+    state.resources.get_mut::<Frame>().unwrap().invalidate_map();
+    assert_eq!(map_generation(&state), None);
+    state.update(None, 33.0);
+    assert!(!scribbled(&state));
+    assert_eq!(map_generation(&state), drawn);
+}
@@ -0,0 +1,84 @@
+//! How the keys are repeated, buffered and rate limited.
+
+use pas_cman_ipl::{input::{InputQueue, InputSettings, PlayerInput}, keys::VirtualKeyCode, Direction};
+
+const SETTINGS: InputSettings = InputSettings { repeat_delay: 250.0, repeat_interval: 100.0, min_interval: 50.0, buffer_turns: true, turn_buffer: 1_000.0 };
+const UP: Option<(VirtualKeyCode, Direction)> = Some((VirtualKeyCode::Up, Direction::Up));
+const RIGHT: Option<(VirtualKeyCode, Direction)> = Some((VirtualKeyCode::Right, Direction::Right));
+
+#[test]
+fn a_key_which_is_held_down_is_repeated() {
+    let mut input = PlayerInput::default();
+    let held = [VirtualKeyCode::Right];
+    let anywhere = |_| true;
+
+    assert_eq!(input.update(&SETTINGS, RIGHT, &held, 100.0, anywhere), Some(Direction::Right));
+    // nothing happens until the repeat delay has elapsed
+    assert_eq!(input.update(&SETTINGS, None, &held, 100.0, anywhere), None);
+    assert_eq!(input.update(&SETTINGS, None, &held, 100.0, anywhere), None);
+    assert_eq!(input.update(&SETTINGS, None, &held, 100.0, anywhere), Some(Direction::Right));
+    // the keyboard repeating the key is not a new key
+    assert_eq!(input.update(&SETTINGS, RIGHT, &held, 50.0, anywhere), None);
+    assert_eq!(input.update(&SETTINGS, None, &held, 50.0, anywhere), Some(Direction::Right));
+    // once released, the key is not repeated anymore
+    for _ in 0..10 {
+        assert_eq!(input.update(&SETTINGS, None, &[], 100.0, anywhere), None);
+    }
+}
+
+#[test]
+fn a_turn_is_taken_as_soon_as_it_is_possible() {
+    let mut input = PlayerInput::default();
+    let wall_above = |direction| direction != Direction::Up;
+    let junction   = |_| true;
+
+    assert_eq!(input.update(&SETTINGS, UP, &[], 16.0, wall_above), None);
+    assert_eq!(input.update(&SETTINGS, None, &[], 16.0, wall_above), None);
+    assert_eq!(input.update(&SETTINGS, None, &[], 16.0, junction), Some(Direction::Up));
+    // the turn has been taken: it is not sent twice
+    assert_eq!(input.update(&SETTINGS, None, &[], 100.0, junction), None);
+}
+
+#[test]
+fn unless_the_turns_are_buffered_the_directions_into_a_wall_are_sent() {
+    let settings = InputSettings { buffer_turns: false, ..InputSettings::default() };
+    let mut input = PlayerInput::default();
+    let wall_above = |direction| direction != Direction::Up;
+
+    assert_eq!(input.update(&settings, UP, &[], 16.0, wall_above), Some(Direction::Up));
+    assert_eq!(input.update(&settings, None, &[], 100.0, |_| true), None);
+}
+
+#[test]
+fn a_buffered_turn_expires() {
+    let mut input = PlayerInput::default();
+    let wall_above = |direction| direction != Direction::Up;
+
+    assert_eq!(input.update(&SETTINGS, UP, &[], 16.0, wall_above), None);
+    assert_eq!(input.update(&SETTINGS, None, &[], 1_100.0, wall_above), None);
+    assert_eq!(input.update(&SETTINGS, None, &[], 16.0, |_| true), None);
+}
+
+#[test]
+fn the_directions_are_rate_limited() {
+    let mut input = PlayerInput::default();
+    let anywhere = |_| true;
+
+    assert_eq!(input.update(&SETTINGS, RIGHT, &[], 10.0, anywhere), Some(Direction::Right));
+    // a key pressed too soon is sent once the minimum interval has elapsed
+    assert_eq!(input.update(&SETTINGS, UP, &[], 10.0, anywhere), None);
+    assert_eq!(input.update(&SETTINGS, None, &[], 10.0, anywhere), None);
+    assert_eq!(input.update(&SETTINGS, None, &[], 30.0, anywhere), Some(Direction::Up));
+}
+
+#[test]
+fn the_keys_pressed_at_once_are_all_noticed() {
+    let mut queue = InputQueue::default();
+
+    assert_eq!(queue.newly_pressed(&[VirtualKeyCode::W, VirtualKeyCode::Up]), vec![VirtualKeyCode::W, VirtualKeyCode::Up]);
+    // the keys held down are not pressed again...
+    assert_eq!(queue.newly_pressed(&[VirtualKeyCode::W, VirtualKeyCode::Up, VirtualKeyCode::Left]), vec![VirtualKeyCode::Left]);
+    assert!(queue.newly_pressed(&[VirtualKeyCode::Left]).is_empty());
+    // ... unless they have been released in the meantime
+    assert_eq!(queue.newly_pressed(&[VirtualKeyCode::W, VirtualKeyCode::Left]), vec![VirtualKeyCode::W]);
+}
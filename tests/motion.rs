@@ -0,0 +1,40 @@
+//! Gliding from one tile to the next.
+
+use pas_cman_ipl::{motion::{Glide, MotionSettings}, Position};
+
+const SETTINGS: MotionSettings = MotionSettings { glide: 100.0 };
+
+fn at(x: usize, y: usize) -> Position {
+    Position { x, y }
+}
+
+#[test]
+fn a_step_is_interpolated() {
+    let mut glide = Glide::start(&SETTINGS, None, at(3, 5), at(4, 5)).unwrap();
+    assert_eq!(glide.at(&SETTINGS, at(4, 5)), (3.0, 5.0));
+    glide.elapsed = 25.0;
+    assert_eq!(glide.at(&SETTINGS, at(4, 5)), (3.25, 5.0));
+    glide.elapsed = 250.0;
+    assert!(glide.is_over(&SETTINGS));
+    assert_eq!(glide.at(&SETTINGS, at(4, 5)), (4.0, 5.0));
+}
+
+#[test]
+fn teleports_are_not_interpolated() {
+    assert!(Glide::start(&SETTINGS, None, at(3, 5), at(9, 1)).is_none());
+    assert!(Glide::start(&SETTINGS, None, at(3, 5), at(4, 6)).is_none());
+    assert!(Glide::start(&SETTINGS, None, at(3, 5), at(3, 5)).is_none());
+    assert!(Glide::start(&MotionSettings { glide: 0.0 }, None, at(3, 5), at(4, 5)).is_none());
+}
+
+#[test]
+fn a_glide_goes_on_in_a_straight_line_but_not_around_a_corner() {
+    let glide = Glide { from: (3.0, 5.0), elapsed: 50.0 };
+    // still halfway between (3, 5) and (4, 5)
+    let ahead = Glide::start(&SETTINGS, Some(&glide), at(4, 5), at(5, 5)).unwrap();
+    assert_eq!(ahead.from, (3.5, 5.0));
+    assert!(Glide::start(&SETTINGS, Some(&glide), at(4, 5), at(4, 4)).is_none());
+    // once the glide is over, turning is fine
+    let done = Glide { from: (3.0, 5.0), elapsed: 100.0 };
+    assert_eq!(Glide::start(&SETTINGS, Some(&done), at(4, 5), at(4, 4)).unwrap().from, (4.0, 5.0));
+}
@@ -0,0 +1,73 @@
+//! Reconciliation of the predicted moves with those decided by the server.
+
+use pas_cman_ipl::{prediction::{Prediction, Verdict, PREDICTION_TIMEOUT, PREDICTION_TIMEOUTS}, Position};
+
+const fn at(x: usize, y: usize) -> Position {
+    Position { x, y }
+}
+
+#[test]
+fn the_moves_which_are_not_predicted_are_applied_as_usual() {
+    let mut prediction = Prediction::new(true);
+    assert_eq!(prediction.confirm(1, at(2, 1)), Verdict::Unpredicted);
+}
+
+#[test]
+fn the_predicted_moves_are_confirmed_in_order() {
+    let mut prediction = Prediction::new(true);
+    prediction.predict(1, at(1, 1), at(2, 1));
+    prediction.predict(1, at(2, 1), at(3, 1));
+
+    assert_eq!(prediction.confirm(1, at(2, 1)), Verdict::Confirmed);
+    assert_eq!(prediction.confirm(1, at(3, 1)), Verdict::Confirmed);
+    assert_eq!(prediction.confirm(1, at(4, 1)), Verdict::Unpredicted);
+}
+
+#[test]
+fn a_wrong_prediction_is_dropped() {
+    let mut prediction = Prediction::new(true);
+    prediction.predict(2, at(1, 1), at(2, 1));
+    prediction.predict(2, at(2, 1), at(3, 1));
+
+    assert_eq!(prediction.confirm(2, at(1, 2)), Verdict::Mispredicted);
+    // the other moves which were predicted are forgotten as well
+    assert_eq!(prediction.confirm(2, at(2, 1)), Verdict::Unpredicted);
+    // the players are predicted independently
+    prediction.predict(1, at(5, 5), at(5, 6));
+    assert_eq!(prediction.confirm(1, at(5, 6)), Verdict::Confirmed);
+}
+
+#[test]
+fn a_move_which_is_never_confirmed_expires() {
+    let mut prediction = Prediction::new(true);
+    prediction.predict(1, at(1, 1), at(2, 1));
+
+    assert_eq!(prediction.expire(1, PREDICTION_TIMEOUT / 2.0), None);
+    // the player goes back to where the server last put it
+    assert_eq!(prediction.expire(1, PREDICTION_TIMEOUT), Some(at(1, 1)));
+    assert_eq!(prediction.expire(1, PREDICTION_TIMEOUT), None);
+}
+
+#[test]
+fn the_timeout_follows_the_round_trip_time() {
+    let mut prediction = Prediction::new(true);
+    assert_eq!(prediction.timeout(), PREDICTION_TIMEOUT);
+
+    // the server confirms the moves after 100 ms
+    for x in 1..10 {
+        prediction.predict(1, at(x, 1), at(x + 1, 1));
+        assert_eq!(prediction.expire(1, 100.0), None);
+        assert_eq!(prediction.confirm(1, at(x + 1, 1)), Verdict::Confirmed);
+    }
+    assert_eq!(prediction.timeout(), 400.0);
+    prediction.predict(1, at(10, 1), at(11, 1));
+    assert_eq!(prediction.expire(1, 300.0), None);
+    assert_eq!(prediction.expire(1, 200.0), Some(at(10, 1)));
+
+    // over a very fast link, the moves are still given some time
+    let mut prediction = Prediction::new(true);
+    prediction.predict(2, at(1, 1), at(2, 1));
+    assert_eq!(prediction.expire(2, 1.0), None);
+    assert_eq!(prediction.confirm(2, at(2, 1)), Verdict::Confirmed);
+    assert_eq!(prediction.timeout(), *PREDICTION_TIMEOUTS.start());
+}
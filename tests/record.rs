@@ -0,0 +1,72 @@
+//! Recording the protocol streams.
+
+use std::fs;
+
+use pas_cman_ipl::{
+    pascman_protocol::{Direction, Message, ProtocolMode, MESSAGE_SIZE},
+    record::{read_replay, Event, Recorder, HEADER_SIZE, MAGIC},
+};
+
+/// Splits the entries of a replay file into their kind and payload (the
+/// timestamps are dropped)
+fn entries(mut bytes: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    let mut entries = vec![];
+    while !bytes.is_empty() {
+        let kind = bytes[0];
+        let size = match kind { 0 => MESSAGE_SIZE, 1 => 4, _ => 8 };
+        entries.push((kind, bytes[9..9 + size].to_vec()));
+        bytes = &bytes[9 + size..];
+    }
+    entries
+}
+
+#[test]
+fn the_inbound_bytes_are_recorded_exactly_as_they_were_received() {
+    let path = std::env::temp_dir().join(format!("pas-cman-record-{}.rec", std::process::id()));
+    let registration = Message::registration(2).to_bytes();
+    let mut unknown = [0_u8; MESSAGE_SIZE];
+    unknown[0] = 42;
+    let garbage = [0xff_u8; MESSAGE_SIZE];
+
+    let recorder = Recorder::create(&path, ProtocolMode::Binary).unwrap();
+    recorder.message(&registration);
+    recorder.message(&unknown);
+    recorder.direction(None, Direction::LEFT);
+    recorder.direction(Some(2), Direction::UP);
+    recorder.message(&garbage);
+    drop(recorder);
+
+    let bytes = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(&bytes[0..6], MAGIC);
+    // the registration has been noted in the header
+    assert_eq!(&bytes[28..32], &2_u32.to_le_bytes());
+    assert_eq!(entries(&bytes[HEADER_SIZE..]), vec![
+        (0, registration.to_vec()),
+        (0, unknown.to_vec()),
+        (1, (Direction::LEFT as u32).to_le_bytes().to_vec()),
+        (2, [2_u32.to_le_bytes(), (Direction::UP as u32).to_le_bytes()].concat()),
+        (0, garbage.to_vec()),
+    ]);
+}
+
+#[test]
+fn the_player_who_chose_a_direction_is_read_back() {
+    let path = std::env::temp_dir().join(format!("pas-cman-record-players-{}.rec", std::process::id()));
+    let recorder = Recorder::create(&path, ProtocolMode::Binary).unwrap();
+    recorder.direction(Some(1), Direction::RIGHT);
+    recorder.direction(Some(2), Direction::LEFT);
+    recorder.direction(None, Direction::DOWN);
+    drop(recorder);
+
+    let (_, entries) = read_replay(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let events = entries.iter().map(|entry| entry.event).collect::<Vec<_>>();
+    assert_eq!(events, vec![
+        Event::Outbound(Some(1), Direction::RIGHT),
+        Event::Outbound(Some(2), Direction::LEFT),
+        Event::Outbound(None, Direction::DOWN),
+    ]);
+}
@@ -0,0 +1,108 @@
+//! Replaying the recorded sessions.
+
+use std::{fs, time::Duration};
+
+use pas_cman_ipl::{
+    frame::Frame,
+    pascman_protocol::{Direction, Item, Message, ProtocolMode, MESSAGE_SIZE},
+    record::{Entry, Event, Header, Recorder, VERSION},
+    replay::{Replayer, Seek},
+    rules::GameRules,
+    State,
+};
+
+/// A corridor where both players eat some food
+const CORRIDOR: &str = "\
+########
+#@. ..!#
+########
+";
+
+/// The number of messages introducing the map and the player
+fn preamble() -> usize {
+    GameRules::from_map(CORRIDOR).1.len() + 1
+}
+
+/// A session where the players move one after the other (one move per second)
+fn session() -> Replayer {
+    let (mut rules, mut messages) = GameRules::from_map(CORRIDOR);
+    messages.push(Message::registration(1));
+    let mut entries = messages.into_iter()
+        .map(|message| Entry { timestamp: Duration::ZERO, event: Event::Inbound(message) })
+        .collect::<Vec<_>>();
+
+    let moves = [(Item::PLAYER1, Direction::RIGHT), (Item::PLAYER2, Direction::LEFT), (Item::PLAYER1, Direction::RIGHT)];
+    for (second, (player, dir)) in (1..).zip(moves) {
+        for message in rules.play(player, dir) {
+            entries.push(Entry { timestamp: Duration::from_secs(second), event: Event::Inbound(message) });
+        }
+    }
+    let header = Header { version: VERSION, width: 30, height: 20, mode: ProtocolMode::Binary, date: 0, player: 1 };
+    Replayer::new(header, entries)
+}
+
+/// Performs the seeks (one per tick) and dumps the frame
+fn frame_after(seeks: &[Seek]) -> String {
+    let mut state = State::replay(session());
+    for seek in seeks {
+        state.replay.as_mut().unwrap().seek(*seek);
+        // a long tick, so that the characters are done gliding
+        state.update(None, 1_000.0);
+    }
+    let frame = state.resources.get::<Frame>().unwrap();
+    frame.to_ascii()
+}
+
+#[test]
+fn going_backwards_re_simulates_the_game_from_the_start() {
+    let end = frame_after(&[Seek::Index(usize::MAX)]);
+    for index in [0, preamble(), preamble() + 2, preamble() + 4] {
+        let direct = frame_after(&[Seek::Index(index)]);
+        let back   = frame_after(&[Seek::Index(usize::MAX), Seek::Index(index)]);
+        assert_eq!(back, direct, "after going back to message #{index}");
+        assert_ne!(back, end, "after going back to message #{index}");
+    }
+}
+
+#[test]
+fn seeking_a_time_delivers_the_messages_recorded_until_then() {
+    let cursor_at = |time: Duration| {
+        let mut state = State::replay(session());
+        state.replay.as_mut().unwrap().seek(Seek::Time(time));
+        state.update(None, 0.0);
+        state.replay.as_ref().unwrap().cursor()
+    };
+    assert_eq!(cursor_at(Duration::ZERO), preamble());
+    assert_eq!(cursor_at(Duration::from_millis(1_500)), preamble() + 2);
+    assert_eq!(cursor_at(Duration::from_secs(2)), preamble() + 4);
+    // beyond the end of the replay
+    assert_eq!(cursor_at(Duration::from_secs(60)), preamble() + 5);
+}
+
+#[test]
+fn seeking_an_index_stops_at_the_end_of_the_replay() {
+    let mut replayer = session();
+    let mut state = State::new(std::sync::mpsc::channel().1);
+    replayer.seek(Seek::Index(usize::MAX));
+    replayer.update(&mut state, None, 0.0);
+    assert_eq!(replayer.cursor(), preamble() + 5);
+    assert!(replayer.is_finished());
+}
+
+#[test]
+fn the_invalid_messages_are_skipped_rather_than_aborting_the_replay() {
+    let path = std::env::temp_dir().join(format!("pas-cman-replay-{}.rec", std::process::id()));
+    let recorder = Recorder::create(&path, ProtocolMode::Binary).unwrap();
+    recorder.message(&Message::registration(1).to_bytes());
+    recorder.message(&[0xff; MESSAGE_SIZE]);
+    recorder.message(&Message::game_over(1).to_bytes());
+    drop(recorder);
+
+    let mut replayer = Replayer::open(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let mut state = State::new(std::sync::mpsc::channel().1);
+    replayer.seek(Seek::Index(usize::MAX));
+    replayer.update(&mut state, None, 0.0);
+    assert_eq!(replayer.cursor(), 2);
+}
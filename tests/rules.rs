@@ -0,0 +1,294 @@
+//! Unit tests of the authoritative game rules.
+
+use pas_cman_ipl::{
+    item_id, item_id_with_size, load_map_with_size, player_ids,
+    pascman_protocol::{Direction, Item, Message, Position, HEIGHT, PLAYER1_ID, PLAYER2_ID, WIDTH},
+    rules::{next_position, GameRules, Mode, FOOD_POINTS, SUPERFOOD_POINTS, VILLAIN_POINTS},
+};
+
+/// A small corridor: player 1 on the left, player 2 on the right and some
+/// food, superfood and empty floor in between.
+const CORRIDOR: &str = "\
+#########
+#@. *. !#
+#########
+";
+
+fn pos(x: u32, y: u32) -> Position {
+    Position { x, y }
+}
+
+fn corridor() -> GameRules {
+    GameRules::from_map(CORRIDOR).0
+}
+
+#[test]
+fn loading_the_map_places_items_and_players() {
+    let rules = corridor();
+    assert_eq!(rules.position(Item::PLAYER1), Some(pos(1, 1)));
+    assert_eq!(rules.position(Item::PLAYER2), Some(pos(7, 1)));
+    assert_eq!(rules.item_at(pos(0, 0)), Item::WALL);
+    assert_eq!(rules.item_at(pos(1, 1)), Item::FLOOR);
+    assert_eq!(rules.item_at(pos(2, 1)), Item::FOOD);
+    assert_eq!(rules.item_at(pos(3, 1)), Item::FLOOR);
+    assert_eq!(rules.item_at(pos(4, 1)), Item::SUPERFOOD);
+    assert_eq!(rules.item_at(pos(7, 1)), Item::FLOOR);
+    assert_eq!(rules.food_count(), 3);
+    assert_eq!(rules.score(Item::PLAYER1), Some(0));
+    assert_eq!(rules.score(Item::PLAYER2), Some(0));
+    assert!(!rules.is_over());
+}
+
+#[test]
+fn tiles_outside_of_the_map_text_are_walls() {
+    let rules = corridor();
+    assert_eq!(rules.item_at(pos(20, 1)), Item::WALL);
+    assert_eq!(rules.item_at(pos(0, 10)), Item::WALL);
+}
+
+#[test]
+fn a_map_without_food_is_over_from_the_start() {
+    let (rules, messages) = GameRules::from_map("####\n#@!#\n####\n");
+    assert!(rules.is_over());
+    assert_eq!(messages.last(), Some(&Message::game_over(1)));
+}
+
+#[test]
+fn the_ids_and_the_moves_depend_on_the_size_of_the_map() {
+    // a map which is wider than WIDTH
+    let walls = "#".repeat(40);
+    let text  = format!("{walls}\n#@{}.!#\n{walls}\n", " ".repeat(35));
+    let [player1, player2] = player_ids(40, 3);
+    assert_eq!([player1, player2], [3 * 120, 3 * 120 + 1]);
+    assert_eq!(player_ids(WIDTH, HEIGHT), [PLAYER1_ID, PLAYER2_ID]);
+
+    let messages = load_map_with_size(&text, 40, 3);
+    assert!(messages.contains(&Message::spawn(120 + 39, Item::WALL, pos(39, 0))));
+    assert!(messages.contains(&Message::spawn(player2, Item::PLAYER2, pos(38, 1))));
+
+    let mut rules = GameRules::with_size(&messages, 40, 3);
+    assert_eq!(rules.size(), (40, 3));
+    assert_eq!(rules.step(pos(38, 1), Direction::RIGHT), pos(39, 1));
+    assert_eq!(rules.play(Item::PLAYER2, Direction::LEFT), vec![
+        Message::movement(player2, pos(37, 1)),
+        Message::eat_food(player2, item_id_with_size(40, 3, 37, 1, Item::FOOD)),
+        Message::game_over(2),
+    ]);
+    assert_eq!(item_id_with_size(40, 3, 37, 1, Item::FOOD), 40 + 37);
+}
+
+#[test]
+fn next_position_moves_one_step_in_each_direction() {
+    let here = pos(5, 5);
+    assert_eq!(next_position(here, Direction::UP),    pos(5, 4));
+    assert_eq!(next_position(here, Direction::DOWN),  pos(5, 6));
+    assert_eq!(next_position(here, Direction::LEFT),  pos(4, 5));
+    assert_eq!(next_position(here, Direction::RIGHT), pos(6, 5));
+}
+
+#[test]
+fn next_position_never_leaves_the_map() {
+    let top_left     = pos(0, 0);
+    let bottom_right = pos(WIDTH as u32 - 1, HEIGHT as u32 - 1);
+    assert_eq!(next_position(top_left, Direction::UP),       top_left);
+    assert_eq!(next_position(top_left, Direction::LEFT),     top_left);
+    assert_eq!(next_position(bottom_right, Direction::DOWN), bottom_right);
+    assert_eq!(next_position(bottom_right, Direction::RIGHT),bottom_right);
+}
+
+#[test]
+fn moving_onto_the_floor_only_moves_the_player() {
+    let (mut rules, _) = GameRules::from_map("#####\n#@ .!\n#####\n");
+    let messages = rules.play(Item::PLAYER1, Direction::RIGHT);
+    assert_eq!(messages, vec![Message::movement(PLAYER1_ID, pos(2, 1))]);
+    assert_eq!(rules.position(Item::PLAYER1), Some(pos(2, 1)));
+    assert_eq!(rules.score(Item::PLAYER1), Some(0));
+    assert_eq!(rules.food_count(), 1);
+}
+
+#[test]
+fn moving_into_a_wall_does_nothing() {
+    let mut rules = corridor();
+    let before = rules.clone();
+    for dir in [Direction::UP, Direction::DOWN, Direction::LEFT] {
+        assert!(rules.play(Item::PLAYER1, dir).is_empty());
+    }
+    assert_eq!(rules, before);
+}
+
+#[test]
+fn moving_against_the_border_of_the_map_stays_in_place() {
+    // just like in game.c, the player "moves" to the very same position
+    let (mut rules, _) = GameRules::from_map("@.!\n");
+    let messages = rules.play(Item::PLAYER1, Direction::LEFT);
+    assert_eq!(messages, vec![Message::movement(PLAYER1_ID, pos(0, 0))]);
+    let messages = rules.play(Item::PLAYER1, Direction::UP);
+    assert_eq!(messages, vec![Message::movement(PLAYER1_ID, pos(0, 0))]);
+    assert_eq!(rules.position(Item::PLAYER1), Some(pos(0, 0)));
+}
+
+#[test]
+fn eating_food_moves_the_player_and_earns_one_point() {
+    let mut rules = corridor();
+    let messages = rules.play(Item::PLAYER1, Direction::RIGHT);
+    assert_eq!(messages, vec![
+        Message::movement(PLAYER1_ID, pos(2, 1)),
+        Message::eat_food(PLAYER1_ID, item_id(2, 1, Item::FOOD)),
+    ]);
+    assert_eq!(rules.score(Item::PLAYER1), Some(FOOD_POINTS));
+    assert_eq!(rules.item_at(pos(2, 1)), Item::FLOOR);
+    assert_eq!(rules.food_count(), 2);
+}
+
+#[test]
+fn eating_superfood_earns_seventeen_points() {
+    let mut rules = corridor();
+    let messages = rules.play(Item::PLAYER2, Direction::LEFT);
+    assert_eq!(messages, vec![Message::movement(PLAYER2_ID, pos(6, 1))]);
+    rules.play(Item::PLAYER2, Direction::LEFT);
+    let messages = rules.play(Item::PLAYER2, Direction::LEFT);
+    assert_eq!(messages, vec![
+        Message::movement(PLAYER2_ID, pos(4, 1)),
+        Message::eat_food(PLAYER2_ID, item_id(4, 1, Item::SUPERFOOD)),
+    ]);
+    assert_eq!(rules.score(Item::PLAYER2), Some(FOOD_POINTS + SUPERFOOD_POINTS));
+    assert_eq!(rules.item_at(pos(4, 1)), Item::FLOOR);
+    assert_eq!(rules.food_count(), 1);
+}
+
+#[test]
+fn food_can_only_be_eaten_once() {
+    let mut rules = corridor();
+    rules.play(Item::PLAYER1, Direction::RIGHT);
+    rules.play(Item::PLAYER1, Direction::LEFT);
+    let messages = rules.play(Item::PLAYER1, Direction::RIGHT);
+    assert_eq!(messages, vec![Message::movement(PLAYER1_ID, pos(2, 1))]);
+    assert_eq!(rules.score(Item::PLAYER1), Some(FOOD_POINTS));
+}
+
+#[test]
+fn eating_the_last_food_ends_the_game() {
+    let (mut rules, _) = GameRules::from_map("#####\n#@.!#\n#####\n");
+    let messages = rules.play(Item::PLAYER1, Direction::RIGHT);
+    assert_eq!(messages, vec![
+        Message::movement(PLAYER1_ID, pos(2, 1)),
+        Message::eat_food(PLAYER1_ID, item_id(2, 1, Item::FOOD)),
+        Message::game_over(1),
+    ]);
+    assert!(rules.is_over());
+    assert_eq!(rules.food_count(), 0);
+}
+
+#[test]
+fn bumping_into_the_other_player_ends_the_game() {
+    let (mut rules, _) = GameRules::from_map("#####\n#@!.#\n#####\n");
+    let messages = rules.play(Item::PLAYER1, Direction::RIGHT);
+    assert_eq!(messages, vec![Message::game_over(2)]);
+    assert!(rules.is_over());
+    assert_eq!(rules.position(Item::PLAYER1), Some(pos(1, 1)));
+}
+
+#[test]
+fn the_player_with_the_best_score_wins() {
+    let (mut rules, _) = GameRules::from_map("######\n#@.!.#\n######\n");
+    rules.play(Item::PLAYER1, Direction::RIGHT);
+    let messages = rules.play(Item::PLAYER1, Direction::RIGHT);
+    assert_eq!(messages, vec![Message::game_over(1)]);
+    assert_eq!(rules.winner(), 1);
+}
+
+#[test]
+fn the_second_player_wins_in_case_of_a_tie() {
+    let rules = corridor();
+    assert_eq!(rules.winner(), 2);
+}
+
+#[test]
+fn nothing_happens_once_the_game_is_over() {
+    let (mut rules, _) = GameRules::from_map("#####\n#@!.#\n#####\n");
+    rules.play(Item::PLAYER1, Direction::RIGHT);
+    let before = rules.clone();
+    for dir in [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT] {
+        assert_eq!(rules.play(Item::PLAYER2, dir), vec![Message::game_over(2)]);
+    }
+    assert_eq!(rules, before);
+}
+
+#[test]
+fn the_map_messages_are_those_of_load_map() {
+    let (_, messages) = GameRules::from_map(CORRIDOR);
+    assert_eq!(messages, pas_cman_ipl::load_map(CORRIDOR));
+    assert!(messages.contains(&Message::spawn(PLAYER1_ID, Item::PLAYER1, pos(1, 1))));
+    assert!(messages.contains(&Message::spawn(PLAYER2_ID, Item::PLAYER2, pos(7, 1))));
+}
+
+#[test]
+fn only_the_players_have_a_position_and_a_score() {
+    let mut rules = corridor();
+    assert_eq!(rules.position(Item::FOOD), None);
+    assert_eq!(rules.score(Item::WALL), None);
+    assert!(rules.play(Item::SUPERFOOD, Direction::RIGHT).is_empty());
+}
+
+/// A corridor where the hero (player 1) is hunted by a villain (player 2)
+fn hunt(map: &str) -> GameRules {
+    GameRules::from_map(map).0.with_mode(Mode::Hunt)
+}
+
+#[test]
+fn in_a_hunt_the_villain_eats_nothing() {
+    let mut rules = hunt(CORRIDOR);
+    rules.play(Item::PLAYER2, Direction::LEFT);
+    rules.play(Item::PLAYER2, Direction::LEFT);
+    let messages = rules.play(Item::PLAYER2, Direction::LEFT);
+    assert_eq!(messages, vec![Message::movement(PLAYER2_ID, pos(4, 1))]);
+    assert_eq!(rules.item_at(pos(4, 1)), Item::SUPERFOOD);
+    assert_eq!(rules.score(Item::PLAYER2), Some(0));
+    assert!(!rules.is_frightened());
+}
+
+#[test]
+fn in_a_hunt_the_villain_wins_when_it_catches_the_hero() {
+    // even though the hero has the best score
+    let mut rules = hunt("######\n#@.!.#\n######\n");
+    rules.play(Item::PLAYER1, Direction::RIGHT);
+    let messages = rules.play(Item::PLAYER2, Direction::LEFT);
+    assert_eq!(messages, vec![Message::game_over(2)]);
+    assert!(rules.is_over());
+}
+
+#[test]
+fn in_a_hunt_the_hero_wins_when_all_the_food_is_eaten() {
+    let mut rules = hunt("#####\n#@.!#\n#####\n");
+    let messages = rules.play(Item::PLAYER1, Direction::RIGHT);
+    assert_eq!(messages.last(), Some(&Message::game_over(1)));
+}
+
+#[test]
+fn in_a_hunt_superfood_frightens_the_villain_which_can_then_be_eaten() {
+    let mut rules = hunt("########\n#@*.!..#\n########\n");
+    rules.play(Item::PLAYER1, Direction::RIGHT);
+    assert!(rules.is_frightened());
+    rules.play(Item::PLAYER2, Direction::LEFT);
+
+    // the villain goes back home and the hero takes its place
+    let messages = rules.play(Item::PLAYER1, Direction::RIGHT);
+    assert_eq!(messages, vec![
+        Message::movement(PLAYER2_ID, pos(4, 1)),
+        Message::movement(PLAYER1_ID, pos(3, 1)),
+        Message::eat_food(PLAYER1_ID, item_id(3, 1, Item::FOOD)),
+    ]);
+    assert_eq!(rules.position(Item::PLAYER2), Some(pos(4, 1)));
+    assert_eq!(rules.score(Item::PLAYER1), Some(SUPERFOOD_POINTS + VILLAIN_POINTS + FOOD_POINTS));
+    assert!(!rules.is_frightened());
+    assert!(!rules.is_over());
+}
+
+#[test]
+fn in_a_hunt_a_calm_villain_cannot_be_eaten() {
+    let mut rules = hunt("#######\n#@*!..#\n#######\n");
+    rules.play(Item::PLAYER1, Direction::RIGHT);
+    rules.calm();
+    let messages = rules.play(Item::PLAYER1, Direction::RIGHT);
+    assert_eq!(messages, vec![Message::game_over(2)]);
+}
@@ -0,0 +1,162 @@
+//! Compiling and playing the scripted scenarios.
+
+use std::{fs, path::{Path, PathBuf}, time::Duration};
+
+use pas_cman_ipl::{
+    item_id, item_id_with_size, load_map, load_map_with_size, player_ids,
+    pascman_protocol::{Direction, Item, Message, Position, HEIGHT, PLAYER1_ID, PLAYER2_ID, WIDTH},
+    rules::GameRules,
+    scenario::{compile, compile_file, compile_with_size, parse_duration, play, ScenarioError, Step},
+};
+
+/// A small corridor: player 1 on the left, player 2 on the right and some
+/// food in between.
+const CORRIDOR: &str = "\
+########
+#@.. .!#
+########
+";
+
+/// Writes the files in a directory of their own and returns that directory
+fn directory(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pas-cman-scenario-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for (file, text) in files {
+        fs::write(dir.join(file), text).unwrap();
+    }
+    dir
+}
+
+fn messages(steps: &[Step]) -> Vec<Message> {
+    steps.iter().map(|step| step.message).collect()
+}
+
+fn error(text: &str) -> ScenarioError {
+    compile(text, Path::new(".")).unwrap_err()
+}
+
+#[test]
+fn the_raw_commands_are_sent_as_is() {
+    let steps = compile("\
+# a comment, then an empty line
+
+register 2
+spawn 12 FOOD 3 4   # a trailing comment
+movement p1 3 4
+eat p1 12
+game_over 1
+", Path::new(".")).unwrap();
+
+    assert_eq!(messages(&steps), vec![
+        Message::registration(2),
+        Message::spawn(12, Item::FOOD, Position { x: 3, y: 4 }),
+        Message::movement(PLAYER1_ID, Position { x: 3, y: 4 }),
+        Message::eat_food(PLAYER1_ID, 12),
+        Message::game_over(1),
+    ]);
+    assert!(steps.iter().all(|step| step.delay == Duration::ZERO));
+}
+
+#[test]
+fn the_waits_delay_the_next_message_only() {
+    let steps = compile("wait 1s\nwait 500ms\nregister 1\nregister 2\n", Path::new(".")).unwrap();
+    assert_eq!(steps[0].delay, Duration::from_millis(1_500));
+    assert_eq!(steps[1].delay, Duration::ZERO);
+}
+
+#[test]
+fn loading_a_map_sends_the_messages_of_load_map() {
+    let dir = directory("load", &[("map.txt", CORRIDOR)]);
+    let steps = compile("load map.txt\n", &dir).unwrap();
+    assert_eq!(messages(&steps), load_map(CORRIDOR));
+}
+
+#[test]
+fn the_ids_depend_on_the_size_of_the_map() {
+    let dir = directory("size", &[("map.txt", CORRIDOR)]);
+    let steps = compile_with_size("load map.txt\nmove p1 right\neat p2 12\n", &dir, 40, 25).unwrap();
+    let [player1, player2] = player_ids(40, 25);
+
+    let moves = &steps[load_map_with_size(CORRIDOR, 40, 25).len()..];
+    assert_eq!(messages(moves), vec![
+        Message::movement(player1, Position { x: 2, y: 1 }),
+        Message::eat_food(player1, item_id_with_size(40, 25, 2, 1, Item::FOOD)),
+        Message::eat_food(player2, 12),
+    ]);
+}
+
+#[test]
+fn the_maps_are_found_next_to_the_scenario_file() {
+    let dir = directory("file", &[("map.txt", CORRIDOR), ("game.scenario", "load map.txt\nregister 1\n")]);
+    let steps = compile_file(dir.join("game.scenario"), WIDTH, HEIGHT).unwrap();
+    assert_eq!(steps.len(), load_map(CORRIDOR).len() + 1);
+    assert_eq!(steps.last().unwrap().message, Message::registration(1));
+}
+
+#[test]
+fn the_moves_follow_the_rules_of_the_game() {
+    let dir = directory("rules", &[("map.txt", CORRIDOR)]);
+    let steps = compile("load map.txt\nmove p1 right x2\nmove p2 up\n", &dir).unwrap();
+
+    let mut rules = GameRules::new(&load_map(CORRIDOR));
+    let mut expected = load_map(CORRIDOR);
+    expected.extend(rules.play(Item::PLAYER1, Direction::RIGHT));
+    expected.extend(rules.play(Item::PLAYER1, Direction::RIGHT));
+    expected.extend(rules.play(Item::PLAYER2, Direction::UP));
+    assert_eq!(messages(&steps), expected);
+
+    // going into a wall yields nothing, the others eat the food on their way
+    let moves = &steps[load_map(CORRIDOR).len()..];
+    assert_eq!(messages(moves), vec![
+        Message::movement(PLAYER1_ID, Position { x: 2, y: 1 }),
+        Message::eat_food(PLAYER1_ID, item_id(2, 1, Item::FOOD)),
+        Message::movement(PLAYER1_ID, Position { x: 3, y: 1 }),
+        Message::eat_food(PLAYER1_ID, item_id(3, 1, Item::FOOD)),
+    ]);
+}
+
+#[test]
+fn each_step_of_a_move_waits_for_the_pace() {
+    let dir = directory("pace", &[("map.txt", CORRIDOR)]);
+    let steps = compile("load map.txt\npace 100ms\nwait 1s\nmove p2 left x2\n", &dir).unwrap();
+    let moves = &steps[load_map(CORRIDOR).len()..];
+    assert_eq!(messages(moves), vec![
+        Message::movement(PLAYER2_ID, Position { x: 5, y: 1 }),
+        Message::eat_food(PLAYER2_ID, item_id(5, 1, Item::FOOD)),
+        Message::movement(PLAYER2_ID, Position { x: 4, y: 1 }),
+    ]);
+    let delays = moves.iter().map(|step| step.delay.as_millis()).collect::<Vec<_>>();
+    assert_eq!(delays, vec![1_100, 0, 100]);
+}
+
+#[test]
+fn the_steps_are_played_in_order() {
+    let steps = compile("register 1\nwait 10ms\ngame_over 2\n", Path::new(".")).unwrap();
+    let mut sent = vec![];
+    play(&steps, |message| sent.push(message));
+    assert_eq!(sent, vec![Message::registration(1), Message::game_over(2)]);
+}
+
+#[test]
+fn malformed_scenarios_tell_where_the_error_is() {
+    assert_eq!(error("register 1\nfly p1\n").line, 2);
+    assert!(error("fly p1").reason.contains("unknown command"));
+    assert!(error("register 1 2").reason.contains("wrong number of arguments"));
+    assert!(error("register one").reason.contains("expected a number, got 'one'"));
+    assert!(error("spawn 12 CHERRY 3 4").reason.contains("unknown item 'CHERRY'"));
+    assert!(error("wait 3 minutes").reason.contains("unknown command"));
+    assert!(error("wait soon").reason.contains("expected a duration"));
+    assert!(error("move p3 left").reason.contains("expected a player"));
+    assert!(error("move p1 sideways").reason.contains("expected a direction"));
+    assert!(error("move p1 left 4").reason.contains("expected a repetition"));
+    assert!(error("move p1 left").reason.contains("a map must be loaded"));
+    assert!(error("load no-such-map.txt").reason.contains("cannot load 'no-such-map.txt'"));
+}
+
+#[test]
+fn durations_are_given_in_seconds_or_milliseconds() {
+    assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+    assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1_500)));
+    assert!(parse_duration("-1s").is_err());
+    assert!(parse_duration("1h").is_err());
+}
@@ -0,0 +1,222 @@
+//! Validating the inbound protocol stream.
+
+use pas_cman_ipl::{
+    pascman_protocol::{Item, Message, Position, PLAYER1_ID, PLAYER2_ID},
+    validator::{ValidationMode, Validator},
+};
+
+const MODES: [ValidationMode; 2] = [ValidationMode::Lenient, ValidationMode::Strict];
+
+/// The ids of the food and superfood
+const FOOD: u32 = 100;
+const SUPERFOOD: u32 = 101;
+
+fn at(x: u32, y: u32) -> Position {
+    Position { x, y }
+}
+
+/// A corridor `#@.!.#` on the second line of the map, with player 1 on the
+/// left and player 2 on the right
+fn map() -> Vec<Message> {
+    vec![
+        Message::spawn(30, Item::WALL, at(0, 1)),
+        Message::spawn(31, Item::FLOOR, at(1, 1)),
+        Message::spawn(32, Item::FLOOR, at(2, 1)),
+        Message::spawn(33, Item::FLOOR, at(3, 1)),
+        Message::spawn(34, Item::FLOOR, at(4, 1)),
+        Message::spawn(35, Item::WALL, at(5, 1)),
+        Message::spawn(FOOD, Item::FOOD, at(2, 1)),
+        Message::spawn(SUPERFOOD, Item::SUPERFOOD, at(3, 1)),
+        Message::spawn(PLAYER1_ID, Item::PLAYER1, at(1, 1)),
+        Message::spawn(PLAYER2_ID, Item::PLAYER2, at(4, 1)),
+    ]
+}
+
+/// A validator which has checked the map and the registration of player 1
+fn ready(mode: ValidationMode) -> Validator {
+    let mut validator = Validator::silent(mode);
+    for message in map().iter().chain([&Message::registration(1)]) {
+        assert!(validator.check(message), "{message}");
+    }
+    assert!(validator.violations().is_empty());
+    validator
+}
+
+/// Checks the message (after the given ones) in both modes. Returns whether
+/// it has been accepted in lenient and in strict mode along with the reasons
+/// why it violates the protocol (which do not depend on the mode).
+fn verdict(validator: fn(ValidationMode) -> Validator, before: &[Message], message: Message) -> (bool, bool, Vec<String>) {
+    let mut accepted = vec![];
+    let mut reasons  = vec![];
+    for mode in MODES {
+        let mut validator = validator(mode);
+        before.iter().for_each(|m| { validator.check(m); });
+        let known = validator.violations().len();
+        accepted.push(validator.check(&message));
+        reasons = validator.violations()[known..].iter().map(|v| v.reason.clone()).collect();
+    }
+    (accepted[0], accepted[1], reasons)
+}
+
+fn fresh(mode: ValidationMode) -> Validator {
+    Validator::silent(mode)
+}
+
+/// Asserts that the message is reported (with the given reason) and that it is
+/// rejected in strict mode but still processed in lenient mode
+fn assert_reported(validator: fn(ValidationMode) -> Validator, before: &[Message], message: Message, reason: &str) {
+    let (lenient, strict, reasons) = verdict(validator, before, message);
+    assert!(reasons.iter().any(|r| r == reason), "{reasons:?} does not contain '{reason}'");
+    assert!(lenient, "{message} should be processed in lenient mode");
+    assert!(!strict, "{message} should be rejected in strict mode");
+}
+
+/// Asserts that the message is reported (with the given reason) and rejected in both modes
+fn assert_rejected(validator: fn(ValidationMode) -> Validator, before: &[Message], message: Message, reason: &str) {
+    let (lenient, strict, reasons) = verdict(validator, before, message);
+    assert!(reasons.iter().any(|r| r == reason), "{reasons:?} does not contain '{reason}'");
+    assert!(!lenient && !strict, "{message} should be rejected in both modes");
+}
+
+#[test]
+fn a_conforming_stream_is_accepted_in_both_modes() {
+    for mode in MODES {
+        let mut validator = ready(mode);
+        let game = [
+            Message::movement(PLAYER1_ID, at(2, 1)),
+            Message::eat_food(PLAYER1_ID, FOOD),
+            Message::movement(PLAYER2_ID, at(3, 1)),
+            Message::eat_food(PLAYER2_ID, SUPERFOOD),
+            Message::game_over(2),
+            Message::game_over(2),
+        ];
+        for message in game {
+            assert!(validator.check(&message), "{message}");
+        }
+        assert!(validator.violations().is_empty(), "{:?}", validator.violations());
+    }
+}
+
+#[test]
+fn the_map_is_sent_before_the_registration() {
+    assert_reported(fresh, &[], Message::registration(1), "registration received before the map");
+}
+
+#[test]
+fn the_game_is_not_played_before_the_map() {
+    let registered = [Message::registration(1)];
+    assert_reported(fresh, &registered, Message::movement(PLAYER1_ID, at(2, 1)), "movement received before the map");
+    assert_rejected(fresh, &registered, Message::eat_food(PLAYER1_ID, FOOD), "food eaten before the map");
+}
+
+#[test]
+fn the_registration_is_only_sent_once() {
+    assert_reported(ready, &[], Message::registration(2), "registration received twice");
+}
+
+#[test]
+fn the_positions_lie_on_the_map() {
+    assert_rejected(ready, &[], Message::spawn(200, Item::FOOD, at(99, 99)), "position (99,99) lies outside of the map");
+    assert_rejected(ready, &[], Message::movement(PLAYER1_ID, at(1, 20)), "position (1,20) lies outside of the map");
+}
+
+#[test]
+fn the_positions_are_checked_against_the_size_of_the_map() {
+    let mut validator = Validator::with_size(ValidationMode::Strict, 40, 25);
+    assert!(validator.check(&Message::spawn(1, Item::FLOOR, at(39, 24))));
+    assert!(!validator.check(&Message::spawn(2, Item::FLOOR, at(40, 24))));
+}
+
+#[test]
+fn the_ids_are_unique() {
+    assert_reported(ready, &[], Message::spawn(PLAYER1_ID, Item::FOOD, at(2, 1)), "id 1800 is already used by the PLAYER1 at (1,1)");
+    assert_reported(ready, &[], Message::spawn(31, Item::FOOD, at(2, 1)), "id 31 is already used by the tile at (1,1)");
+    assert_reported(ready, &[], Message::spawn(31, Item::FLOOR, at(2, 1)), "id 31 is already used by the tile at (1,1)");
+}
+
+#[test]
+fn a_tile_may_be_spawned_again_at_its_own_position() {
+    let (lenient, strict, reasons) = verdict(ready, &[], Message::spawn(30, Item::FLOOR, at(0, 1)));
+    assert!(lenient && strict);
+    assert!(reasons.is_empty(), "{reasons:?}");
+}
+
+#[test]
+fn only_the_known_players_move() {
+    assert_reported(ready, &[], Message::movement(999, at(2, 1)), "unknown id 999");
+    assert_reported(ready, &[], Message::movement(FOOD, at(1, 1)), "a FOOD cannot move");
+}
+
+#[test]
+fn the_players_move_after_the_registration() {
+    let map = map();
+    assert_reported(fresh, &map, Message::movement(PLAYER1_ID, at(2, 1)), "movement received before the registration");
+}
+
+#[test]
+fn the_players_move_one_tile_at_a_time() {
+    assert_reported(ready, &[], Message::movement(PLAYER1_ID, at(3, 1)), "moves from (1,1) to (3,1): more than one tile at a time");
+}
+
+#[test]
+fn the_players_only_move_onto_the_floor() {
+    assert_reported(ready, &[], Message::movement(PLAYER1_ID, at(0, 1)), "moves into the wall at (0,1)");
+    assert_reported(ready, &[], Message::movement(PLAYER1_ID, at(1, 0)), "moves onto (1,0) which is not known to be floor");
+}
+
+#[test]
+fn only_a_player_eats() {
+    assert_reported(ready, &[], Message::eat_food(999, FOOD), "unknown eater 999");
+    assert_reported(ready, &[], Message::eat_food(SUPERFOOD, FOOD), "a SUPERFOOD cannot eat");
+}
+
+#[test]
+fn the_players_eat_where_they_stand() {
+    assert_reported(ready, &[], Message::eat_food(PLAYER1_ID, FOOD), "the eater at (1,1) is not on the food at (2,1)");
+}
+
+#[test]
+fn what_is_not_food_is_never_eaten() {
+    assert_rejected(ready, &[], Message::eat_food(PLAYER1_ID, 999), "unknown food 999");
+    assert_rejected(ready, &[], Message::eat_food(PLAYER1_ID, PLAYER2_ID), "a PLAYER2 cannot be eaten");
+
+    // the player which was supposedly eaten is still there
+    let mut validator = ready(ValidationMode::Lenient);
+    validator.check(&Message::eat_food(PLAYER1_ID, PLAYER2_ID));
+    assert!(validator.check(&Message::movement(PLAYER2_ID, at(3, 1))));
+    assert_eq!(validator.violations().len(), 1);
+}
+
+#[test]
+fn the_winner_is_a_player() {
+    assert_reported(ready, &[], Message::game_over(0), "invalid winner 0");
+    assert_reported(ready, &[], Message::game_over(3), "invalid winner 3");
+}
+
+#[test]
+fn nothing_but_game_over_is_sent_once_the_game_is_over() {
+    let over = [Message::game_over(1)];
+    assert_reported(ready, &over, Message::movement(PLAYER1_ID, at(2, 1)), "message received after the game is over");
+    let (lenient, strict, reasons) = verdict(ready, &over, Message::game_over(1));
+    assert!(lenient && strict && reasons.is_empty());
+}
+
+#[test]
+fn the_violations_tell_which_message_was_faulty() {
+    let mut validator = ready(ValidationMode::Strict);
+    validator.check(&Message::movement(PLAYER1_ID, at(0, 1)));
+    let violation = &validator.violations()[0];
+    assert_eq!(violation.index, map().len() + 1);
+    assert_eq!(violation.message, Message::movement(PLAYER1_ID, at(0, 1)));
+}
+
+#[test]
+fn a_reset_forgets_everything() {
+    let mut validator = ready(ValidationMode::Strict);
+    validator.check(&Message::registration(1));
+    validator.reset();
+    assert!(validator.violations().is_empty());
+    assert!(!validator.check(&Message::registration(1)));
+    assert_eq!(validator.violations()[0].reason, "registration received before the map");
+    assert_eq!(validator.mode(), ValidationMode::Strict);
+}
@@ -0,0 +1,86 @@
+//! Choosing the glyphs of the walls after their neighbours.
+
+use pas_cman_ipl::{walls::{WallStyle, Walls, BLOCK}, Map, Position, TileType};
+
+fn map(rows: &[&str]) -> Map {
+    let mut map = Map::new(rows[0].len(), rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            if c == '#' {
+                map.tiles[y * map.width + x] = TileType::Wall;
+            }
+        }
+    }
+    map
+}
+
+fn draw(walls: &Walls, map: &Map) -> Vec<String> {
+    (0..map.height)
+        .map(|y| (0..map.width).map(|x| walls.get(Position { x, y }).unwrap_or(' ')).collect())
+        .collect()
+}
+
+#[test]
+fn walls_are_drawn_as_corners_edges_junctions_and_end_caps() {
+    let map = map(&[
+        "#####",
+        "#.#.#",
+        "#...#",
+        "##..#",
+        "#####",
+    ]);
+    assert_eq!(draw(&Walls::new(&map), &map), [
+        "╔═╦═╗",
+        "║ ╨ ║",
+        "║   ║",
+        "╠╗  ║",
+        "╚╩══╝",
+    ]);
+}
+
+#[test]
+fn thick_walls_are_drawn_as_their_outline() {
+    let map = map(&[
+        ".....",
+        ".###.",
+        ".###.",
+        ".###.",
+        ".....",
+    ]);
+    assert_eq!(draw(&Walls::new(&map), &map), [
+        "     ",
+        " ╔═╗ ",
+        " ║█║ ",
+        " ╚═╝ ",
+        "     ",
+    ]);
+}
+
+#[test]
+fn only_the_neighbours_of_a_changed_tile_are_updated() {
+    let mut map = map(&[
+        "#.#",
+    ]);
+    let mut walls = Walls::new(&map);
+    assert_eq!(draw(&walls, &map), ["■ ■"]);
+
+    map.tiles[1] = TileType::Wall;
+    walls.update(&map, Position { x: 1, y: 0 });
+    assert_eq!(draw(&walls, &map), ["╞═╡"]);
+    assert_eq!(walls, Walls::new(&map));
+}
+
+#[test]
+fn the_walls_can_be_drawn_as_blocks() {
+    let map = map(&[
+        "###",
+        "#.#",
+    ]);
+    let walls = Walls::new(&map);
+    let glyph = |x, y, style| walls.glyph(Position { x, y }, style);
+
+    assert_eq!(glyph(0, 0, WallStyle::Lines), Some('╔'));
+    assert_eq!(glyph(0, 0, WallStyle::Blocks), Some(BLOCK));
+    assert_eq!(glyph(2, 1, WallStyle::Blocks), Some('0'));
+    assert_eq!(glyph(1, 1, WallStyle::Blocks), None);
+}